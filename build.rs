@@ -4,13 +4,28 @@ fn main() {
      
     // Use shaderc to build the shaders.
     let mut compiler = shaderc::Compiler::new().unwrap();
+
+    // Shaders can #include shared code, like "pbr_lighting.glsl", from the
+    // shader folder.
+    let mut options = shaderc::CompileOptions::new().unwrap();
+    options.set_include_callback(|requested, _include_type, _requesting, _depth| {
+        let path = std::path::Path::new("res/shader").join(requested);
+
+        std::fs::read_to_string(&path)
+            .map(|content| shaderc::ResolvedInclude {
+                resolved_name: path.to_string_lossy().into_owned(),
+                content,
+            })
+            .map_err(|error| format!("Couldn't include {:?}: {}", path, error))
+    });
     
     for entry in std::fs::read_dir("res/shader").unwrap() {
         let path = entry.unwrap().path();
 
         let file_name = &path.file_name().unwrap().to_str().unwrap();
 
-        if !file_name.ends_with(".spv") {
+        // Included files and compiled shaders are skipped.
+        if file_name.ends_with(".frag") || file_name.ends_with(".vert") {
             let folder = &path.parent().unwrap();
             let source = std::fs::read_to_string(&path).unwrap();
 
@@ -21,7 +36,7 @@ fn main() {
                         shaderc::ShaderKind::Fragment,
                         file_name,
                         "main",
-                        None
+                        Some(&options)
                     ).unwrap()
                 } else {
                     compiler.compile_into_spirv(
@@ -29,7 +44,7 @@ fn main() {
                         shaderc::ShaderKind::Vertex,
                         file_name,
                         "main",
-                        None
+                        Some(&options)
                     ).unwrap()
                 }
             };
@@ -0,0 +1,12 @@
+#version 450
+
+layout(location=0) out vec2 v_texcoord;
+
+void main() {
+    // A triangle covering the whole target.
+    vec2 position = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2)) * 2.0 - 1.0;
+
+    v_texcoord = vec2(position.x + 1.0, 1.0 - position.y) * 0.5;
+
+    gl_Position = vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,25 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec4 a_color;
+layout(location=2) in float a_overlay;
+
+layout(location=0) out vec4 v_color;
+
+layout(set=0, binding=0)
+uniform CameraUniform {
+    mat4 u_view_mat;
+    mat4 u_proj_mat;
+};
+
+void main() {
+    v_color = a_color;
+
+    gl_Position = u_proj_mat * u_view_mat * vec4(a_position, 1.0);
+
+    // Lines drawn without depth testing sit on the near plane, so they pass
+    // against anything already drawn.
+    if (a_overlay > 0.5) {
+        gl_Position.z = 0.0;
+    }
+}
@@ -0,0 +1,46 @@
+#version 450
+
+layout(location=0) in vec2 v_texcoord;
+
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_panorama;
+layout(set = 0, binding = 1) uniform sampler s_panorama;
+
+layout(set = 0, binding = 2) uniform Face {
+    uint u_face;
+};
+
+const float PI = 3.14159265359;
+
+// The direction through a point on a cube face, with faces in the order
+// +X, -X, +Y, -Y, +Z, -Z.
+vec3 face_direction(uint face, vec2 uv) {
+    vec2 st = uv * 2.0 - 1.0;
+
+    if (face == 0u) {
+        return vec3(1.0, -st.y, -st.x);
+    } else if (face == 1u) {
+        return vec3(-1.0, -st.y, st.x);
+    } else if (face == 2u) {
+        return vec3(st.x, 1.0, st.y);
+    } else if (face == 3u) {
+        return vec3(st.x, -1.0, -st.y);
+    } else if (face == 4u) {
+        return vec3(st.x, -st.y, 1.0);
+    }
+
+    return vec3(-st.x, -st.y, -1.0);
+}
+
+void main() {
+    vec3 direction = normalize(face_direction(u_face, v_texcoord));
+
+    // The middle of the panorama faces -Z.
+    vec2 uv = vec2(
+        0.5 + atan(direction.x, -direction.z) / (2.0 * PI),
+        acos(clamp(direction.y, -1.0, 1.0)) / PI
+    );
+
+    f_color = vec4(textureLod(sampler2D(t_panorama, s_panorama), uv, 0.0).rgb, 1.0);
+}
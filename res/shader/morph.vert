@@ -0,0 +1,58 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec3 a_normal;
+layout(location=2) in vec2 a_texcoord;
+layout(location=3) in vec4 a_tangent;
+
+layout(location=0) out vec3 v_normal;
+layout(location=1) out vec2 v_texcoord;
+layout(location=2) out vec3 v_worldpos;
+layout(location=3) out vec3 v_campos;
+layout(location=4) out vec4 v_tangent;
+
+layout(set=0, binding=0)
+uniform CameraUniform {
+    mat4 u_view_mat;
+    mat4 u_proj_mat;
+};
+
+// A position and a normal delta per vertex, for each target in turn.
+layout(set=2, binding=0)
+readonly buffer MorphDeltas {
+    vec4 u_deltas[];
+};
+
+layout(set=2, binding=1)
+readonly buffer MorphWeights {
+    uint u_target_count;
+    uint u_vertex_count;
+    float u_weights[];
+};
+
+void main() { 
+    mat4 vp_mat =  u_proj_mat * u_view_mat;
+
+    vec3 position = a_position;
+    vec3 normal = a_normal;
+
+    for (uint i = 0; i < u_target_count; i++) {
+        float weight = u_weights[i];
+
+        if (weight != 0.0) {
+            uint delta = (i * u_vertex_count + uint(gl_VertexIndex)) * 2;
+
+            position += weight * u_deltas[delta].xyz;
+            normal += weight * u_deltas[delta + 1].xyz;
+        }
+    }
+
+    v_normal = normalize(normal);
+    v_texcoord = a_texcoord;
+    v_tangent = a_tangent;
+
+    v_worldpos = position;
+    v_campos = vec3(0, 0, 5);
+
+    gl_Position = vp_mat * vec4(position, 1.0);
+} 
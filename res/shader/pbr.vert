@@ -3,11 +3,13 @@
 layout(location=0) in vec3 a_position;
 layout(location=1) in vec3 a_normal;
 layout(location=2) in vec2 a_texcoord;
+layout(location=3) in vec4 a_tangent;
 
 layout(location=0) out vec3 v_normal;
 layout(location=1) out vec2 v_texcoord;
 layout(location=2) out vec3 v_worldpos;
 layout(location=3) out vec3 v_campos;
+layout(location=4) out vec4 v_tangent;
 
 layout(set=0, binding=0)
 uniform CameraUniform {
@@ -20,6 +22,7 @@ void main() {
     
     v_normal = a_normal;
     v_texcoord = a_texcoord;
+    v_tangent = a_tangent;
 
     vec4 pos = vp_mat * vec4(a_position, 1.0);
 
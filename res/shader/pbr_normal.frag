@@ -0,0 +1,33 @@
+#version 450
+#extension GL_GOOGLE_include_directive : require
+
+layout(location=0) in vec3 v_normal;
+layout(location=1) in vec2 v_texcoord;
+layout(location=2) in vec3 v_worldpos;
+layout(location=3) in vec3 v_campos;
+layout(location=4) in vec4 v_tangent;
+
+layout(location=0) out vec4 f_color;
+
+layout(set = 1, binding = 0) uniform texture2D t_diffuse;
+layout(set = 1, binding = 1) uniform sampler s_diffuse;
+
+layout(set = 2, binding = 0) uniform texture2D t_normal;
+layout(set = 2, binding = 1) uniform sampler s_normal;
+
+#include "pbr_lighting.glsl"
+
+void main() {
+    // Bring the normal map sample from tangent space into world space.
+    vec3 normal = normalize(v_normal);
+    vec3 tangent = normalize(v_tangent.xyz - normal * dot(normal, v_tangent.xyz));
+    vec3 bitangent = cross(normal, tangent) * v_tangent.w;
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    vec3 normal_sample = texture(sampler2D(t_normal, s_normal), v_texcoord).xyz * 2.0 - 1.0;
+
+    vec3 N = normalize(tbn * normal_sample);
+    vec3 V = normalize(v_campos - v_worldpos);
+
+    f_color = vec4(pbr_lighting(N, V, v_worldpos), 1.0);
+}
@@ -0,0 +1,46 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec3 a_normal;
+layout(location=2) in vec2 a_texcoord;
+layout(location=3) in vec4 a_tangent;
+layout(location=4) in uvec4 a_joints;
+layout(location=5) in vec4 a_weights;
+
+layout(location=0) out vec3 v_normal;
+layout(location=1) out vec2 v_texcoord;
+layout(location=2) out vec3 v_worldpos;
+layout(location=3) out vec3 v_campos;
+layout(location=4) out vec4 v_tangent;
+
+layout(set=0, binding=0)
+uniform CameraUniform {
+    mat4 u_view_mat;
+    mat4 u_proj_mat;
+};
+
+layout(set=2, binding=0)
+readonly buffer JointMatrices {
+    mat4 u_joint_mats[];
+};
+
+void main() { 
+    mat4 vp_mat =  u_proj_mat * u_view_mat;
+
+    mat4 skin_mat =
+        a_weights.x * u_joint_mats[a_joints.x] +
+        a_weights.y * u_joint_mats[a_joints.y] +
+        a_weights.z * u_joint_mats[a_joints.z] +
+        a_weights.w * u_joint_mats[a_joints.w];
+
+    vec4 skinned_pos = skin_mat * vec4(a_position, 1.0);
+
+    v_normal = normalize(mat3(skin_mat) * a_normal);
+    v_texcoord = a_texcoord;
+    v_tangent = vec4(normalize(mat3(skin_mat) * a_tangent.xyz), a_tangent.w);
+
+    v_worldpos = skinned_pos.xyz;
+    v_campos = vec3(0, 0, 5);
+
+    gl_Position = vp_mat * skinned_pos;
+} 
@@ -0,0 +1,12 @@
+#version 450
+
+layout(location=0) in vec3 v_direction;
+
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 1) uniform textureCube t_sky;
+layout(set = 0, binding = 2) uniform sampler s_sky;
+
+void main() {
+    f_color = vec4(texture(samplerCube(t_sky, s_sky), normalize(v_direction)).rgb, 1.0);
+}
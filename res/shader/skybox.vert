@@ -0,0 +1,19 @@
+#version 450
+
+layout(location=0) out vec3 v_direction;
+
+layout(set = 0, binding = 0) uniform Skybox {
+    // Takes a point on the far plane back to a direction from the camera.
+    mat4 u_inverse_view_proj;
+};
+
+void main() {
+    // A triangle covering the whole screen.
+    vec2 position = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2)) * 2.0 - 1.0;
+
+    vec4 direction = u_inverse_view_proj * vec4(position, 1.0, 1.0);
+    v_direction = direction.xyz / direction.w;
+
+    // On the far plane, so the sky is only drawn where nothing else was.
+    gl_Position = vec4(position, 1.0, 1.0);
+}
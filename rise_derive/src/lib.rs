@@ -0,0 +1,160 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta};
+
+/// Derives `rise::graphics::Vertex` for a `#[repr(C)]` struct.
+/// See the trait documentation for the supported field attributes.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct FieldOptions {
+    format: Option<Ident>,
+    location: Option<u32>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+
+    let is_repr_c = input.attrs.iter().any(|attr| {
+        attr.path.is_ident("repr")
+            && attr.parse_args::<Ident>().map(|repr| repr == "C").unwrap_or(false)
+    });
+
+    if !is_repr_c {
+        return Err(syn::Error::new_spanned(name, "Vertex types must be #[repr(C)]"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "Vertex types must have named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "Vertex can only be derived for structs")),
+    };
+
+    let mut attributes = Vec::new();
+    let mut preceding = Vec::new();
+    let mut next_location = 0u32;
+
+    for field in fields {
+        let ty = &field.ty;
+        let options = field_options(field)?;
+
+        let location = options.location.unwrap_or(next_location);
+        next_location = location + 1;
+
+        let format = match options.format {
+            Some(format) => quote!(rise::graphics::VertexFormat::#format),
+            None => quote!(<#ty as rise::graphics::VertexAttributeType>::FORMAT),
+        };
+
+        attributes.push(quote! {
+            rise::graphics::vertex_attribute(
+                (0 #(+ ::std::mem::size_of::<#preceding>())*) as u64,
+                #location,
+                #format,
+            )
+        });
+
+        preceding.push(ty.clone());
+    }
+
+    let field_named = |name: &str| fields.iter().find(|field| field.ident.as_ref().unwrap() == name);
+
+    let position = match field_named("position") {
+        Some(_) => quote! {
+            fn position(&self) -> ::cgmath::Vector3<f32> {
+                self.position.into()
+            }
+
+            fn set_position(&mut self, position: ::cgmath::Vector3<f32>) {
+                self.position = position.into();
+            }
+        },
+        None => return Err(syn::Error::new_spanned(name, "Vertex types must have a `position` field")),
+    };
+
+    let accessor = |field: &str, ty: proc_macro2::TokenStream| {
+        let getter = Ident::new(field, Span::call_site());
+        let setter = Ident::new(&format!("set_{}", field), Span::call_site());
+
+        match field_named(field) {
+            Some(_) => quote! {
+                fn #getter(&self) -> Option<#ty> {
+                    Some(self.#getter.into())
+                }
+
+                fn #setter(&mut self, #getter: #ty) {
+                    self.#getter = #getter.into();
+                }
+            },
+            None => quote!(),
+        }
+    };
+
+    let normal = accessor("normal", quote!(::cgmath::Vector3<f32>));
+    let uv = accessor("uv", quote!(::cgmath::Vector2<f32>));
+    let tangent = accessor("tangent", quote!(::cgmath::Vector4<f32>));
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics rise::graphics::Vertex for #name #ty_generics #where_clause {
+            const ATTRIBUTES: &'static [rise::graphics::VertexAttributeDescriptor] = &[
+                #(#attributes),*
+            ];
+
+            #position
+            #normal
+            #uv
+            #tangent
+        }
+
+        // Attribute offsets assume there is no padding between fields.
+        const _: [(); 1] = [(); (::std::mem::size_of::<#name>() == (0 #(+ ::std::mem::size_of::<#preceding>())*)) as usize];
+    })
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions {
+        format: None,
+        location: None,
+    };
+
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("vertex")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "Expected #[vertex(...)]")),
+        };
+
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("format") => {
+                    match &value.lit {
+                        Lit::Str(format) => options.format = Some(format.parse()?),
+                        lit => return Err(syn::Error::new_spanned(lit, "Expected a vertex format name")),
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("location") => {
+                    match &value.lit {
+                        Lit::Int(location) => options.location = Some(location.base10_parse()?),
+                        lit => return Err(syn::Error::new_spanned(lit, "Expected a shader location")),
+                    }
+                }
+                nested => return Err(syn::Error::new_spanned(nested, "Unknown vertex attribute option")),
+            }
+        }
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,295 @@
+use cgmath::{InnerSpace, Quaternion, Vector3, VectorSpace};
+
+use super::{AnimationError, Pose};
+
+/// How values are filled in between keyframes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Holds each keyframe's value until the next one.
+    Step,
+    Linear,
+    /// A cubic Hermite spline. Each keyframe has three values: the incoming
+    /// tangent, the value, and the outgoing tangent.
+    CubicSpline,
+}
+
+/// A value that can be keyframed.
+pub trait Keyframe: Copy {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self;
+
+    /// Evaluates the Hermite spline from `a` to `b`, where the tangents have
+    /// already been scaled by the time between the keyframes.
+    fn hermite(a: Self, a_out: Self, b: Self, b_in: Self, t: f32) -> Self;
+
+    fn scaled(self, factor: f32) -> Self;
+}
+
+impl Keyframe for f32 {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    fn hermite(a: Self, a_out: Self, b: Self, b_in: Self, t: f32) -> Self {
+        let (h00, h10, h01, h11) = hermite_basis(t);
+
+        a * h00 + a_out * h10 + b * h01 + b_in * h11
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor
+    }
+}
+
+impl Keyframe for Vector3<f32> {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(b, t)
+    }
+
+    fn hermite(a: Self, a_out: Self, b: Self, b_in: Self, t: f32) -> Self {
+        let (h00, h10, h01, h11) = hermite_basis(t);
+
+        a * h00 + a_out * h10 + b * h01 + b_in * h11
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor
+    }
+}
+
+impl Keyframe for Quaternion<f32> {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        super::skeleton::nlerp(a, b, t)
+    }
+
+    fn hermite(a: Self, a_out: Self, b: Self, b_in: Self, t: f32) -> Self {
+        let (h00, h10, h01, h11) = hermite_basis(t);
+
+        (a * h00 + a_out * h10 + b * h01 + b_in * h11).normalize()
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor
+    }
+}
+
+fn hermite_basis(t: f32) -> (f32, f32, f32, f32) {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (2. * t3 - 3. * t2 + 1., t3 - 2. * t2 + t, -2. * t3 + 3. * t2, t3 - t2)
+}
+
+/// The keyframes of one animated value.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    /// Keyframe times in seconds, in increasing order.
+    pub times: Vec<f32>,
+    /// One value per keyframe, or three for `CubicSpline`.
+    pub values: Vec<T>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Keyframe> Track<T> {
+    /// Makes a track, checking its keyframe times are finite and strictly
+    /// increasing, as glTF requires.
+    pub fn new(times: Vec<f32>, values: Vec<T>, interpolation: Interpolation) -> Result<Track<T>, AnimationError> {
+        if let Some(time) = times.iter().find(|time| !time.is_finite()) {
+            return Err(AnimationError::new(&format!("Keyframe time {} isn't finite", time)));
+        }
+
+        if let Some(pair) = times.windows(2).find(|pair| pair[1] <= pair[0]) {
+            return Err(AnimationError::new(&format!(
+                "Keyframe time {} follows {}, but times have to increase", pair[1], pair[0]
+            )));
+        }
+
+        Ok(Track {
+            times,
+            values,
+            interpolation,
+        })
+    }
+
+    /// The value at `time`. Times outside the keyframes hold the first or
+    /// last value, and there's no value at a NaN time.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let last = self.times.len().checked_sub(1)?;
+
+        if time.is_nan() {
+            return None;
+        }
+
+        let value = |key: usize| match self.interpolation {
+            Interpolation::CubicSpline => self.values.get(key * 3 + 1).copied(),
+            _ => self.values.get(key).copied(),
+        };
+
+        if time <= self.times[0] {
+            return value(0);
+        }
+
+        if time >= self.times[last] {
+            return value(last);
+        }
+
+        // The keyframe at or before `time`. The fields are public, so a NaN
+        // keyframe is ordered before everything rather than trusted away.
+        let key = match self
+            .times
+            .binary_search_by(|probe| probe.partial_cmp(&time).unwrap_or(std::cmp::Ordering::Less))
+        {
+            Ok(key) => return value(key),
+            Err(next) => next - 1,
+        };
+
+        let dt = self.times[key + 1] - self.times[key];
+        let t = if dt > 0. { (time - self.times[key]) / dt } else { 0. };
+
+        match self.interpolation {
+            Interpolation::Step => value(key),
+            Interpolation::Linear => Some(T::interpolate(value(key)?, value(key + 1)?, t)),
+            Interpolation::CubicSpline => {
+                let a_out = self.values.get(key * 3 + 2)?.scaled(dt);
+                let b_in = self.values.get((key + 1) * 3)?.scaled(dt);
+
+                Some(T::hermite(value(key)?, a_out, value(key + 1)?, b_in, t))
+            }
+        }
+    }
+
+    /// The time of the last keyframe.
+    pub fn end_time(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.)
+    }
+}
+
+/// The animated parts of one joint's transform. Anything without a track
+/// is left as it was in the pose being sampled into.
+#[derive(Clone, Debug)]
+pub struct JointTracks {
+    pub joint: usize,
+    pub translation: Option<Track<Vector3<f32>>>,
+    pub rotation: Option<Track<Quaternion<f32>>>,
+    pub scale: Option<Track<Vector3<f32>>>,
+}
+
+impl JointTracks {
+    pub fn new(joint: usize) -> JointTracks {
+        JointTracks {
+            joint,
+            translation: None,
+            rotation: None,
+            scale: None,
+        }
+    }
+
+    fn end_time(&self) -> f32 {
+        let translation = self.translation.as_ref().map_or(0., |track| track.end_time());
+        let rotation = self.rotation.as_ref().map_or(0., |track| track.end_time());
+        let scale = self.scale.as_ref().map_or(0., |track| track.end_time());
+
+        translation.max(rotation).max(scale)
+    }
+}
+
+/// The keyframed weight of one morph target.
+#[derive(Clone, Debug)]
+pub struct WeightTrack {
+    pub target: usize,
+    pub track: Track<f32>,
+}
+
+impl WeightTrack {
+    pub fn new(target: usize, track: Track<f32>) -> WeightTrack {
+        WeightTrack {
+            target,
+            track,
+        }
+    }
+}
+
+/// A named animation, made of keyframed tracks for some of a skeleton's
+/// joints and some of a mesh's morph target weights.
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    name: String,
+    duration: f32,
+    tracks: Vec<JointTracks>,
+    weight_tracks: Vec<WeightTrack>,
+}
+
+impl AnimationClip {
+    /// Makes a clip lasting until its last keyframe.
+    pub fn new(name: &str, tracks: Vec<JointTracks>) -> AnimationClip {
+        let duration = tracks.iter().map(|track| track.end_time()).fold(0., f32::max);
+
+        AnimationClip {
+            name: String::from(name),
+            duration,
+            tracks,
+            weight_tracks: Vec::new(),
+        }
+    }
+
+    /// Adds keyframed morph target weights to the clip, lengthening it if
+    /// they run past its end.
+    pub fn with_weight_tracks(mut self, weight_tracks: Vec<WeightTrack>) -> AnimationClip {
+        for weight_track in &weight_tracks {
+            self.duration = self.duration.max(weight_track.track.end_time());
+        }
+
+        self.weight_tracks.extend(weight_tracks);
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// The length of the clip in seconds.
+    pub fn get_duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn get_tracks(&self) -> &Vec<JointTracks> {
+        &self.tracks
+    }
+
+    pub fn get_weight_tracks(&self) -> &Vec<WeightTrack> {
+        &self.weight_tracks
+    }
+
+    /// Writes the clip's values at `time` into `pose`. Joints, parts of
+    /// transforms and weights the clip doesn't animate are left alone, so
+    /// start from the skeleton's rest pose to get a complete pose.
+    pub fn sample(&self, time: f32, pose: &mut Pose) {
+        for track in &self.tracks {
+            let transform = match pose.transforms.get_mut(track.joint) {
+                Some(transform) => transform,
+                None => continue,
+            };
+
+            if let Some(translation) = track.translation.as_ref().and_then(|t| t.sample(time)) {
+                transform.translation = translation;
+            }
+
+            if let Some(rotation) = track.rotation.as_ref().and_then(|t| t.sample(time)) {
+                transform.rotation = rotation;
+            }
+
+            if let Some(scale) = track.scale.as_ref().and_then(|t| t.sample(time)) {
+                transform.scale = scale;
+            }
+        }
+
+        for weight_track in &self.weight_tracks {
+            if let Some(weight) = weight_track.track.sample(time) {
+                if pose.weights.len() <= weight_track.target {
+                    pose.weights.resize(weight_track.target + 1, 0.);
+                }
+
+                pose.weights[weight_track.target] = weight;
+            }
+        }
+    }
+}
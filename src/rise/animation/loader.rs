@@ -0,0 +1,370 @@
+use cgmath::{Matrix4, Quaternion, SquareMatrix, Vector2, Vector3, Vector4};
+
+use std::collections::HashMap;
+
+use crate::graphics::{Mesh, MorphTarget, SkinnedVertex, Submesh};
+
+use super::{AnimationClip, AnimationError, Interpolation, Joint, JointTracks, JointTransform, Skeleton, Track, WeightTrack};
+
+/// A skinned mesh together with the skeleton it's bound to and the
+/// animations made for it.
+pub struct SkinnedModel {
+    pub mesh: Mesh<SkinnedVertex>,
+    pub skeleton: Skeleton,
+    pub clips: Vec<AnimationClip>,
+}
+
+impl SkinnedModel {
+    /// Loads the first skinned mesh in a glTF file, along with its skin, its
+    /// morph targets and every animation that moves its joints or morph
+    /// weights. The mesh's primitives are merged into one mesh, each as a
+    /// submesh. Missing normals are generated, and missing tangents are
+    /// generated when the mesh has texture coordinates.
+    pub fn load_from_file<P: AsRef<std::path::Path> + core::fmt::Debug>(path: P) -> Result<SkinnedModel, Box<dyn std::error::Error>> {
+        let (document, buffers, _images) = gltf::import(&path)?;
+
+        let node = document
+            .nodes()
+            .find(|node| node.mesh().is_some() && node.skin().is_some())
+            .ok_or_else(|| AnimationError::new(&format!("{:?} contains no skinned meshes", path)))?;
+
+        let skin = node.skin().unwrap();
+        let gltf_mesh = node.mesh().unwrap();
+
+        let get_buffer = |buffer: gltf::Buffer| buffers.get(buffer.index()).map(|data| &*data.0);
+
+        // Joints.
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+
+        for parent in document.nodes() {
+            for child in parent.children() {
+                parents.insert(child.index(), parent.index());
+            }
+        }
+
+        let joint_nodes: Vec<gltf::Node> = skin.joints().collect();
+        let joint_of: HashMap<usize, usize> = joint_nodes
+            .iter()
+            .enumerate()
+            .map(|(joint, node)| (node.index(), joint))
+            .collect();
+
+        let inverse_bind_matrices: Vec<Matrix4<f32>> = match skin.reader(get_buffer).read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(Matrix4::from).collect(),
+            None => vec![Matrix4::identity(); joint_nodes.len()],
+        };
+
+        let joints = joint_nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                // The nearest ancestor that's also a joint.
+                let mut parent = parents.get(&node.index());
+
+                while let Some(ancestor) = parent {
+                    if joint_of.contains_key(ancestor) {
+                        break;
+                    }
+
+                    parent = parents.get(ancestor);
+                }
+
+                Joint {
+                    name: node.name().map_or_else(|| format!("joint_{}", index), String::from),
+                    parent: parent.map(|ancestor| joint_of[ancestor]),
+                    inverse_bind_matrix: inverse_bind_matrices
+                        .get(index)
+                        .copied()
+                        .unwrap_or_else(Matrix4::identity),
+                    rest: joint_transform(node.transform()),
+                }
+            })
+            .collect();
+
+        let mut skeleton = Skeleton::new(joints)?;
+
+        // Nodes above the root joints, such as an armature object, still
+        // move the whole skeleton.
+        if let Some(root) = joint_nodes.iter().find(|node| {
+            parents.get(&node.index()).map_or(true, |parent| !joint_of.contains_key(parent))
+        }) {
+            let mut root_transform = Matrix4::identity();
+            let mut ancestor = parents.get(&root.index());
+
+            while let Some(index) = ancestor {
+                let node = document.nodes().nth(*index).unwrap();
+                root_transform = Matrix4::from(node.transform().matrix()) * root_transform;
+                ancestor = parents.get(index);
+            }
+
+            skeleton.set_root_transform(root_transform);
+        }
+
+        // Mesh.
+        let mut mesh: Mesh<SkinnedVertex> = Mesh::new();
+        let mut has_normals = true;
+        let mut has_uvs = true;
+        let mut has_tangents = true;
+
+        // Position and normal deltas for each morph target, across every
+        // primitive.
+        let mut target_deltas: Vec<(Vec<Vector3<f32>>, Vec<Vector3<f32>>)> = Vec::new();
+        let mut has_normal_deltas = true;
+
+        for primitive in gltf_mesh.primitives() {
+            let reader = primitive.reader(get_buffer);
+
+            let positions: Vec<[f32; 3]> = reader
+                .read_positions()
+                .ok_or_else(|| AnimationError::new(&format!("{:?} has a primitive with no positions", path)))?
+                .collect();
+
+            let count = positions.len();
+
+            let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(|normals| normals.collect());
+            let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0).map(|uvs| uvs.into_f32().collect());
+            let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|tangents| tangents.collect());
+
+            let joints: Vec<[u16; 4]> = reader
+                .read_joints(0)
+                .ok_or_else(|| AnimationError::new(&format!("{:?} has a skinned primitive with no joints", path)))?
+                .into_u16()
+                .collect();
+
+            let weights: Vec<[f32; 4]> = reader
+                .read_weights(0)
+                .ok_or_else(|| AnimationError::new(&format!("{:?} has a skinned primitive with no weights", path)))?
+                .into_f32()
+                .collect();
+
+            if joints.len() != count || weights.len() != count {
+                return Err(Box::new(AnimationError::new(&format!(
+                    "{:?} has a primitive with mismatched vertex attributes", path
+                ))));
+            }
+
+            has_normals &= normals.is_some();
+            has_uvs &= uvs.is_some();
+            has_tangents &= tangents.is_some();
+
+            let base = mesh.vertices.len() as u32;
+
+            let targets: Vec<_> = reader.read_morph_targets().collect();
+
+            if primitive.index() == 0 {
+                target_deltas.resize(targets.len(), (Vec::new(), Vec::new()));
+            } else if targets.len() != target_deltas.len() {
+                return Err(Box::new(AnimationError::new(&format!(
+                    "{:?} has primitives with different numbers of morph targets", path
+                ))));
+            }
+
+            for ((positions, normals, _tangents), deltas) in targets.into_iter().zip(target_deltas.iter_mut()) {
+                let zero = Vector3::new(0., 0., 0.);
+
+                match positions {
+                    Some(positions) => deltas.0.extend(positions.map(Vector3::from)),
+                    None => deltas.0.extend(std::iter::repeat(zero).take(count)),
+                }
+
+                match normals {
+                    Some(normals) => deltas.1.extend(normals.map(Vector3::from)),
+                    None => {
+                        has_normal_deltas = false;
+                        deltas.1.extend(std::iter::repeat(zero).take(count));
+                    }
+                }
+
+                if deltas.0.len() != base as usize + count || deltas.1.len() != base as usize + count {
+                    return Err(Box::new(AnimationError::new(&format!(
+                        "{:?} has a morph target with the wrong number of vertices", path
+                    ))));
+                }
+            }
+
+            for i in 0..count {
+                if let Some(joint) = joints[i].iter().find(|joint| **joint as usize >= joint_nodes.len()) {
+                    return Err(Box::new(AnimationError::new(&format!(
+                        "{:?} has a vertex bound to joint {}, but its skin only has {}", path, joint, joint_nodes.len()
+                    ))));
+                }
+
+                let weight = Vector4::from(weights[i]);
+                let total = weight.x + weight.y + weight.z + weight.w;
+
+                mesh.add_vertex(SkinnedVertex::new(
+                    Vector3::from(positions[i]),
+                    normals.as_ref().map_or(Vector3::new(0., 0., 0.), |normals| Vector3::from(normals[i])),
+                    uvs.as_ref().map_or(Vector2::new(0., 0.), |uvs| Vector2::from(uvs[i])),
+                    tangents.as_ref().map_or(Vector4::new(0., 0., 0., 1.), |tangents| Vector4::from(tangents[i])),
+                    joints[i],
+                    if total > 0. { weight / total } else { Vector4::new(1., 0., 0., 0.) },
+                ));
+            }
+
+            let first_index = mesh.indices.len() as u32;
+
+            match reader.read_indices() {
+                Some(indices) => mesh.indices.extend(indices.into_u32().map(|index| base + index)),
+                None => mesh.indices.extend(base..base + count as u32),
+            }
+
+            // Each primitive becomes a submesh named after its material.
+            let name = primitive.material().name().map_or_else(|| format!("primitive_{}", primitive.index()), String::from);
+            mesh.add_submesh(Submesh::new(&name, first_index..mesh.indices.len() as u32))?;
+        }
+
+        mesh.check_triangles()
+            .map_err(|e| AnimationError::new(&format!("{:?}: {}", path, e)))?;
+
+        // Added before generating normals and tangents, which split vertices
+        // and copy their deltas along with them.
+        for (index, (positions, normals)) in target_deltas.into_iter().enumerate() {
+            let normals = if has_normal_deltas { Some(normals) } else { None };
+
+            mesh.add_morph_target(MorphTarget::new(&format!("target_{}", index), positions, normals))?;
+        }
+
+        let default_weights: Vec<f32> = node.weights().or_else(|| gltf_mesh.weights()).map_or_else(Vec::new, |weights| weights.to_vec());
+        mesh.set_morph_weights(&default_weights);
+
+        if !has_normals {
+            mesh.compute_smooth_normals(cgmath::Deg(60.).into())?;
+        }
+
+        if has_uvs && !has_tangents {
+            mesh.generate_tangents()?;
+        }
+
+        // Animations.
+        let mut clips = Vec::new();
+
+        for animation in document.animations() {
+            let mut tracks: HashMap<usize, JointTracks> = HashMap::new();
+            let mut weight_tracks: Vec<WeightTrack> = Vec::new();
+
+            for channel in animation.channels() {
+                if channel.target().node().index() == node.index() {
+                    if let Some(gltf::animation::util::ReadOutputs::MorphTargetWeights(values)) = channel.reader(get_buffer).read_outputs() {
+                        let times: Vec<f32> = match channel.reader(get_buffer).read_inputs() {
+                            Some(inputs) => inputs.collect(),
+                            None => continue,
+                        };
+
+                        let interpolation = match channel.sampler().interpolation() {
+                            gltf::animation::Interpolation::Step => Interpolation::Step,
+                            gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                            gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                        };
+
+                        weight_tracks.extend(weight_tracks_from(times, values.into_f32().collect(), interpolation, mesh.get_morph_targets().len())?);
+                    }
+
+                    continue;
+                }
+
+                let joint = match joint_of.get(&channel.target().node().index()) {
+                    Some(joint) => *joint,
+                    None => continue,
+                };
+
+                let reader = channel.reader(get_buffer);
+
+                let times: Vec<f32> = match reader.read_inputs() {
+                    Some(inputs) => inputs.collect(),
+                    None => continue,
+                };
+
+                let interpolation = match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::Step => Interpolation::Step,
+                    gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                    gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                };
+
+                let joint_tracks = tracks.entry(joint).or_insert_with(|| JointTracks::new(joint));
+
+                match reader.read_outputs() {
+                    Some(gltf::animation::util::ReadOutputs::Translations(values)) => {
+                        joint_tracks.translation = Some(Track::new(
+                            times,
+                            values.map(Vector3::from).collect(),
+                            interpolation
+                        )?);
+                    }
+                    Some(gltf::animation::util::ReadOutputs::Rotations(values)) => {
+                        joint_tracks.rotation = Some(Track::new(
+                            times,
+                            values.into_f32().map(|[x, y, z, w]| Quaternion::new(w, x, y, z)).collect(),
+                            interpolation
+                        )?);
+                    }
+                    Some(gltf::animation::util::ReadOutputs::Scales(values)) => {
+                        joint_tracks.scale = Some(Track::new(
+                            times,
+                            values.map(Vector3::from).collect(),
+                            interpolation
+                        )?);
+                    }
+                    _ => {}
+                }
+            }
+
+            if tracks.is_empty() && weight_tracks.is_empty() {
+                continue;
+            }
+
+            let mut tracks: Vec<JointTracks> = tracks.into_iter().map(|(_, tracks)| tracks).collect();
+            tracks.sort_by_key(|tracks| tracks.joint);
+
+            let name = animation.name().map_or_else(|| format!("animation_{}", animation.index()), String::from);
+
+            clips.push(AnimationClip::new(&name, tracks).with_weight_tracks(weight_tracks));
+        }
+
+        Ok(SkinnedModel {
+            mesh,
+            skeleton,
+            clips,
+        })
+    }
+
+    pub fn find_clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.iter().find(|clip| clip.get_name() == name)
+    }
+}
+
+/// Splits a glTF weights channel, which holds every target's weight for a
+/// keyframe together (or, for cubic splines, every target's in tangent, then
+/// every value, then every out tangent), into one track per target.
+fn weight_tracks_from(
+    times: Vec<f32>,
+    values: Vec<f32>,
+    interpolation: Interpolation,
+    target_count: usize,
+) -> Result<Vec<WeightTrack>, AnimationError> {
+    let per_key = match interpolation {
+        Interpolation::CubicSpline => 3,
+        _ => 1,
+    };
+
+    if target_count == 0 || values.len() != times.len() * per_key * target_count {
+        return Ok(Vec::new());
+    }
+
+    (0..target_count)
+        .map(|target| {
+            let values = values
+                .chunks(target_count)
+                .map(|chunk| chunk[target])
+                .collect();
+
+            Ok(WeightTrack::new(target, Track::new(times.clone(), values, interpolation)?))
+        })
+        .collect()
+}
+
+fn joint_transform(transform: gltf::scene::Transform) -> JointTransform {
+    let (translation, [x, y, z, w], scale) = transform.decomposed();
+
+    JointTransform::new(Vector3::from(translation), Quaternion::new(w, x, y, z), Vector3::from(scale))
+}
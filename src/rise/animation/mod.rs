@@ -0,0 +1,15 @@
+mod skeleton;
+
+pub use skeleton::{*};
+
+mod clip;
+
+pub use clip::{*};
+
+mod player;
+
+pub use player::{*};
+
+mod loader;
+
+pub use loader::{*};
@@ -0,0 +1,257 @@
+use std::rc::Rc;
+
+use super::{AnimationClip, Pose, Skeleton};
+
+/// A clip being played, along with how far through it is.
+#[derive(Clone)]
+pub struct PlayingClip {
+    clip: Rc<AnimationClip>,
+    time: f32,
+    looping: bool,
+}
+
+impl PlayingClip {
+    pub fn new(clip: Rc<AnimationClip>, looping: bool) -> PlayingClip {
+        PlayingClip {
+            clip,
+            time: 0.,
+            looping,
+        }
+    }
+
+    pub fn get_clip(&self) -> &AnimationClip {
+        self.clip.as_ref()
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Whether a clip that doesn't loop has reached its end.
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.clip.get_duration()
+    }
+
+    fn advance(&mut self, delta: f32) {
+        let duration = self.clip.get_duration();
+
+        self.time += delta;
+
+        if self.looping && duration > 0. {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.max(0.).min(duration);
+        }
+    }
+
+    fn sample(&self, pose: &mut Pose) {
+        self.clip.sample(self.time, pose);
+    }
+}
+
+/// Plays clips on a skeleton, crossfading when switching between them.
+pub struct AnimationPlayer {
+    current: Option<PlayingClip>,
+    /// What's being faded out, which keeps playing until the fade is done.
+    /// It can still be fading between clips itself, when a crossfade starts
+    /// before the last one has finished.
+    previous: Option<Box<AnimationPlayer>>,
+    fade_duration: f32,
+    fade_time: f32,
+    speed: f32,
+}
+
+impl AnimationPlayer {
+    pub fn new() -> AnimationPlayer {
+        AnimationPlayer {
+            current: None,
+            previous: None,
+            fade_duration: 0.,
+            fade_time: 0.,
+            speed: 1.,
+        }
+    }
+
+    /// Switches straight to `clip`, from the start.
+    pub fn play(&mut self, clip: Rc<AnimationClip>, looping: bool) {
+        self.current = Some(PlayingClip::new(clip, looping));
+        self.previous = None;
+    }
+
+    /// Starts `clip` and blends over to it across `duration` seconds, while
+    /// the current clip keeps playing underneath. Starting a crossfade during
+    /// another one fades out from the blend as it is, so the pose doesn't
+    /// jump.
+    pub fn crossfade(&mut self, clip: Rc<AnimationClip>, looping: bool, duration: f32) {
+        if duration <= 0. || self.current.is_none() {
+            self.play(clip, looping);
+            return;
+        }
+
+        // The outgoing player's speed is already applied by this one.
+        self.previous = Some(Box::new(AnimationPlayer {
+            current: self.current.take(),
+            previous: self.previous.take(),
+            fade_duration: self.fade_duration,
+            fade_time: self.fade_time,
+            speed: 1.,
+        }));
+        self.current = Some(PlayingClip::new(clip, looping));
+        self.fade_duration = duration;
+        self.fade_time = 0.;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    /// Sets the playback rate, where 1 is normal speed.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn get_current(&self) -> Option<&PlayingClip> {
+        self.current.as_ref()
+    }
+
+    pub fn get_current_mut(&mut self) -> Option<&mut PlayingClip> {
+        self.current.as_mut()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current.as_ref().map_or(true, |current| current.is_finished())
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        let delta = delta * self.speed;
+
+        if let Some(current) = &mut self.current {
+            current.advance(delta);
+        }
+
+        if let Some(previous) = &mut self.previous {
+            previous.update(delta);
+
+            self.fade_time += delta.abs();
+
+            if self.fade_time >= self.fade_duration {
+                self.previous = None;
+            }
+        }
+    }
+
+    /// The skeleton's pose at the current point of playback. Joints no clip
+    /// animates stay in their rest pose.
+    pub fn sample(&self, skeleton: &Skeleton) -> Pose {
+        self.sample_from(&skeleton.rest_pose())
+    }
+
+    /// The pose at the current point of playback, starting from `base` for
+    /// anything the clips don't animate. Meshes with morph targets but no
+    /// skeleton can pass a pose with no joints and their resting weights.
+    pub fn sample_from(&self, base: &Pose) -> Pose {
+        let mut pose = base.clone();
+
+        if let Some(current) = &self.current {
+            current.sample(&mut pose);
+        }
+
+        match &self.previous {
+            Some(previous) => previous.sample_from(base).blend(&pose, self.fade_time / self.fade_duration),
+            None => pose,
+        }
+    }
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> AnimationPlayer {
+        AnimationPlayer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::AnimationPlayer;
+    use crate::animation::{AnimationClip, Interpolation, Pose, Track, WeightTrack};
+
+    /// A looping clip that holds the first morph weight at `weight`.
+    fn constant_clip(weight: f32) -> Rc<AnimationClip> {
+        let track = Track::new(vec![0., 10.], vec![weight, weight], Interpolation::Linear).unwrap();
+
+        Rc::new(AnimationClip::new("constant", Vec::new()).with_weight_tracks(vec![WeightTrack::new(0, track)]))
+    }
+
+    fn weight(player: &AnimationPlayer) -> f32 {
+        player.sample_from(&Pose::new(Vec::new(), vec![0.])).weights[0]
+    }
+
+    #[test]
+    fn crossfade_blends_between_clips() {
+        let mut player = AnimationPlayer::new();
+
+        player.play(constant_clip(0.), true);
+        player.crossfade(constant_clip(1.), true, 1.);
+        player.update(0.25);
+
+        assert!((weight(&player) - 0.25).abs() < 1e-5);
+
+        player.update(1.);
+
+        assert!((weight(&player) - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn crossfade_during_a_crossfade_starts_from_the_blended_pose() {
+        let mut player = AnimationPlayer::new();
+
+        player.play(constant_clip(0.), true);
+        player.crossfade(constant_clip(1.), true, 1.);
+        player.update(0.5);
+
+        let before = weight(&player);
+        player.crossfade(constant_clip(2.), true, 1.);
+
+        assert!((weight(&player) - before).abs() < 1e-5, "The pose jumped from {} to {}", before, weight(&player));
+
+        // The first fade finishes underneath, then the new one completes.
+        player.update(0.5);
+        assert!((weight(&player) - 1.5).abs() < 1e-5);
+
+        player.update(0.5);
+        assert!((weight(&player) - 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tracks_reject_times_that_arent_finite_or_increasing() {
+        let track = |times: Vec<f32>| Track::new(times, vec![0., 1., 2.], Interpolation::Linear);
+
+        assert!(track(vec![0., 1., 2.]).is_ok());
+        assert!(track(vec![0., f32::NAN, 2.]).is_err());
+        assert!(track(vec![0., 1., f32::INFINITY]).is_err());
+        assert!(track(vec![0., 2., 1.]).is_err());
+        assert!(track(vec![0., 1., 1.]).is_err());
+    }
+
+    #[test]
+    fn sampling_at_nan_or_with_nan_keyframes_doesnt_panic() {
+        let mut track = Track::new(vec![0., 1., 2.], vec![0., 1., 2.], Interpolation::Linear).unwrap();
+
+        assert_eq!(track.sample(f32::NAN), None);
+        assert_eq!(track.sample(1.5), Some(1.5));
+
+        // The fields are public, so a bad time can still get in.
+        track.times[1] = f32::NAN;
+        track.sample(1.5);
+    }
+}
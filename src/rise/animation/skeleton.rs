@@ -0,0 +1,257 @@
+use cgmath::{InnerSpace, Matrix4, Quaternion, SquareMatrix, Vector3, VectorSpace};
+
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct AnimationError {
+    message: String
+}
+
+impl AnimationError {
+    pub fn new(message: &str) -> AnimationError {
+        AnimationError {
+            message: String::from(message)
+        }
+    }
+}
+
+impl std::error::Error for AnimationError {}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error creating animation: {}", self.message)
+    }
+}
+
+/// The local transform of a joint relative to its parent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JointTransform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl JointTransform {
+    pub fn new(translation: Vector3<f32>, rotation: Quaternion<f32>, scale: Vector3<f32>) -> JointTransform {
+        JointTransform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> JointTransform {
+        JointTransform::new(
+            Vector3::new(0., 0., 0.),
+            Quaternion::new(1., 0., 0., 0.),
+            Vector3::new(1., 1., 1.)
+        )
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation.normalize())
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// Interpolates towards `other`, taking the shortest way round for the
+    /// rotation.
+    pub fn blend(&self, other: &JointTransform, weight: f32) -> JointTransform {
+        JointTransform {
+            translation: self.translation.lerp(other.translation, weight),
+            rotation: nlerp(self.rotation, other.rotation, weight),
+            scale: self.scale.lerp(other.scale, weight),
+        }
+    }
+}
+
+/// Normalized linear interpolation between rotations. It doesn't keep a
+/// constant speed like slerp, but it's cheaper and commutes when blending
+/// several poses together.
+pub(crate) fn nlerp(a: Quaternion<f32>, b: Quaternion<f32>, t: f32) -> Quaternion<f32> {
+    let b = if a.dot(b) < 0. { -b } else { b };
+
+    (a * (1. - t) + b * t).normalize()
+}
+
+/// A joint in a skeleton.
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub name: String,
+    /// The index of the parent joint, or `None` for a root.
+    pub parent: Option<usize>,
+    /// Takes a vertex from the mesh's space into the joint's space in the
+    /// bind pose.
+    pub inverse_bind_matrix: Matrix4<f32>,
+    /// The joint's transform when no animation is playing.
+    pub rest: JointTransform,
+}
+
+/// A transform for every joint of a skeleton, indexed the same way, along
+/// with the weights of a mesh's morph targets.
+#[derive(Clone, Debug)]
+pub struct Pose {
+    pub transforms: Vec<JointTransform>,
+    pub weights: Vec<f32>,
+}
+
+impl Pose {
+    pub fn new(transforms: Vec<JointTransform>, weights: Vec<f32>) -> Pose {
+        Pose {
+            transforms,
+            weights,
+        }
+    }
+
+    /// Interpolates every joint and morph weight towards `other`. A weight of
+    /// 0 gives this pose and 1 gives `other`.
+    pub fn blend(&self, other: &Pose, weight: f32) -> Pose {
+        let weight_count = self.weights.len().max(other.weights.len());
+
+        Pose {
+            transforms: self
+                .transforms
+                .iter()
+                .zip(&other.transforms)
+                .map(|(a, b)| a.blend(b, weight))
+                .collect(),
+            weights: (0..weight_count)
+                .map(|index| {
+                    let a = self.weights.get(index).copied().unwrap_or(0.);
+                    let b = other.weights.get(index).copied().unwrap_or(0.);
+
+                    a + (b - a) * weight
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A hierarchy of joints that a skinned mesh's vertices are bound to.
+pub struct Skeleton {
+    joints: Vec<Joint>,
+    /// The joints in an order where parents come before their children.
+    order: Vec<usize>,
+    root_transform: Matrix4<f32>,
+}
+
+impl Skeleton {
+    /// Makes a skeleton from its joints. The joints can be in any order, but
+    /// their indices are the ones skinned vertices refer to.
+    pub fn new(joints: Vec<Joint>) -> Result<Skeleton, AnimationError> {
+        for (index, joint) in joints.iter().enumerate() {
+            if let Some(parent) = joint.parent {
+                if parent >= joints.len() {
+                    return Err(AnimationError::new(&format!(
+                        "Joint {} has parent {}, but there are only {} joints", joint.name, parent, joints.len()
+                    )));
+                }
+
+                if parent == index {
+                    return Err(AnimationError::new(&format!("Joint {} is its own parent", joint.name)));
+                }
+            }
+        }
+
+        // Repeatedly take every joint whose parent has already been taken.
+        let mut order = Vec::with_capacity(joints.len());
+        let mut placed = vec![false; joints.len()];
+
+        while order.len() < joints.len() {
+            let before = order.len();
+
+            for (index, joint) in joints.iter().enumerate() {
+                if !placed[index] && joint.parent.map_or(true, |parent| placed[parent]) {
+                    placed[index] = true;
+                    order.push(index);
+                }
+            }
+
+            if order.len() == before {
+                return Err(AnimationError::new("Skeleton's joints form a cycle"));
+            }
+        }
+
+        Ok(Skeleton {
+            joints,
+            order,
+            root_transform: Matrix4::identity(),
+        })
+    }
+
+    pub fn get_joints(&self) -> &Vec<Joint> {
+        &self.joints
+    }
+
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    /// Sets a transform applied above the root joints, for when the skeleton
+    /// sits under other nodes in the file it came from.
+    pub fn set_root_transform(&mut self, root_transform: Matrix4<f32>) {
+        self.root_transform = root_transform;
+    }
+
+    pub fn get_root_transform(&self) -> Matrix4<f32> {
+        self.root_transform
+    }
+
+    pub fn rest_pose(&self) -> Pose {
+        Pose::new(self.joints.iter().map(|joint| joint.rest).collect(), Vec::new())
+    }
+
+    /// The transform of every joint in the skeleton's space. Joints the pose
+    /// has no transform for stay at their rest transform.
+    pub fn global_matrices(&self, pose: &Pose) -> Vec<Matrix4<f32>> {
+        let mut globals = vec![Matrix4::identity(); self.joints.len()];
+
+        for index in &self.order {
+            let parent = match self.joints[*index].parent {
+                Some(parent) => globals[parent],
+                None => self.root_transform,
+            };
+
+            let transform = pose.transforms.get(*index).unwrap_or(&self.joints[*index].rest);
+
+            globals[*index] = parent * transform.to_matrix();
+        }
+
+        globals
+    }
+
+    /// The matrices that move each vertex from the bind pose into `pose`, in
+    /// the order the joint buffer expects them.
+    pub fn joint_matrices(&self, pose: &Pose) -> Vec<Matrix4<f32>> {
+        self.global_matrices(pose)
+            .iter()
+            .zip(&self.joints)
+            .map(|(global, joint)| global * joint.inverse_bind_matrix)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix4, Quaternion, SquareMatrix, Vector3};
+
+    use super::{Joint, JointTransform, Pose, Skeleton};
+
+    #[test]
+    fn joints_missing_from_a_pose_stay_at_rest() {
+        let rest = JointTransform::new(Vector3::new(0., 1., 0.), Quaternion::new(1., 0., 0., 0.), Vector3::new(1., 1., 1.));
+
+        let joint = |name: &str, parent| Joint {
+            name: String::from(name),
+            parent,
+            inverse_bind_matrix: Matrix4::identity(),
+            rest,
+        };
+
+        let skeleton = Skeleton::new(vec![joint("root", None), joint("child", Some(0))]).unwrap();
+        let expected = skeleton.joint_matrices(&skeleton.rest_pose());
+
+        assert_eq!(skeleton.joint_matrices(&Pose::new(Vec::new(), Vec::new())), expected);
+        assert_eq!(skeleton.joint_matrices(&Pose::new(vec![rest], Vec::new())), expected);
+    }
+}
@@ -100,7 +100,7 @@ pub fn run_application<A: 'static + Application>() -> Result<(), Box<dyn std::er
                 let frame_time = Instant::now();
                 let delta = frame_time - last_frame;
                 last_frame = frame_time;
-                let delta_flt: f32 = delta.as_secs_f32();
+                let delta_flt: f32 = render_context.get_fixed_timestep().unwrap_or(delta.as_secs_f32());
 
                 app.update(delta_flt);
 
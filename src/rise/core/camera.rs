@@ -44,6 +44,32 @@ impl PerspectiveCamera {
 }
 
 impl PerspectiveCamera {
+    /// Moves the camera back along its view direction until `sphere` fits
+    /// inside both the vertical and horizontal field of view.
+    pub fn frame_bounds(&mut self, sphere: &crate::graphics::BoundingSphere) {
+        use cgmath::{InnerSpace, Rotation};
+
+        let half_fov_y = cgmath::Rad::from(cgmath::Deg(self.fov)).0 / 2.;
+        let half_fov_x = (half_fov_y.tan() * self.aspect_ratio).atan();
+
+        let distance = sphere.radius / half_fov_y.min(half_fov_x).sin();
+        // Transform rotations aren't kept normalized, and rotating by an
+        // unnormalized quaternion scales the vector too.
+        let forward = self.transform.rotation.normalize().rotate_vector(cgmath::Vector3::new(0., 0., -1.));
+
+        self.transform.position = sphere.center - forward * distance;
+    }
+
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// Changes the width to height ratio of the view, like to match a
+    /// `RenderTarget` that isn't the size of the window.
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.aspect_ratio = aspect_ratio;
+    }
+
     pub fn bind(&mut self, render_context: &RenderContext, camera_uniform: &Uniform) {
         /*let uniform_buffer = render_context.device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
@@ -65,7 +91,7 @@ impl PerspectiveCamera {
         let camera_bind_group = render_context.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: camera_uniform.get_bind_group_layout(),
             entries: &[wgpu::BindGroupEntry {
-                binding: 0,
+                binding: camera_uniform.get_binding(),
                 resource: wgpu::BindingResource::Buffer(uniform_buffer.slice(..)),
             }],
             label: Some("camera_bind_group"),
@@ -84,10 +110,11 @@ impl Camera for PerspectiveCamera {
 
     fn view_matrix(&self) -> cgmath::Matrix4<f32> {
 
-        use cgmath::Rotation;
+        use cgmath::{InnerSpace, Rotation};
 
-        let forward = self.transform.rotation.rotate_vector(cgmath::Vector3::new(0., 0., -1.));
-        let up = self.transform.rotation.rotate_vector(cgmath::Vector3::new(0., 1., 0.));
+        let rotation = self.transform.rotation.normalize();
+        let forward = rotation.rotate_vector(cgmath::Vector3::new(0., 0., -1.));
+        let up = rotation.rotate_vector(cgmath::Vector3::new(0., 1., 0.));
 
         let view_mat = cgmath::Matrix4::look_at_dir(
             cgmath::Point3::from((self.transform.position.x, self.transform.position.y, self.transform.position.z)),
@@ -11,11 +11,17 @@ impl Transform {
         }
     }
 
-    pub fn build_transform_matrix() -> cgmath::Matrix4<f32> {
-        unimplemented!();
+    /// The matrix taking points from local space into world space.
+    pub fn build_transform_matrix(&self) -> cgmath::Matrix4<f32> {
+        use cgmath::InnerSpace;
+
+        cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation.normalize())
     }
 
-    pub fn build_inverse_transform_matrix() -> cgmath::Matrix4<f32> {
-        unimplemented!();
+    /// The matrix taking points from world space into local space.
+    pub fn build_inverse_transform_matrix(&self) -> cgmath::Matrix4<f32> {
+        use cgmath::{InnerSpace, Rotation};
+
+        cgmath::Matrix4::from(self.rotation.normalize().invert()) * cgmath::Matrix4::from_translation(-self.position)
     }
 }
\ No newline at end of file
@@ -0,0 +1,271 @@
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+
+use super::{create_blit_pipeline, create_readback_buffer, padded_bytes_per_row, read_buffer, RenderContext, TextureError};
+
+/// Captures frames begun with `begin_captured_frame`. Captured frames are
+/// rendered into a texture of the swap chain's size and format, which is
+/// copied back for saving and then drawn to the window as usual.
+///
+/// The capture is made for the swap chain's size when it's created, and
+/// `begin_captured_frame` resizes it whenever the window has changed size.
+pub struct FrameCapture {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    buffer: wgpu::Buffer,
+    captured: Cell<bool>,
+}
+
+impl FrameCapture {
+    pub fn new(render_context: &RenderContext) -> Result<FrameCapture, Box<dyn std::error::Error>> {
+        let device = &render_context.device;
+        let format = render_context.sc_desc.format;
+
+        let (pipeline, bind_group_layout) = create_blit_pipeline(device, format)?;
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Capture Sampler"),
+            ..Default::default()
+        });
+
+        let (width, height) = (render_context.sc_desc.width, render_context.sc_desc.height);
+        let (texture, view, bind_group, buffer) = create_target(render_context, &bind_group_layout, &sampler, format, width, height);
+
+        Ok(FrameCapture {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            format,
+            width,
+            height,
+            texture,
+            view,
+            bind_group,
+            buffer,
+            captured: Cell::new(false),
+        })
+    }
+
+    /// Matches the capture to the swap chain's current size.
+    pub fn resize(&mut self, render_context: &RenderContext) {
+        let (width, height) = (render_context.sc_desc.width, render_context.sc_desc.height);
+
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        let (texture, view, bind_group, buffer) =
+            create_target(render_context, &self.bind_group_layout, &self.sampler, self.format, width, height);
+
+        self.width = width;
+        self.height = height;
+        self.texture = texture;
+        self.view = view;
+        self.bind_group = bind_group;
+        self.buffer = buffer;
+        self.captured.set(false);
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub(crate) fn get_view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Copies the rendered frame into the readback buffer and draws it to
+    /// the swap chain.
+    pub(crate) fn finish(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &self.buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: padded_bytes_per_row(self.width, 4),
+                    rows_per_image: self.height,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            },
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        self.captured.set(true);
+    }
+
+    /// Reads back the last captured frame once it's been submitted with
+    /// `end_frame`. Each frame can only be read once.
+    pub async fn read_image(&self, render_context: &RenderContext) -> Result<image::RgbaImage, TextureError> {
+        if !self.captured.replace(false) {
+            return Err(TextureError::new("No frame has been captured since the last read"));
+        }
+
+        let mut pixels = read_buffer(render_context, &self.buffer, self.width, self.height, 4).await?;
+
+        let bgra = match self.format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            format => return Err(TextureError::new(&format!("{:?} frames can't be saved as images", format))),
+        };
+
+        for pixel in pixels.chunks_mut(4) {
+            if bgra {
+                pixel.swap(0, 2);
+            }
+
+            // What the window shows is always opaque.
+            pixel[3] = 255;
+        }
+
+        image::RgbaImage::from_raw(self.width, self.height, pixels)
+            .ok_or_else(|| TextureError::new("The captured frame is the wrong size"))
+    }
+
+    /// Waits for the last captured frame and writes it to a PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, render_context: &RenderContext, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let image = futures::executor::block_on(self.read_image(render_context))?;
+
+        image.save_with_format(path, image::ImageFormat::Png)?;
+
+        Ok(())
+    }
+}
+
+fn create_target(
+    render_context: &RenderContext,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup, wgpu::Buffer) {
+    let texture = render_context.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Capture Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_SRC,
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let bind_group = render_context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("Capture Bind Group"),
+    });
+
+    let buffer = create_readback_buffer(render_context, width, height, 4);
+
+    (texture, view, bind_group, buffer)
+}
+
+/// Records numbered PNG frames into a directory. While recording, the
+/// application's update is given a fixed timestep instead of the measured
+/// frame time, so the frames play back at the right speed no matter how long
+/// each one took to render and save.
+pub struct ImageSequence {
+    capture: FrameCapture,
+    directory: PathBuf,
+    timestep: f32,
+    frame_count: Cell<u32>,
+}
+
+impl ImageSequence {
+    pub fn start<P: AsRef<Path>>(
+        render_context: &mut RenderContext,
+        directory: P,
+        frames_per_second: f32,
+    ) -> Result<ImageSequence, Box<dyn std::error::Error>> {
+        if frames_per_second <= 0. {
+            return Err(Box::new(TextureError::new("Image sequences need a positive frame rate")));
+        }
+
+        std::fs::create_dir_all(directory.as_ref())?;
+
+        let timestep = 1. / frames_per_second;
+        render_context.set_fixed_timestep(Some(timestep));
+
+        Ok(ImageSequence {
+            capture: FrameCapture::new(render_context)?,
+            directory: directory.as_ref().to_path_buf(),
+            timestep,
+            frame_count: Cell::new(0),
+        })
+    }
+
+    /// Begins a frame that `save_frame` can write out after `end_frame`.
+    pub fn begin_frame<'r>(&'r mut self, render_context: &'r mut RenderContext) -> super::Frame<'r> {
+        super::begin_captured_frame(render_context, &mut self.capture)
+    }
+
+    /// Writes the last frame as the next image, like `frame_00000.png`, and
+    /// returns its path.
+    pub fn save_frame(&self, render_context: &RenderContext) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = self.directory.join(format!("frame_{:05}.png", self.frame_count.get()));
+
+        self.capture.save_png(render_context, &path)?;
+        self.frame_count.set(self.frame_count.get() + 1);
+
+        Ok(path)
+    }
+
+    pub fn get_frame_count(&self) -> u32 {
+        self.frame_count.get()
+    }
+
+    pub fn get_timestep(&self) -> f32 {
+        self.timestep
+    }
+
+    /// Gives the application back its measured frame time.
+    pub fn stop(&self, render_context: &mut RenderContext) {
+        render_context.set_fixed_timestep(None);
+    }
+}
@@ -0,0 +1,394 @@
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4};
+
+use std::cell::{Cell, RefCell};
+use std::f32::consts::PI;
+
+use super::{Aabb, BoundingSphere, CameraUniform, RenderContext, Vertex};
+
+/// How many segments make up each circle of a debug sphere.
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Vertex)]
+struct DebugVertex {
+    position: Vector3<f32>,
+    color: [f32; 4],
+    /// 1 for lines drawn over everything, 0 for depth tested lines.
+    overlay: f32,
+}
+
+unsafe impl bytemuck::Zeroable for DebugVertex {}
+unsafe impl bytemuck::Pod for DebugVertex {}
+
+#[derive(Copy, Clone, Debug)]
+struct DebugLine {
+    start: Vector3<f32>,
+    end: Vector3<f32>,
+    color: [f32; 4],
+    depth_test: bool,
+    /// Seconds left before the line is removed. Lines are always drawn at
+    /// least once.
+    remaining: f32,
+    /// Whether the line has been uploaded to be drawn.
+    drawn: bool,
+}
+
+/// Immediate mode line drawing for visualizing things like physics, ai and
+/// culling. Shapes can be added from anywhere with a shared reference, then
+/// `Frame::render_debug` draws them all in a single line list.
+///
+/// Shapes use the depth test and lifetime set at the time they're added.
+/// A lifetime of 0, the default, draws a shape for one frame. Longer lived
+/// shapes count down in `update`.
+///
+/// The shapes are uploaded once a frame, by its first `render_debug`, so
+/// every call that frame draws the same shapes. Shapes added after that
+/// are drawn from the next frame.
+pub struct DebugDraw {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// A camera buffer and bind group for each `render_debug` call in a
+    /// frame, since they may use different cameras.
+    cameras: RefCell<Vec<(wgpu::Buffer, wgpu::BindGroup)>>,
+    cameras_used: Cell<usize>,
+    vertex_buffer: RefCell<Option<wgpu::Buffer>>,
+    vertex_capacity: Cell<usize>,
+    vertex_count: Cell<u32>,
+    /// The frame the shapes were last uploaded for.
+    uploaded_frame: Cell<Option<u64>>,
+    lines: RefCell<Vec<DebugLine>>,
+    depth_test: Cell<bool>,
+    lifetime: Cell<f32>,
+}
+
+impl DebugDraw {
+    pub fn new(render_context: &RenderContext) -> Result<DebugDraw, Box<dyn std::error::Error>> {
+        let vs_spirv = std::fs::read("res/shader/debug_line.vert.spv")?;
+        let fs_spirv = std::fs::read("res/shader/debug_line.frag.spv")?;
+
+        let vs_module = render_context.device.create_shader_module(wgpu::util::make_spirv(&vs_spirv));
+        let fs_module = render_context.device.create_shader_module(wgpu::util::make_spirv(&fs_spirv));
+
+        let bind_group_layout = render_context.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Debug Draw Camera Layout"),
+        });
+
+        let pipeline_layout = render_context.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Draw Pipeline Layout"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+
+        let pipeline = render_context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug Draw Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: &vs_module,
+                entry_point: "main",
+            },
+            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                module: &fs_module,
+                entry_point: "main",
+            }),
+            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                depth_bias: 0,
+                depth_bias_slope_scale: 0.0,
+                depth_bias_clamp: 0.0,
+                clamp_depth: false,
+            }),
+            color_states: &[wgpu::ColorStateDescriptor {
+                format: render_context.sc_desc.format,
+                color_blend: wgpu::BlendDescriptor {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            primitive_topology: wgpu::PrimitiveTopology::LineList,
+            // Lines don't write depth, so they never hide each other or
+            // anything drawn after them.
+            depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilStateDescriptor::default(),
+            }),
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: wgpu::IndexFormat::Uint16,
+                vertex_buffers: &[DebugVertex::desc()],
+            },
+            sample_count: 1,
+            sample_mask: !0,
+            alpha_to_coverage_enabled: false,
+        });
+
+        Ok(DebugDraw {
+            pipeline,
+            bind_group_layout,
+            cameras: RefCell::new(Vec::new()),
+            cameras_used: Cell::new(0),
+            vertex_buffer: RefCell::new(None),
+            vertex_capacity: Cell::new(0),
+            vertex_count: Cell::new(0),
+            uploaded_frame: Cell::new(None),
+            lines: RefCell::new(Vec::new()),
+            depth_test: Cell::new(true),
+            lifetime: Cell::new(0.),
+        })
+    }
+
+    /// Sets whether shapes added from now on are hidden behind other
+    /// geometry.
+    pub fn set_depth_test(&self, depth_test: bool) {
+        self.depth_test.set(depth_test);
+    }
+
+    pub fn get_depth_test(&self) -> bool {
+        self.depth_test.get()
+    }
+
+    /// Sets how many seconds shapes added from now on stay for.
+    pub fn set_lifetime(&self, lifetime: f32) {
+        self.lifetime.set(lifetime.max(0.));
+    }
+
+    pub fn get_lifetime(&self) -> f32 {
+        self.lifetime.get()
+    }
+
+    /// Counts down the lifetime of every shape.
+    pub fn update(&self, delta: f32) {
+        for line in self.lines.borrow_mut().iter_mut() {
+            line.remaining -= delta;
+        }
+    }
+
+    /// Removes every shape, however long it had left.
+    pub fn clear(&self) {
+        self.lines.borrow_mut().clear();
+    }
+
+    pub fn line(&self, start: Vector3<f32>, end: Vector3<f32>, color: [f32; 4]) {
+        self.lines.borrow_mut().push(DebugLine {
+            start,
+            end,
+            color,
+            depth_test: self.depth_test.get(),
+            remaining: self.lifetime.get(),
+            drawn: false,
+        });
+    }
+
+    /// A line from `origin` to `origin + direction`.
+    pub fn ray(&self, origin: Vector3<f32>, direction: Vector3<f32>, color: [f32; 4]) {
+        self.line(origin, origin + direction, color);
+    }
+
+    pub fn aabb(&self, aabb: &Aabb, color: [f32; 4]) {
+        self.box_edges(&aabb.corners(), color);
+    }
+
+    /// A circle around each axis through the sphere's center.
+    pub fn sphere(&self, sphere: &BoundingSphere, color: [f32; 4]) {
+        let x = Vector3::new(sphere.radius, 0., 0.);
+        let y = Vector3::new(0., sphere.radius, 0.);
+        let z = Vector3::new(0., 0., sphere.radius);
+
+        self.circle(sphere.center, x, y, color);
+        self.circle(sphere.center, y, z, color);
+        self.circle(sphere.center, z, x, color);
+    }
+
+    /// The outline of the volume a camera sees, given its projection matrix
+    /// times its view matrix, e.g. `camera.proj_matrix() * camera.view_matrix()`.
+    pub fn frustum(&self, view_proj: &Matrix4<f32>, color: [f32; 4]) {
+        let inverse = match view_proj.invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        // The corners of clip space, in the order `Aabb::corners` uses.
+        let mut corners = [Vector3::new(0., 0., 0.); 8];
+
+        for (index, corner) in corners.iter_mut().enumerate() {
+            let clip = Vector4::new(
+                if index & 1 == 0 { -1. } else { 1. },
+                if index & 2 == 0 { -1. } else { 1. },
+                if index & 4 == 0 { -1. } else { 1. },
+                1.
+            );
+
+            let world = inverse * clip;
+            *corner = world.truncate() / world.w;
+        }
+
+        self.box_edges(&corners, color);
+    }
+
+    /// A square grid on the xz plane, `size` across with `divisions` cells
+    /// along each side.
+    pub fn grid(&self, center: Vector3<f32>, size: f32, divisions: u32, color: [f32; 4]) {
+        let divisions = divisions.max(1);
+        let half = size / 2.;
+
+        for i in 0..=divisions {
+            let offset = -half + size * i as f32 / divisions as f32;
+
+            self.line(center + Vector3::new(offset, 0., -half), center + Vector3::new(offset, 0., half), color);
+            self.line(center + Vector3::new(-half, 0., offset), center + Vector3::new(half, 0., offset), color);
+        }
+    }
+
+    /// The x, y and z axes of `matrix` in red, green and blue, `size` long.
+    pub fn axes(&self, matrix: &Matrix4<f32>, size: f32) {
+        let origin = matrix.w.truncate();
+
+        self.ray(origin, matrix.x.truncate().normalize() * size, [1., 0., 0., 1.]);
+        self.ray(origin, matrix.y.truncate().normalize() * size, [0., 1., 0., 1.]);
+        self.ray(origin, matrix.z.truncate().normalize() * size, [0., 0., 1., 1.]);
+    }
+
+    fn circle(&self, center: Vector3<f32>, x: Vector3<f32>, y: Vector3<f32>, color: [f32; 4]) {
+        let point = |segment: usize| {
+            let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * 2. * PI;
+            center + x * angle.cos() + y * angle.sin()
+        };
+
+        for segment in 0..CIRCLE_SEGMENTS {
+            self.line(point(segment), point(segment + 1), color);
+        }
+    }
+
+    /// The twelve edges of a box whose corners are indexed by bits for x, y
+    /// and z.
+    fn box_edges(&self, corners: &[Vector3<f32>; 8], color: [f32; 4]) {
+        for a in 0..8 {
+            for bit in &[1, 2, 4] {
+                if a & bit == 0 {
+                    self.line(corners[a], corners[a | bit], color);
+                }
+            }
+        }
+    }
+
+    /// Uploads the camera for a `render_debug` call, and the shapes if this
+    /// is the frame's first call. Returns the number of vertices to draw and
+    /// which of the cameras to draw them with.
+    pub(crate) fn prepare<C: crate::core::Camera>(&self, render_context: &RenderContext, camera: &C) -> (u32, usize) {
+        let frame = render_context.get_frame_count();
+
+        if self.uploaded_frame.get() != Some(frame) {
+            self.uploaded_frame.set(Some(frame));
+            self.cameras_used.set(0);
+            self.vertex_count.set(self.upload_lines(render_context));
+        }
+
+        let index = self.cameras_used.get();
+        self.cameras_used.set(index + 1);
+
+        let camera = CameraUniform::new(camera.view_matrix(), camera.proj_matrix());
+        let mut cameras = self.cameras.borrow_mut();
+
+        match cameras.get(index) {
+            Some((buffer, _)) => render_context.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[camera])),
+            None => {
+                use wgpu::util::DeviceExt;
+
+                let buffer = render_context.device.create_buffer_init(
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Debug Draw Camera Buffer"),
+                        contents: bytemuck::cast_slice(&[camera]),
+                        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                    }
+                );
+
+                let bind_group = render_context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+                    }],
+                    label: Some("Debug Draw Camera Bind Group"),
+                });
+
+                cameras.push((buffer, bind_group));
+            }
+        }
+
+        (self.vertex_count.get(), index)
+    }
+
+    /// Drops the shapes drawn last frame whose time is up, then uploads the
+    /// rest. Returns the number of vertices to draw.
+    fn upload_lines(&self, render_context: &RenderContext) -> u32 {
+        let mut lines = self.lines.borrow_mut();
+
+        lines.retain(|line| !line.drawn || line.remaining > 0.);
+
+        let vertices: Vec<DebugVertex> = lines
+            .iter()
+            .flat_map(|line| {
+                let overlay = if line.depth_test { 0. } else { 1. };
+
+                vec![
+                    DebugVertex { position: line.start, color: line.color, overlay },
+                    DebugVertex { position: line.end, color: line.color, overlay },
+                ]
+            })
+            .collect();
+
+        for line in lines.iter_mut() {
+            line.drawn = true;
+        }
+
+        if vertices.is_empty() {
+            return 0;
+        }
+
+        if vertices.len() > self.vertex_capacity.get() {
+            let capacity = vertices.len().next_power_of_two();
+
+            self.vertex_buffer.replace(Some(render_context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Debug Draw Vertex Buffer"),
+                size: (capacity * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            })));
+
+            self.vertex_capacity.set(capacity);
+        }
+
+        if let Some(buffer) = self.vertex_buffer.borrow().as_ref() {
+            render_context.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&vertices[..]));
+        }
+
+        vertices.len() as u32
+    }
+
+    pub(crate) fn get_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub(crate) fn get_cameras(&self) -> std::cell::Ref<Vec<(wgpu::Buffer, wgpu::BindGroup)>> {
+        self.cameras.borrow()
+    }
+
+    pub(crate) fn get_vertex_buffer(&self) -> std::cell::Ref<Option<wgpu::Buffer>> {
+        self.vertex_buffer.borrow()
+    }
+}
@@ -1,5 +1,44 @@
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct MaterialError {
+    message: String
+}
+
+impl MaterialError {
+    pub fn new(message: &str) -> MaterialError {
+        MaterialError {
+            message: String::from(message)
+        }
+    }
+}
+
+impl std::error::Error for MaterialError {}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error creating material: {}", self.message)
+    }
+}
+
+/// The color format, whether there's a depth buffer and the index format a
+/// pipeline was made for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    color_format: wgpu::TextureFormat,
+    depth: bool,
+    index_format: wgpu::IndexFormat,
+}
+
 pub struct Material {
     render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_u32: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+    vertex_layout: wgpu::VertexBufferDescriptor<'static>,
+    target_pipelines: RefCell<HashMap<PipelineKey, Rc<wgpu::RenderPipeline>>>,
+    vertex_type: TypeId,
     uniforms: Vec<super::Uniform>,
     uniform_names: HashMap<String, usize>
 }
@@ -9,6 +48,50 @@ impl Material {
         &self.render_pipeline
     }
 
+    /// Gets the pipeline matching the index format of a mesh's index buffer.
+    pub fn get_render_pipeline_for(&self, index_format: wgpu::IndexFormat) -> &wgpu::RenderPipeline {
+        match index_format {
+            wgpu::IndexFormat::Uint16 => &self.render_pipeline,
+            wgpu::IndexFormat::Uint32 => &self.render_pipeline_u32,
+        }
+    }
+
+    /// Gets a pipeline for drawing into a render target, made the first
+    /// time a target with that color format and depth is drawn to.
+    pub(crate) fn get_target_pipeline(
+        &self,
+        render_context: &crate::graphics::RenderContext,
+        color_format: wgpu::TextureFormat,
+        depth: bool,
+        index_format: wgpu::IndexFormat,
+    ) -> Rc<wgpu::RenderPipeline> {
+        let mut target_pipelines = self.target_pipelines.borrow_mut();
+
+        let key = PipelineKey {
+            color_format,
+            depth,
+            index_format,
+        };
+
+        let pipeline = target_pipelines.entry(key).or_insert_with(|| {
+            Rc::new(MaterialBuilder::create_render_pipeline(
+                render_context,
+                &self.vertex_layout,
+                &self.render_pipeline_layout,
+                &self.vs_module,
+                &self.fs_module,
+                key,
+            ))
+        });
+
+        pipeline.clone()
+    }
+
+    /// The vertex type the material's pipelines were built for.
+    pub fn get_vertex_type(&self) -> TypeId {
+        self.vertex_type
+    }
+
     pub fn get_uniforms(&self) -> &Vec<super::Uniform> {
         &self.uniforms
     }
@@ -22,101 +105,132 @@ impl Material {
     }
 }
 
+use std::any::TypeId;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::graphics::{StandardVertex, Vertex};
 
 pub struct MaterialBuilder<'a> {
     render_context: &'a crate::graphics::RenderContext,
+    vertex_layout: wgpu::VertexBufferDescriptor<'static>,
+    vertex_type: TypeId,
     vertex_stage: Option<Vec<u8>>,
     fragment_stage: Option<Vec<u8>>,
     uniforms: Vec<super::Uniform>,
-    uniform_map: HashMap<String, usize>
+    uniform_map: HashMap<String, usize>,
+    error: Option<MaterialError>
 }
 
 impl<'a> MaterialBuilder<'a> {
     pub fn new(r: &'a crate::graphics::RenderContext) -> Self {
         Self {
             render_context: r,
+            vertex_layout: StandardVertex::desc(),
+            vertex_type: TypeId::of::<StandardVertex>(),
             vertex_stage: None,
             fragment_stage: None,
             uniforms: Vec::new(),
             uniform_map: HashMap::new(),
+            error: None,
         }
     }
 
+    /// Loads the shader stages and uniforms described by a `.mat` file.
+    /// Each uniform has a `name` and a `type`, one of `camera`, `texture`,
+    /// `sampler`, `float`, `vec2`, `vec3`, `vec4`, `color`, `mat4`,
+    /// `storage_buffer`, `joints` or `morph_targets`, and can give its `set`,
+    /// first `binding` and the shader stages in its `visibility`. Textures
+    /// also take a `dimension`, `sampler` and `comparison`, samplers a
+    /// `comparison` and storage buffers `readonly`.
+    ///
+    /// Problems with the file are returned by `build`.
     pub fn from_file<P: AsRef<std::path::Path>>(mut self, p: P) -> Self {
-        let material_src =
-            std::fs::read_to_string(p).expect("Invalid path when creating material.");
-
-        let material_layout: serde_json::Value =
-            serde_json::from_str(&material_src).expect("Unable to parse material.");
-
-        let vs_path = material_layout["vertex_stage"].as_str().unwrap();
-        let fs_path = material_layout["fragment_stage"].as_str().unwrap();
-
-        let fs_spirv = std::fs::read(fs_path).unwrap();
-        let vs_spirv = std::fs::read(vs_path).unwrap();
-        self.fragment_stage = Some(fs_spirv);
-        self.vertex_stage = Some(vs_spirv);
-
-        let uniform_descriptors = material_layout["uniforms"].as_array().unwrap();
-
-        for value in uniform_descriptors {
-            let name = value["name"].as_str().unwrap();
-            let uniform_type = value["type"].as_str().unwrap();
-            // Big ol todo.
-
-
-            let bind_group_layout = if uniform_type == "camera" {
-                self.render_context.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStage::VERTEX,
-                            ty: wgpu::BindingType::UniformBuffer {
-                                dynamic: false,
-                                min_binding_size: None
-                            },
-                            count: None
-                        }
-                    ],
-                    label: Some(name),
-                })
-            } else {
-                self.render_context.device.create_bind_group_layout(
-                    &wgpu::BindGroupLayoutDescriptor {
-                        entries: &[
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 0,
-                                visibility: wgpu::ShaderStage::FRAGMENT,
-                                ty: wgpu::BindingType::SampledTexture {
-                                    multisampled: false,
-                                    dimension: wgpu::TextureViewDimension::D2,
-                                    component_type: wgpu::TextureComponentType::Uint,
-                                },
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 1,
-                                visibility: wgpu::ShaderStage::FRAGMENT,
-                                ty: wgpu::BindingType::Sampler {
-                                    comparison: false,
-                                },
-                                count: None,
-                            },
-                        ],
-                        label: Some(name),
-                    })
-            };
-
-            self.uniforms.push(super::Uniform::new(name, bind_group_layout));
-
-            self.uniform_map.insert(name.to_string(), self.uniforms.len() - 1);
+        if let Err(error) = self.load_file(p.as_ref()) {
+            self.error = Some(error);
+        }
+
+        self
+    }
+
+    fn load_file(&mut self, path: &std::path::Path) -> Result<(), MaterialError> {
+        let material_file = super::schema::MaterialFile::load(path)?;
+
+        let read_stage = |field: &str, stage_path: &std::path::Path| {
+            std::fs::read(stage_path).map_err(|error| {
+                MaterialError::new(&format!("{:?}: {} {:?} couldn't be read: {}", path, field, stage_path, error))
+            })
+        };
+
+        self.vertex_stage = Some(read_stage("vertex_stage", &material_file.vertex_stage)?);
+        self.fragment_stage = Some(read_stage("fragment_stage", &material_file.fragment_stage)?);
+
+        for uniform in &material_file.uniforms {
+            let bind_group_layout = self.render_context.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &uniform.layout_entries(),
+                label: Some(&uniform.name),
+            });
+
+            self.uniforms.push(super::Uniform::new(
+                &uniform.name,
+                uniform.uniform_type,
+                uniform.get_binding(),
+                uniform.has_sampler(),
+                bind_group_layout,
+            ));
+
+            self.uniform_map.insert(uniform.name.clone(), self.uniforms.len() - 1);
         }
 
+        Ok(())
+    }
+
+    /// Builds the material's pipelines for meshes of vertex type `V` instead
+    /// of `StandardVertex`.
+    pub fn with_vertex_layout<V: Vertex + 'static>(mut self) -> Self {
+        self.vertex_layout = V::desc();
+        self.vertex_type = TypeId::of::<V>();
         self
     }
 
-    pub fn build(self) -> Material {
+    /// Checks every input of the vertex shader is fed by an attribute of the
+    /// vertex layout with a matching component type.
+    fn validate_vertex_layout(vertex_layout: &wgpu::VertexBufferDescriptor, vs_spirv: &[u8]) -> Result<(), MaterialError> {
+        let inputs = super::reflect::shader_inputs(vs_spirv)
+            .map_err(|message| MaterialError::new(&message))?;
+
+        for input in inputs {
+            let attribute = vertex_layout.attributes
+                .iter()
+                .find(|attribute| attribute.shader_location == input.location);
+
+            match attribute {
+                Some(attribute) => {
+                    if super::reflect::format_kind(attribute.format) != input.kind {
+                        return Err(MaterialError::new(&format!(
+                            "Vertex shader input at location {} is {:?} but the vertex layout provides {:?}",
+                            input.location, input.kind, attribute.format
+                        )));
+                    }
+                }
+                None => {
+                    return Err(MaterialError::new(&format!(
+                        "Vertex shader input at location {} is not provided by the vertex layout",
+                        input.location
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<Material, Box<dyn std::error::Error>> {
+        if let Some(error) = self.error {
+            return Err(Box::new(error));
+        }
+
         let vs_spirv = self
             .vertex_stage
             .expect("Attempted to build material without vertex stage.");
@@ -124,6 +238,8 @@ impl<'a> MaterialBuilder<'a> {
             .fragment_stage
             .expect("Attempted to build material without fragment stage.");
 
+        Self::validate_vertex_layout(&self.vertex_layout, &vs_spirv)?;
+
         let vs_module = self
             .render_context
             .device
@@ -139,7 +255,7 @@ impl<'a> MaterialBuilder<'a> {
             .collect();
 
         let render_pipeline_layout =
-            &self
+            self
                 .render_context
                 .device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -148,57 +264,103 @@ impl<'a> MaterialBuilder<'a> {
                     bind_group_layouts: &uniform_layouts[..],
                 });
 
-        use crate::graphics::Vertex;
+        let render_pipeline = Self::create_render_pipeline(
+            self.render_context,
+            &self.vertex_layout,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            PipelineKey {
+                color_format: self.render_context.sc_desc.format,
+                depth: true,
+                index_format: wgpu::IndexFormat::Uint16,
+            },
+        );
 
-        let render_pipeline =
-            self.render_context
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("Default RISE Render Pipeline"),
-                    layout: Some(&render_pipeline_layout),
-                    vertex_stage: wgpu::ProgrammableStageDescriptor {
-                        module: &vs_module,
-                        entry_point: "main",
-                    },
-                    fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                        module: &fs_module,
-                        entry_point: "main",
-                    }),
-                    rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                        front_face: wgpu::FrontFace::Ccw,
-                        cull_mode: wgpu::CullMode::Back,
-                        depth_bias: 0,
-                        depth_bias_slope_scale: 0.0,
-                        depth_bias_clamp: 0.0,
-                        clamp_depth: false,
-                    }),
-                    color_states: &[wgpu::ColorStateDescriptor {
-                        format: self.render_context.sc_desc.format,
-                        color_blend: wgpu::BlendDescriptor::REPLACE,
-                        alpha_blend: wgpu::BlendDescriptor::REPLACE,
-                        write_mask: wgpu::ColorWrite::ALL,
-                    }],
-
-                    primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-                    depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
-                        format: wgpu::TextureFormat::Depth32Float,
-                        depth_write_enabled: true,
-                        depth_compare: wgpu::CompareFunction::LessEqual,
-                        stencil: wgpu::StencilStateDescriptor::default(),
-                    }),
-                    vertex_state: wgpu::VertexStateDescriptor {
-                        index_format: wgpu::IndexFormat::Uint16,
-                        vertex_buffers: &[Vertex::desc()],
-                    },
-                    sample_count: 1,
-                    sample_mask: !0,
-                    alpha_to_coverage_enabled: false,
-                });
+        let render_pipeline_u32 = Self::create_render_pipeline(
+            self.render_context,
+            &self.vertex_layout,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            PipelineKey {
+                color_format: self.render_context.sc_desc.format,
+                depth: true,
+                index_format: wgpu::IndexFormat::Uint32,
+            },
+        );
 
-        Material {
+        Ok(Material {
             render_pipeline,
+            render_pipeline_u32,
+            render_pipeline_layout,
+            vs_module,
+            fs_module,
+            vertex_layout: self.vertex_layout,
+            target_pipelines: RefCell::new(HashMap::new()),
+            vertex_type: self.vertex_type,
             uniforms: self.uniforms,
             uniform_names: self.uniform_map
-        }
+        })
+    }
+
+    fn create_render_pipeline(
+        render_context: &crate::graphics::RenderContext,
+        vertex_layout: &wgpu::VertexBufferDescriptor,
+        render_pipeline_layout: &wgpu::PipelineLayout,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        key: PipelineKey,
+    ) -> wgpu::RenderPipeline {
+        let depth_stencil_state = if key.depth {
+            Some(wgpu::DepthStencilStateDescriptor {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilStateDescriptor::default(),
+            })
+        } else {
+            None
+        };
+
+
+        render_context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Default RISE Render Pipeline"),
+                layout: Some(render_pipeline_layout),
+                vertex_stage: wgpu::ProgrammableStageDescriptor {
+                    module: vs_module,
+                    entry_point: "main",
+                },
+                fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                    module: fs_module,
+                    entry_point: "main",
+                }),
+                rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: wgpu::CullMode::Back,
+                    depth_bias: 0,
+                    depth_bias_slope_scale: 0.0,
+                    depth_bias_clamp: 0.0,
+                    clamp_depth: false,
+                }),
+                color_states: &[wgpu::ColorStateDescriptor {
+                    format: key.color_format,
+                    color_blend: wgpu::BlendDescriptor::REPLACE,
+                    alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+
+                primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+                depth_stencil_state,
+                vertex_state: wgpu::VertexStateDescriptor {
+                    index_format: key.index_format,
+                    vertex_buffers: &[vertex_layout.clone()],
+                },
+                sample_count: 1,
+                sample_mask: !0,
+                alpha_to_coverage_enabled: false,
+            })
     }
 }
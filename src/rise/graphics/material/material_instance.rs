@@ -1,6 +1,28 @@
-use crate::{RISEError, Result};
-
 use std::collections::HashMap;
+use std::fmt;
+
+use super::UniformType;
+
+#[derive(Debug, Clone)]
+pub struct UniformError {
+  message: String,
+}
+
+impl UniformError {
+  pub fn new(message: &str) -> UniformError {
+    UniformError {
+      message: String::from(message),
+    }
+  }
+}
+
+impl std::error::Error for UniformError {}
+
+impl fmt::Display for UniformError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Error binding uniform: {}", self.message)
+  }
+}
 
 pub struct MaterialInstance {
   base_material: std::rc::Rc<super::Material>,
@@ -8,47 +30,164 @@ pub struct MaterialInstance {
 }
 
 impl MaterialInstance {
+  /// Binds a texture to a `texture` uniform, along with its sampler if the
+  /// uniform has one.
   pub fn use_texture(
     &mut self,
     name: &str,
     texture: &crate::graphics::Texture,
     render_context: &crate::graphics::RenderContext,
-  ) -> Result<()> {
-    let base = self.base_material.as_ref();
+  ) -> Result<(), UniformError> {
+    self.bind(name, UniformType::Texture, "texture_bind_group", render_context, |uniform| {
+      texture_entries(uniform, texture)
+    })
+  }
+
+  /// Binds a streaming texture to a texture uniform. Draws sample whichever
+  /// of its textures was presented last, without binding it again.
+  pub fn use_streaming_texture(
+    &mut self,
+    name: &str,
+    texture: &crate::graphics::StreamingTexture,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    let binding_index = self.find_uniform(name, UniformType::Texture)?;
+    let uniform = &self.base_material.get_uniforms()[binding_index];
+
+    let bind_groups = texture
+      .get_textures()
+      .iter()
+      .map(|buffer| {
+        create_bind_group(
+          uniform,
+          &texture_entries(uniform, buffer),
+          "streaming_texture_bind_group",
+          render_context,
+        )
+      })
+      .collect();
+
+    self.uniform_values.insert(
+      binding_index,
+      super::UniformBinding::new_switching(bind_groups, texture.get_current()),
+    );
+
+    Ok(())
+  }
+
+  /// Binds a skeleton's joint matrices to a `joints` uniform. The buffer can
+  /// be updated afterwards without binding it again.
+  pub fn use_joints(
+    &mut self,
+    name: &str,
+    joints: &crate::graphics::JointBuffer,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    self.bind(name, UniformType::Joints, "joints_bind_group", render_context, |uniform| {
+      vec![wgpu::BindGroupEntry {
+        binding: uniform.get_binding(),
+        resource: wgpu::BindingResource::Buffer(joints.get_buffer().slice(..)),
+      }]
+    })
+  }
+
+  /// Binds a mesh's morph targets to a `morph_targets` uniform. Weights can
+  /// be updated afterwards without binding it again.
+  pub fn use_morph_targets(
+    &mut self,
+    name: &str,
+    morph_targets: &crate::graphics::MorphTargetBuffer,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    self.bind(name, UniformType::MorphTargets, "morph_targets_bind_group", render_context, |uniform| {
+      vec![
+        wgpu::BindGroupEntry {
+          binding: uniform.get_binding(),
+          resource: wgpu::BindingResource::Buffer(morph_targets.get_deltas_buffer().slice(..)),
+        },
+        wgpu::BindGroupEntry {
+          binding: uniform.get_binding() + 1,
+          resource: wgpu::BindingResource::Buffer(morph_targets.get_weights_buffer().slice(..)),
+        },
+      ]
+    })
+  }
+
+  /// Binds a sampler to a `sampler` uniform.
+  pub fn use_sampler(
+    &mut self,
+    name: &str,
+    sampler: &crate::graphics::Sampler,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    self.bind(name, UniformType::Sampler, "sampler_bind_group", render_context, |uniform| {
+      vec![wgpu::BindGroupEntry {
+        binding: uniform.get_binding(),
+        resource: wgpu::BindingResource::Sampler(sampler.get_sampler()),
+      }]
+    })
+  }
+
+  /// Binds a buffer made with `STORAGE` usage to a `storage_buffer` uniform.
+  /// The buffer can be written afterwards without binding it again.
+  pub fn use_buffer(
+    &mut self,
+    name: &str,
+    buffer: &wgpu::Buffer,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    self.bind(name, UniformType::StorageBuffer, "storage_buffer_bind_group", render_context, |uniform| {
+      vec![wgpu::BindGroupEntry {
+        binding: uniform.get_binding(),
+        resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+      }]
+    })
+  }
+
+  /// Sets a `float`, `vec2`, `vec3`, `vec4`, `color` or `mat4` uniform. The
+  /// first value makes the uniform's buffer and later ones are written into
+  /// it. The value has to match the uniform's type.
+  pub fn set_value(
+    &mut self,
+    name: &str,
+    value: super::UniformValue,
+    render_context: &crate::graphics::RenderContext,
+  ) -> Result<(), UniformError> {
+    use wgpu::util::DeviceExt;
+
+    let binding_index = self.find_uniform(name, value.get_type())?;
+
+    if let Some(buffer) = self.uniform_values.get(&binding_index).and_then(|binding| binding.get_buffer()) {
+      render_context.queue.write_buffer(buffer, 0, &value.to_bytes());
 
-    
-
-    match base.get_binding_by_name(name) {
-      Some(binding_index) => {
-        println!("Putting {} texture in bind_group {}", name, binding_index);
-        let uniform = &base.get_uniforms()[*binding_index];
-        let bind_group_layout = uniform.get_bind_group_layout();
-
-        let bind_group = render_context
-          .device
-          .create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-              wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(texture.get_view()),
-              },
-              wgpu::BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::Sampler(texture.get_sampler()),
-              },
-            ],
-            label: Some("texture_bind_group"),
-          });
-
-        self
-          .uniform_values
-          .insert(*binding_index, super::UniformBinding::new(bind_group, None));
-
-        Ok(())
-      }
-      None => Err(RISEError {}),
+      return Ok(());
     }
+
+    let uniform = &self.base_material.get_uniforms()[binding_index];
+
+    let buffer = render_context
+      .device
+      .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(uniform.get_name()),
+        contents: &value.to_bytes(),
+        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+      });
+
+    let bind_group = create_bind_group(
+      uniform,
+      &[wgpu::BindGroupEntry {
+        binding: uniform.get_binding(),
+        resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+      }],
+      "value_bind_group",
+      render_context,
+    );
+
+    self
+      .uniform_values
+      .insert(binding_index, super::UniformBinding::new(bind_group, Some(buffer)));
+
+    Ok(())
   }
 
   pub fn get_base_material(&self) -> &super::Material {
@@ -58,6 +197,50 @@ impl MaterialInstance {
   pub fn get_uniforms(&self) -> &HashMap<usize, super::UniformBinding> {
     &self.uniform_values
   }
+
+  /// The index of the uniform called `name`, which has to hold `expected`.
+  fn find_uniform(&self, name: &str, expected: UniformType) -> Result<usize, UniformError> {
+    let base = self.base_material.as_ref();
+
+    let binding_index = *base
+      .get_binding_by_name(name)
+      .ok_or_else(|| UniformError::new(&format!("The material has no uniform named {:?}", name)))?;
+
+    let uniform_type = base.get_uniforms()[binding_index].get_type();
+
+    if uniform_type != expected {
+      return Err(UniformError::new(&format!(
+        "{:?} is a {} uniform, but was given a {}",
+        name,
+        uniform_type.get_name(),
+        expected.get_name()
+      )));
+    }
+
+    Ok(binding_index)
+  }
+
+  /// Binds the resources `entries` gives for the uniform called `name`,
+  /// replacing whatever was bound to it before.
+  fn bind<'r>(
+    &mut self,
+    name: &str,
+    expected: UniformType,
+    label: &'static str,
+    render_context: &crate::graphics::RenderContext,
+    entries: impl FnOnce(&super::Uniform) -> Vec<wgpu::BindGroupEntry<'r>>,
+  ) -> Result<(), UniformError> {
+    let binding_index = self.find_uniform(name, expected)?;
+    let uniform = &self.base_material.get_uniforms()[binding_index];
+
+    let bind_group = create_bind_group(uniform, &entries(uniform), label, render_context);
+
+    self
+      .uniform_values
+      .insert(binding_index, super::UniformBinding::new(bind_group, None));
+
+    Ok(())
+  }
 }
 
 pub trait MaterialInstanceBuilder {
@@ -72,3 +255,36 @@ impl MaterialInstanceBuilder for std::rc::Rc<super::Material> {
     }
   }
 }
+
+/// The texture's view and, if the uniform has one, its sampler.
+fn texture_entries<'a>(uniform: &super::Uniform, texture: &'a crate::graphics::Texture) -> Vec<wgpu::BindGroupEntry<'a>> {
+  let mut entries = vec![wgpu::BindGroupEntry {
+    binding: uniform.get_binding(),
+    resource: wgpu::BindingResource::TextureView(texture.get_view()),
+  }];
+
+  if uniform.has_sampler() {
+    entries.push(wgpu::BindGroupEntry {
+      binding: uniform.get_binding() + 1,
+      resource: wgpu::BindingResource::Sampler(texture.get_sampler()),
+    });
+  }
+
+  entries
+}
+
+/// A bind group for `uniform`'s layout.
+fn create_bind_group(
+  uniform: &super::Uniform,
+  entries: &[wgpu::BindGroupEntry],
+  label: &'static str,
+  render_context: &crate::graphics::RenderContext,
+) -> wgpu::BindGroup {
+  render_context
+    .device
+    .create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: uniform.get_bind_group_layout(),
+      entries,
+      label: Some(label),
+    })
+}
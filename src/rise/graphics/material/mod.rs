@@ -1,11 +1,17 @@
 mod uniform;
 
-pub use uniform::{CameraUniform, Uniform, UniformBinding};
+pub use uniform::{CameraUniform, Uniform, UniformBinding, UniformValue};
 
 mod material;
 
-pub use material::{Material,  MaterialBuilder};
+pub use material::{Material,  MaterialBuilder, MaterialError};
+
+mod reflect;
+
+mod schema;
+
+pub use schema::UniformType;
 
 mod material_instance;
 
-pub use material_instance::{MaterialInstance, MaterialInstanceBuilder};
\ No newline at end of file
+pub use material_instance::{MaterialInstance, MaterialInstanceBuilder, UniformError};
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+
+const STORAGE_CLASS_INPUT: u32 = 1;
+
+/// The kind of scalar a shader input is made of.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScalarKind {
+  Float,
+  Int,
+  Uint,
+}
+
+/// A `layout(location = N) in` variable of a shader stage.
+#[derive(Copy, Clone, Debug)]
+pub struct ShaderInput {
+  pub location: u32,
+  pub kind: ScalarKind,
+  pub components: u32,
+}
+
+#[derive(Copy, Clone)]
+enum Type {
+  Scalar(ScalarKind),
+  Vector(u32, u32),
+  Pointer(u32, u32),
+}
+
+/// Reads the located input variables out of a SPIR-V module. Built in inputs
+/// and anything that isn't a scalar or vector are skipped.
+pub fn shader_inputs(spirv: &[u8]) -> Result<Vec<ShaderInput>, String> {
+  if spirv.len() % 4 != 0 || spirv.len() < 20 {
+    return Err(String::from("Shader is not valid SPIR-V"));
+  }
+
+  let words: Vec<u32> = spirv
+    .chunks(4)
+    .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    .collect();
+
+  if words[0] != SPIRV_MAGIC {
+    return Err(String::from("Shader is not valid SPIR-V"));
+  }
+
+  let mut types: HashMap<u32, Type> = HashMap::new();
+  let mut locations: HashMap<u32, u32> = HashMap::new();
+  let mut built_ins: Vec<u32> = Vec::new();
+  let mut inputs: Vec<(u32, u32)> = Vec::new();
+
+  let mut offset = 5;
+
+  while offset < words.len() {
+    let word_count = (words[offset] >> 16) as usize;
+    let opcode = words[offset] & 0xffff;
+
+    if word_count == 0 || offset + word_count > words.len() {
+      return Err(String::from("Shader is not valid SPIR-V"));
+    }
+
+    let operands = &words[offset + 1..offset + word_count];
+
+    match opcode {
+      OP_TYPE_INT if operands.len() >= 3 => {
+        let kind = if operands[2] == 1 { ScalarKind::Int } else { ScalarKind::Uint };
+        types.insert(operands[0], Type::Scalar(kind));
+      }
+      OP_TYPE_FLOAT if operands.len() >= 2 => {
+        types.insert(operands[0], Type::Scalar(ScalarKind::Float));
+      }
+      OP_TYPE_VECTOR if operands.len() >= 3 => {
+        types.insert(operands[0], Type::Vector(operands[1], operands[2]));
+      }
+      OP_TYPE_POINTER if operands.len() >= 3 => {
+        types.insert(operands[0], Type::Pointer(operands[1], operands[2]));
+      }
+      OP_VARIABLE if operands.len() >= 3 && operands[2] == STORAGE_CLASS_INPUT => {
+        inputs.push((operands[1], operands[0]));
+      }
+      OP_DECORATE if operands.len() >= 3 && operands[1] == DECORATION_LOCATION => {
+        locations.insert(operands[0], operands[2]);
+      }
+      OP_DECORATE if operands.len() >= 2 && operands[1] == DECORATION_BUILT_IN => {
+        built_ins.push(operands[0]);
+      }
+      _ => {}
+    }
+
+    offset += word_count;
+  }
+
+  let mut shader_inputs = Vec::new();
+
+  for (variable, pointer_type) in inputs {
+    if built_ins.contains(&variable) {
+      continue;
+    }
+
+    let location = match locations.get(&variable) {
+      Some(location) => *location,
+      None => continue,
+    };
+
+    let pointee = match types.get(&pointer_type) {
+      Some(Type::Pointer(_, pointee)) => *pointee,
+      _ => continue,
+    };
+
+    let (kind, components) = match types.get(&pointee) {
+      Some(Type::Scalar(kind)) => (*kind, 1),
+      Some(Type::Vector(component, count)) => match types.get(component) {
+        Some(Type::Scalar(kind)) => (*kind, *count),
+        _ => continue,
+      },
+      _ => continue,
+    };
+
+    shader_inputs.push(ShaderInput {
+      location,
+      kind,
+      components,
+    });
+  }
+
+  shader_inputs.sort_by_key(|input| input.location);
+
+  Ok(shader_inputs)
+}
+
+/// The kind of value a shader sees when reading an attribute of this format.
+pub fn format_kind(format: wgpu::VertexFormat) -> ScalarKind {
+  use wgpu::VertexFormat::*;
+
+  match format {
+    Uchar2 | Uchar4 | Ushort2 | Ushort4 | Uint | Uint2 | Uint3 | Uint4 => ScalarKind::Uint,
+    Char2 | Char4 | Short2 | Short4 | Int | Int2 | Int3 | Int4 => ScalarKind::Int,
+    _ => ScalarKind::Float,
+  }
+}
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::MaterialError;
+
+/// What a material uniform holds, given by its `type` in a `.mat` file.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UniformType {
+    Camera,
+    /// A texture and, unless `"sampler": false`, its sampler at the next
+    /// binding.
+    Texture,
+    Sampler,
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    /// A linear rgba color, laid out like a vec4.
+    Color,
+    Mat4,
+    StorageBuffer,
+    Joints,
+    MorphTargets,
+}
+
+impl UniformType {
+    /// The size of the buffer behind uniforms set with
+    /// `MaterialInstance::set_value`, with vec3s padded out to 16 bytes the
+    /// way std140 lays them out.
+    pub fn get_value_size(self) -> Option<u64> {
+        match self {
+            UniformType::Float => Some(4),
+            UniformType::Vec2 => Some(8),
+            UniformType::Vec3 | UniformType::Vec4 | UniformType::Color => Some(16),
+            UniformType::Mat4 => Some(64),
+            _ => None,
+        }
+    }
+
+    /// The uniform's `type` as it's written in a `.mat` file.
+    pub fn get_name(self) -> &'static str {
+        match self {
+            UniformType::Camera => "camera",
+            UniformType::Texture => "texture",
+            UniformType::Sampler => "sampler",
+            UniformType::Float => "float",
+            UniformType::Vec2 => "vec2",
+            UniformType::Vec3 => "vec3",
+            UniformType::Vec4 => "vec4",
+            UniformType::Color => "color",
+            UniformType::Mat4 => "mat4",
+            UniformType::StorageBuffer => "storage_buffer",
+            UniformType::Joints => "joints",
+            UniformType::MorphTargets => "morph_targets",
+        }
+    }
+
+    /// The stages that see the uniform when the file doesn't say.
+    fn default_visibility(self) -> wgpu::ShaderStage {
+        match self {
+            UniformType::Camera | UniformType::Joints | UniformType::MorphTargets => wgpu::ShaderStage::VERTEX,
+            UniformType::Texture | UniformType::Sampler => wgpu::ShaderStage::FRAGMENT,
+            _ => wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TextureDimension {
+    #[serde(rename = "2d")]
+    D2,
+    #[serde(rename = "2d_array")]
+    D2Array,
+    #[serde(rename = "3d")]
+    D3,
+    #[serde(rename = "cube")]
+    Cube,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ShaderStageName {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+/// One entry of a `.mat` file's `uniforms`. Options that don't apply to
+/// the uniform's type are rejected rather than ignored.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UniformDescriptor {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub uniform_type: UniformType,
+    /// The bind group the uniform is bound to, which defaults to its place
+    /// in the list.
+    pub set: Option<u32>,
+    /// The first binding in its group, 0 unless given.
+    pub binding: Option<u32>,
+    pub visibility: Option<Vec<ShaderStageName>>,
+    pub dimension: Option<TextureDimension>,
+    pub comparison: Option<bool>,
+    pub sampler: Option<bool>,
+    pub readonly: Option<bool>,
+}
+
+impl UniformDescriptor {
+    pub fn get_set(&self) -> u32 {
+        self.set.unwrap_or(0)
+    }
+
+    pub fn get_binding(&self) -> u32 {
+        self.binding.unwrap_or(0)
+    }
+
+    pub fn has_sampler(&self) -> bool {
+        match self.uniform_type {
+            UniformType::Texture => self.sampler.unwrap_or(true),
+            UniformType::Sampler => true,
+            _ => false,
+        }
+    }
+
+    fn get_visibility(&self) -> wgpu::ShaderStage {
+        match &self.visibility {
+            Some(stages) => stages.iter().fold(wgpu::ShaderStage::NONE, |visibility, stage| {
+                visibility
+                    | match stage {
+                        ShaderStageName::Vertex => wgpu::ShaderStage::VERTEX,
+                        ShaderStageName::Fragment => wgpu::ShaderStage::FRAGMENT,
+                        ShaderStageName::Compute => wgpu::ShaderStage::COMPUTE,
+                    }
+            }),
+            None => self.uniform_type.default_visibility(),
+        }
+    }
+
+    /// The entries of the uniform's bind group layout.
+    pub fn layout_entries(&self) -> Vec<wgpu::BindGroupLayoutEntry> {
+        let binding = self.get_binding();
+        let visibility = self.get_visibility();
+
+        let entry = |binding: u32, ty: wgpu::BindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: None,
+        };
+
+        let storage = |readonly: bool| wgpu::BindingType::StorageBuffer {
+            dynamic: false,
+            min_binding_size: None,
+            readonly,
+        };
+
+        let sampler = wgpu::BindingType::Sampler {
+            comparison: self.comparison.unwrap_or(false),
+        };
+
+        match self.uniform_type {
+            UniformType::Camera => vec![entry(binding, wgpu::BindingType::UniformBuffer {
+                dynamic: false,
+                min_binding_size: None,
+            })],
+            UniformType::Texture => {
+                // Textures are 2d unless the uniform says otherwise. Shaders
+                // sample them all as floats.
+                let dimension = match self.dimension.unwrap_or(TextureDimension::D2) {
+                    TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+                    TextureDimension::D2Array => wgpu::TextureViewDimension::D2Array,
+                    TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+                    TextureDimension::Cube => wgpu::TextureViewDimension::Cube,
+                };
+
+                let mut entries = vec![entry(binding, wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension,
+                    component_type: wgpu::TextureComponentType::Float,
+                })];
+
+                if self.has_sampler() {
+                    entries.push(entry(binding + 1, sampler));
+                }
+
+                entries
+            }
+            UniformType::Sampler => vec![entry(binding, sampler)],
+            UniformType::StorageBuffer => vec![entry(binding, storage(self.readonly.unwrap_or(true)))],
+            UniformType::Joints => vec![entry(binding, storage(true))],
+            UniformType::MorphTargets => vec![entry(binding, storage(true)), entry(binding + 1, storage(true))],
+            value_type => vec![entry(binding, wgpu::BindingType::UniformBuffer {
+                dynamic: false,
+                min_binding_size: value_type.get_value_size().and_then(wgpu::BufferSize::new),
+            })],
+        }
+    }
+
+    /// Checks the options given make sense for the uniform's type.
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err(String::from("`name` can't be empty"));
+        }
+
+        let uniform_type = self.uniform_type;
+        let is_texture = uniform_type == UniformType::Texture;
+
+        let options = [
+            ("dimension", self.dimension.is_some(), is_texture, "texture"),
+            ("sampler", self.sampler.is_some(), is_texture, "texture"),
+            (
+                "comparison",
+                self.comparison.is_some(),
+                is_texture || uniform_type == UniformType::Sampler,
+                "texture and sampler",
+            ),
+            ("readonly", self.readonly.is_some(), uniform_type == UniformType::StorageBuffer, "storage_buffer"),
+        ];
+
+        for (option, given, applies, types) in options.iter() {
+            if *given && !applies {
+                return Err(format!("`{}` only applies to {} uniforms, not {}", option, types, uniform_type.get_name()));
+            }
+        }
+
+        if is_texture && self.sampler == Some(false) && self.comparison.is_some() {
+            return Err(String::from("`comparison` needs the texture's sampler, but `sampler` is false"));
+        }
+
+        if let Some(stages) = &self.visibility {
+            if stages.is_empty() {
+                return Err(String::from("`visibility` needs at least one stage"));
+            }
+        }
+
+        if uniform_type == UniformType::Camera && self.name != "camera" {
+            return Err(String::from("camera uniforms have to be named \"camera\""));
+        }
+
+        Ok(())
+    }
+}
+
+/// The contents of a `.mat` file.
+#[derive(Debug)]
+pub(crate) struct MaterialFile {
+    pub vertex_stage: PathBuf,
+    pub fragment_stage: PathBuf,
+    pub uniforms: Vec<UniformDescriptor>,
+}
+
+/// A `.mat` file with its uniforms still unread, so that errors in them can
+/// say which uniform they're in.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMaterialFile {
+    vertex_stage: PathBuf,
+    fragment_stage: PathBuf,
+    #[serde(default)]
+    uniforms: Vec<serde_json::Value>,
+}
+
+impl MaterialFile {
+    /// Reads and checks a material file. Its uniforms come back in set
+    /// order, each with its set filled in.
+    pub fn load(path: &Path) -> Result<MaterialFile, MaterialError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| MaterialError::new(&format!("{:?} couldn't be read: {}", path, error)))?;
+
+        let raw: RawMaterialFile = serde_json::from_str(&source)
+            .map_err(|error| MaterialError::new(&format!("{:?}: {}", path, error)))?;
+
+        let uniforms = raw
+            .uniforms
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let name = value.get("name").and_then(|name| name.as_str()).unwrap_or("").to_string();
+
+                serde_json::from_value(value).map_err(|error| {
+                    MaterialError::new(&format!("{:?}: uniforms[{}] ({:?}): {}", path, index, name, error))
+                })
+            })
+            .collect::<Result<Vec<UniformDescriptor>, MaterialError>>()?;
+
+        let mut material_file = MaterialFile {
+            vertex_stage: raw.vertex_stage,
+            fragment_stage: raw.fragment_stage,
+            uniforms,
+        };
+
+        let count = material_file.uniforms.len() as u32;
+        let mut names: HashMap<&str, usize> = HashMap::new();
+        let mut sets: HashMap<u32, usize> = HashMap::new();
+
+        for (index, uniform) in material_file.uniforms.iter().enumerate() {
+            let error = |message: String| {
+                MaterialError::new(&format!("{:?}: uniforms[{}] ({:?}): {}", path, index, uniform.name, message))
+            };
+
+            uniform.validate().map_err(error)?;
+
+            if let Some(other) = names.insert(&uniform.name, index) {
+                return Err(error(format!("`name` is already used by uniforms[{}]", other)));
+            }
+
+            let set = uniform.set.unwrap_or(index as u32);
+
+            if set >= count {
+                return Err(error(format!(
+                    "`set` {} is past the last set, {}, but sets have to run from 0 without gaps",
+                    set, count - 1
+                )));
+            }
+
+            if let Some(other) = sets.insert(set, index) {
+                return Err(error(format!(
+                    "`set` {} is already used by uniforms[{}] ({:?}), but each uniform has its own bind group",
+                    set, other, material_file.uniforms[other].name
+                )));
+            }
+        }
+
+        let has_camera = matches!(
+            names.get("camera"),
+            Some(index) if material_file.uniforms[*index].uniform_type == UniformType::Camera
+        );
+
+        if !has_camera {
+            return Err(MaterialError::new(&format!(
+                "{:?}: materials need a camera uniform named \"camera\"",
+                path
+            )));
+        }
+
+        for (index, uniform) in material_file.uniforms.iter_mut().enumerate() {
+            uniform.set = Some(uniform.set.unwrap_or(index as u32));
+        }
+
+        material_file.uniforms.sort_by_key(|uniform| uniform.get_set());
+
+        Ok(material_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{MaterialFile, UniformDescriptor, UniformType};
+
+    /// Writes `uniforms` into a material file named `name` in the temp
+    /// directory.
+    fn write_material(name: &str, uniforms: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rise_schema_{}.mat", name));
+
+        let source = format!(
+            r#"{{ "vertex_stage": "a.vert.spv", "fragment_stage": "a.frag.spv", "uniforms": [{}] }}"#,
+            uniforms
+        );
+
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    /// The error loading a material with `uniforms`, checking it names the
+    /// file.
+    fn load_error(name: &str, uniforms: &str) -> String {
+        let path = write_material(name, uniforms);
+        let error = MaterialFile::load(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(error.contains(&format!("{:?}", path)), "{} doesn't name the file", error);
+        error
+    }
+
+    const CAMERA: &str = r#"{ "type": "camera", "name": "camera" }"#;
+
+    #[test]
+    fn loads_the_bundled_materials() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("res/mat");
+
+        for (file, last) in [
+            ("standard.mat", UniformType::Texture),
+            ("pbr_normal.mat", UniformType::Texture),
+            ("skinned.mat", UniformType::Joints),
+            ("morph.mat", UniformType::MorphTargets),
+        ]
+        .iter()
+        {
+            let material = MaterialFile::load(&directory.join(file)).unwrap();
+
+            assert_eq!(material.uniforms[0].uniform_type, UniformType::Camera, "{}", file);
+            assert_eq!(material.uniforms.last().unwrap().uniform_type, *last, "{}", file);
+
+            for (set, uniform) in material.uniforms.iter().enumerate() {
+                assert_eq!(uniform.get_set(), set as u32, "{}", file);
+            }
+        }
+    }
+
+    #[test]
+    fn textures_of_every_dimension_are_sampled_as_floats() {
+        for dimension in ["2d", "2d_array", "3d", "cube"].iter() {
+            let source = format!(r#"{{ "type": "texture", "name": "diffuse", "dimension": "{}" }}"#, dimension);
+            let uniform: UniformDescriptor = serde_json::from_str(&source).unwrap();
+
+            assert!(
+                matches!(
+                    uniform.layout_entries()[0].ty,
+                    wgpu::BindingType::SampledTexture { component_type: wgpu::TextureComponentType::Float, .. }
+                ),
+                "{} textures should be float", dimension
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = load_error(
+            "unknown_field",
+            &format!(r#"{}, {{ "type": "texture", "name": "diffuse", "filter": "linear" }}"#, CAMERA),
+        );
+
+        assert!(error.contains("uniforms[1] (\"diffuse\")"), "{}", error);
+        assert!(error.contains("filter"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let error = load_error(
+            "duplicate_name",
+            &format!(
+                r#"{}, {{ "type": "texture", "name": "diffuse" }}, {{ "type": "sampler", "name": "diffuse" }}"#,
+                CAMERA
+            ),
+        );
+
+        assert!(error.contains("uniforms[2] (\"diffuse\")"), "{}", error);
+        assert!(error.contains("already used by uniforms[1]"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_gaps_between_sets() {
+        let error = load_error(
+            "set_gap",
+            &format!(r#"{}, {{ "type": "texture", "name": "diffuse", "set": 2 }}"#, CAMERA),
+        );
+
+        assert!(error.contains("uniforms[1] (\"diffuse\")"), "{}", error);
+        assert!(error.contains("without gaps"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_duplicate_sets() {
+        let error = load_error(
+            "duplicate_set",
+            &format!(r#"{}, {{ "type": "texture", "name": "diffuse", "set": 0 }}"#, CAMERA),
+        );
+
+        assert!(error.contains("uniforms[1] (\"diffuse\")"), "{}", error);
+        assert!(error.contains("already used by uniforms[0] (\"camera\")"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_missing_camera() {
+        let error = load_error("missing_camera", r#"{ "type": "texture", "name": "diffuse" }"#);
+
+        assert!(error.contains("camera uniform"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_camera_with_another_name() {
+        let error = load_error("camera_name", r#"{ "type": "camera", "name": "view" }"#);
+
+        assert!(error.contains("uniforms[0] (\"view\")"), "{}", error);
+    }
+}
@@ -28,13 +28,25 @@ unsafe impl bytemuck::Pod for CameraUniform {}
 
 pub struct Uniform {
     bind_group_layout: wgpu::BindGroupLayout,
-    name: String
+    name: String,
+    uniform_type: super::UniformType,
+    binding: u32,
+    sampler: bool
 }
 
 impl Uniform {
-  pub fn new(name: &str, bind_group_layout: wgpu::BindGroupLayout) -> Self {
+  pub fn new(
+    name: &str,
+    uniform_type: super::UniformType,
+    binding: u32,
+    sampler: bool,
+    bind_group_layout: wgpu::BindGroupLayout
+  ) -> Self {
     Uniform {
       name: String::from(name),
+      uniform_type,
+      binding,
+      sampler,
       bind_group_layout
     }
   }
@@ -46,23 +58,93 @@ impl Uniform {
   pub fn get_name(&self) -> &str {
     &self.name
   }
+
+  pub fn get_type(&self) -> super::UniformType {
+    self.uniform_type
+  }
+
+  /// The binding of the uniform's first entry in its bind group.
+  pub fn get_binding(&self) -> u32 {
+    self.binding
+  }
+
+  /// Whether a texture uniform has a sampler at the binding after it.
+  pub fn has_sampler(&self) -> bool {
+    self.sampler
+  }
+}
+
+/// A value for a `float`, `vec2`, `vec3`, `vec4`, `color` or `mat4` uniform.
+#[derive(Copy, Clone, Debug)]
+pub enum UniformValue {
+  Float(f32),
+  Vec2(cgmath::Vector2<f32>),
+  Vec3(cgmath::Vector3<f32>),
+  Vec4(cgmath::Vector4<f32>),
+  Color(wgpu::Color),
+  Mat4(cgmath::Matrix4<f32>)
+}
+
+impl UniformValue {
+  pub fn get_type(&self) -> super::UniformType {
+    match self {
+      UniformValue::Float(_) => super::UniformType::Float,
+      UniformValue::Vec2(_) => super::UniformType::Vec2,
+      UniformValue::Vec3(_) => super::UniformType::Vec3,
+      UniformValue::Vec4(_) => super::UniformType::Vec4,
+      UniformValue::Color(_) => super::UniformType::Color,
+      UniformValue::Mat4(_) => super::UniformType::Mat4,
+    }
+  }
+
+  /// The value laid out for a uniform buffer, with vec3s padded to 16 bytes.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let floats: Vec<f32> = match *self {
+      UniformValue::Float(x) => vec![x],
+      UniformValue::Vec2(v) => vec![v.x, v.y],
+      UniformValue::Vec3(v) => vec![v.x, v.y, v.z, 0.],
+      UniformValue::Vec4(v) => vec![v.x, v.y, v.z, v.w],
+      UniformValue::Color(c) => vec![c.r as f32, c.g as f32, c.b as f32, c.a as f32],
+      UniformValue::Mat4(m) => {
+        let columns: [[f32; 4]; 4] = m.into();
+
+        columns.iter().flatten().copied().collect()
+      }
+    };
+
+    bytemuck::cast_slice(&floats).to_vec()
+  }
 }
 
 pub struct UniformBinding {
-  bind_group: wgpu::BindGroup,
+  bind_groups: Vec<wgpu::BindGroup>,
+  current: Option<std::rc::Rc<std::cell::Cell<usize>>>,
   buffer: Option<wgpu::Buffer>
 }
 
 impl UniformBinding {
   pub fn new(bind_group: wgpu::BindGroup, buffer: Option<wgpu::Buffer>) -> Self {
     UniformBinding {
-      bind_group,
+      bind_groups: vec![bind_group],
+      current: None,
       buffer
     }
   }
 
+  /// A binding that switches between bind groups, using whichever one
+  /// `current` points at when it's drawn.
+  pub fn new_switching(bind_groups: Vec<wgpu::BindGroup>, current: std::rc::Rc<std::cell::Cell<usize>>) -> Self {
+    UniformBinding {
+      bind_groups,
+      current: Some(current),
+      buffer: None
+    }
+  }
+
   pub fn get_bind_group(&self) -> &wgpu::BindGroup {
-    &self.bind_group
+    let index = self.current.as_ref().map_or(0, |current| current.get());
+
+    &self.bind_groups[index]
   }
 
   pub fn get_buffer(&self) -> Option<&wgpu::Buffer> {
@@ -0,0 +1,510 @@
+use log::{warn};
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use super::{Aabb, BoundingSphere, Mesh, MeshError, Submesh, Vertex};
+
+const MAGIC: [u8; 4] = *b"RMSH";
+
+/// Bumped whenever the layout changes, so old caches are rebuilt rather than
+/// misread.
+pub const MESH_BINARY_VERSION: u32 = 1;
+
+/// Added to a source file's name to get its cache, e.g. `model.obj.rmesh`.
+pub const MESH_CACHE_EXTENSION: &str = "rmesh";
+
+/// The vertex and index blobs start on this alignment, so they can be viewed
+/// in place when the file is loaded or mapped at an aligned address.
+const BLOB_ALIGNMENT: usize = 16;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Header {
+    magic: [u8; 4],
+    version: u32,
+    vertex_stride: u32,
+    attribute_count: u32,
+    vertex_count: u32,
+    index_count: u32,
+    /// 0 to choose on upload, 1 for `Uint16` and 2 for `Uint32`.
+    index_format: u32,
+    submesh_count: u32,
+    has_bounds: u32,
+    bounds_min: [f32; 3],
+    bounds_max: [f32; 3],
+    sphere: [f32; 4],
+    vertex_offset: u32,
+    index_offset: u32,
+}
+
+unsafe impl bytemuck::Zeroable for Header {}
+unsafe impl bytemuck::Pod for Header {}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct AttributeRecord {
+    offset: u32,
+    location: u32,
+    format: u32,
+}
+
+unsafe impl bytemuck::Zeroable for AttributeRecord {}
+unsafe impl bytemuck::Pod for AttributeRecord {}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SubmeshRecord {
+    start: u32,
+    end: u32,
+    /// Where the name's utf-8 bytes are, from the start of the file.
+    name_offset: u32,
+    name_length: u32,
+}
+
+unsafe impl bytemuck::Zeroable for SubmeshRecord {}
+unsafe impl bytemuck::Pod for SubmeshRecord {}
+
+/// A mesh read from the binary format written by `Mesh::save_binary`.
+///
+/// The file is a header, the vertex layout, the submesh ranges and their
+/// names, then the raw vertices and `u32` indices, all in the machine's byte
+/// order. When the bytes are suitably aligned the vertices and indices are
+/// borrowed rather than copied, so a memory-mapped file can be uploaded
+/// straight to the gpu.
+///
+/// The layout must match `V` exactly, which is checked when parsing. Morph
+/// targets aren't stored.
+pub struct BinaryMesh<'a, V: Vertex> {
+    vertices: Cow<'a, [V]>,
+    indices: Cow<'a, [u32]>,
+    index_format: Option<wgpu::IndexFormat>,
+    submeshes: Vec<Submesh>,
+    bounds: Option<(Aabb, BoundingSphere)>,
+}
+
+impl<'a, V: Vertex> BinaryMesh<'a, V> {
+    pub fn parse(bytes: &'a [u8]) -> Result<BinaryMesh<'a, V>, MeshError> {
+        let header = records::<Header>(bytes, 0, 1)?[0];
+
+        if header.magic != MAGIC {
+            return Err(MeshError::new("Data is not a binary mesh"));
+        }
+
+        if header.version != MESH_BINARY_VERSION {
+            return Err(MeshError::new(&format!(
+                "Binary mesh is version {}, but version {} is expected", header.version, MESH_BINARY_VERSION
+            )));
+        }
+
+        let mut offset = std::mem::size_of::<Header>();
+
+        let attributes = records::<AttributeRecord>(bytes, offset, header.attribute_count as usize)?;
+        offset += std::mem::size_of_val(&attributes[..]);
+
+        let matches_layout = header.vertex_stride as usize == std::mem::size_of::<V>()
+            && attributes.len() == V::ATTRIBUTES.len()
+            && attributes.iter().zip(V::ATTRIBUTES).all(|(record, attribute)| {
+                record.offset as u64 == attribute.offset
+                    && record.location == attribute.shader_location
+                    && record.format == attribute.format as u32
+            });
+
+        if !matches_layout {
+            return Err(MeshError::new("Binary mesh's vertex layout doesn't match the vertex type"));
+        }
+
+        let mut submeshes = Vec::with_capacity(header.submesh_count as usize);
+
+        for record in records::<SubmeshRecord>(bytes, offset, header.submesh_count as usize)?.iter() {
+            let name = records::<u8>(bytes, record.name_offset as usize, record.name_length as usize)?;
+            let name = std::str::from_utf8(&name)
+                .map_err(|_| MeshError::new("Binary mesh has a submesh name that isn't valid utf-8"))?;
+
+            if record.start > record.end || record.end > header.index_count {
+                return Err(MeshError::new(&format!("Binary mesh's submesh {} is out of range", name)));
+            }
+
+            submeshes.push(Submesh::new(name, record.start..record.end));
+        }
+
+        let vertices = records::<V>(bytes, header.vertex_offset as usize, header.vertex_count as usize)?;
+        let indices = records::<u32>(bytes, header.index_offset as usize, header.index_count as usize)?;
+
+        if indices.iter().any(|index| *index >= header.vertex_count) {
+            return Err(MeshError::new("Binary mesh has an index out of range"));
+        }
+
+        let index_format = match header.index_format {
+            0 => None,
+            1 => Some(wgpu::IndexFormat::Uint16),
+            2 => Some(wgpu::IndexFormat::Uint32),
+            other => return Err(MeshError::new(&format!("Binary mesh has unknown index format {}", other))),
+        };
+
+        let bounds = if header.has_bounds != 0 {
+            let sphere = header.sphere;
+
+            Some((
+                Aabb::new(header.bounds_min.into(), header.bounds_max.into()),
+                BoundingSphere::new(cgmath::Vector3::new(sphere[0], sphere[1], sphere[2]), sphere[3]),
+            ))
+        } else {
+            None
+        };
+
+        Ok(BinaryMesh {
+            vertices,
+            indices,
+            index_format,
+            submeshes,
+            bounds,
+        })
+    }
+
+    pub fn get_vertices(&self) -> &[V] {
+        &self.vertices
+    }
+
+    pub fn get_indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn get_submeshes(&self) -> &Vec<Submesh> {
+        &self.submeshes
+    }
+
+    pub fn get_bounds(&self) -> Option<Aabb> {
+        self.bounds.map(|(bounds, _)| bounds)
+    }
+
+    pub fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.bounds.map(|(_, sphere)| sphere)
+    }
+
+    /// Whether the vertices and indices are viewed in place rather than
+    /// copied out of misaligned bytes.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.vertices, Cow::Borrowed(_)) && matches!(self.indices, Cow::Borrowed(_))
+    }
+
+    pub fn into_mesh(self) -> Mesh<V> {
+        let mut mesh = Mesh::new();
+
+        mesh.vertices = self.vertices.into_owned();
+        mesh.indices = self.indices.into_owned();
+        mesh.index_format = self.index_format;
+        mesh.submeshes = self.submeshes;
+
+        if let Some((bounds, sphere)) = self.bounds {
+            mesh.bounds = Some(bounds);
+            mesh.bounding_sphere = Some(sphere);
+        }
+
+        mesh
+    }
+}
+
+impl<V: Vertex> Mesh<V> {
+    /// Encodes the mesh in the binary format read by `BinaryMesh`.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let attribute_size = std::mem::size_of::<AttributeRecord>() * V::ATTRIBUTES.len();
+        let submesh_size = std::mem::size_of::<SubmeshRecord>() * self.submeshes.len();
+
+        let names_offset = std::mem::size_of::<Header>() + attribute_size + submesh_size;
+        let names_size: usize = self.submeshes.iter().map(|submesh| submesh.name.len()).sum();
+
+        let vertex_offset = align(names_offset + names_size, BLOB_ALIGNMENT);
+        let vertex_data: &[u8] = bytemuck::cast_slice(&self.vertices[..]);
+
+        let index_offset = align(vertex_offset + vertex_data.len(), BLOB_ALIGNMENT);
+        let index_data: &[u8] = bytemuck::cast_slice(&self.indices[..]);
+
+        let positions = self.vertices.iter().map(|vertex| vertex.position());
+        let bounds = Aabb::from_points(positions.clone()).zip(BoundingSphere::from_points(positions));
+
+        let header = Header {
+            magic: MAGIC,
+            version: MESH_BINARY_VERSION,
+            vertex_stride: std::mem::size_of::<V>() as u32,
+            attribute_count: V::ATTRIBUTES.len() as u32,
+            vertex_count: self.vertices.len() as u32,
+            index_count: self.indices.len() as u32,
+            index_format: match self.index_format {
+                None => 0,
+                Some(wgpu::IndexFormat::Uint16) => 1,
+                Some(wgpu::IndexFormat::Uint32) => 2,
+            },
+            submesh_count: self.submeshes.len() as u32,
+            has_bounds: bounds.is_some() as u32,
+            bounds_min: bounds.map_or([0.; 3], |(bounds, _)| bounds.min.into()),
+            bounds_max: bounds.map_or([0.; 3], |(bounds, _)| bounds.max.into()),
+            sphere: bounds.map_or([0.; 4], |(_, sphere)| sphere.center.extend(sphere.radius).into()),
+            vertex_offset: vertex_offset as u32,
+            index_offset: index_offset as u32,
+        };
+
+        let mut data = Vec::with_capacity(index_offset + index_data.len());
+        data.extend_from_slice(bytemuck::bytes_of(&header));
+
+        for attribute in V::ATTRIBUTES {
+            data.extend_from_slice(bytemuck::bytes_of(&AttributeRecord {
+                offset: attribute.offset as u32,
+                location: attribute.shader_location,
+                format: attribute.format as u32,
+            }));
+        }
+
+        let mut name_offset = names_offset;
+
+        for submesh in &self.submeshes {
+            data.extend_from_slice(bytemuck::bytes_of(&SubmeshRecord {
+                start: submesh.indices.start,
+                end: submesh.indices.end,
+                name_offset: name_offset as u32,
+                name_length: submesh.name.len() as u32,
+            }));
+
+            name_offset += submesh.name.len();
+        }
+
+        for submesh in &self.submeshes {
+            data.extend_from_slice(submesh.name.as_bytes());
+        }
+
+        data.resize(vertex_offset, 0);
+        data.extend_from_slice(vertex_data);
+        data.resize(index_offset, 0);
+        data.extend_from_slice(index_data);
+
+        data
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Mesh<V>, MeshError> {
+        BinaryMesh::parse(bytes).map(BinaryMesh::into_mesh)
+    }
+
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, self.to_binary())?;
+
+        Ok(())
+    }
+
+    pub fn load_binary<P: AsRef<Path> + core::fmt::Debug>(path: P) -> Result<Mesh<V>, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(&path)?;
+
+        Mesh::from_binary(&bytes).map_err(|e| Box::new(MeshError::new(&format!("{:?}: {}", path, e))).into())
+    }
+}
+
+impl Mesh {
+    /// Loads an OBJ file through its binary cache, which sits next to it with
+    /// `MESH_CACHE_EXTENSION` added to its name. The cache is rebuilt when
+    /// it's missing, older than the source, or from another format version.
+    /// Failing to write the cache is only logged.
+    pub fn load_cached<P: AsRef<Path> + core::fmt::Debug>(path: P) -> Result<Mesh, Box<dyn std::error::Error>> {
+        let cache_path = mesh_cache_path(path.as_ref());
+
+        if is_cache_fresh(path.as_ref(), &cache_path) {
+            match Mesh::load_binary(&cache_path) {
+                Ok(mesh) => return Ok(mesh),
+                Err(e) => warn!("Rebuilding mesh cache: {}", e),
+            }
+        }
+
+        let mesh = Mesh::load_from_file(&path)?;
+
+        if let Err(e) = mesh.save_binary(&cache_path) {
+            warn!("Unable to write mesh cache {:?}: {}", cache_path, e);
+        }
+
+        Ok(mesh)
+    }
+}
+
+/// Where the binary cache for a source asset is kept.
+pub fn mesh_cache_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(MESH_CACHE_EXTENSION);
+
+    PathBuf::from(name)
+}
+
+/// Whether the cache exists and is at least as new as its source. A cache
+/// shipped without its source is always used.
+fn is_cache_fresh(source: &Path, cache: &Path) -> bool {
+    let cache_modified = match std::fs::metadata(cache).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return false,
+    };
+
+    match std::fs::metadata(source).and_then(|metadata| metadata.modified()) {
+        Ok(source_modified) => cache_modified >= source_modified,
+        Err(_) => true,
+    }
+}
+
+/// Views `count` records at `offset`, copying them out only when the bytes
+/// aren't aligned for `T`.
+fn records<T: bytemuck::Pod>(bytes: &[u8], offset: usize, count: usize) -> Result<Cow<'_, [T]>, MeshError> {
+    let end = count
+        .checked_mul(std::mem::size_of::<T>())
+        .and_then(|size| size.checked_add(offset))
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| MeshError::new("Binary mesh is truncated"))?;
+
+    let bytes = &bytes[offset..end];
+
+    match bytemuck::try_cast_slice(bytes) {
+        Ok(records) => Ok(Cow::Borrowed(records)),
+        Err(_) => {
+            let mut records = vec![<T as bytemuck::Zeroable>::zeroed(); count];
+            bytemuck::cast_slice_mut(&mut records[..]).copy_from_slice(bytes);
+
+            Ok(Cow::Owned(records))
+        }
+    }
+}
+
+fn align(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) / alignment * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Vector2, Vector3};
+
+    use super::{AttributeRecord, BinaryMesh, Header, SubmeshRecord};
+    use crate::graphics::{Aabb, Mesh, SkinnedVertex, StandardVertex, Submesh, Vertex};
+
+    fn quad() -> Mesh {
+        let mut mesh = Mesh::new();
+
+        for (x, y) in [(0., 0.), (2., 0.), (2., 1.), (0., 1.)].iter() {
+            mesh.add_vertex(StandardVertex::new(Vector3::new(*x, *y, 0.), Vector3::new(0., 0., 1.), Vector2::new(*x, *y)));
+        }
+
+        mesh.set_indices(vec![0, 1, 2, 0, 2, 3]);
+        mesh.set_index_format(Some(wgpu::IndexFormat::Uint32));
+        mesh.add_submesh(Submesh::new("first", 0..3)).unwrap();
+        mesh.add_submesh(Submesh::new("second", 3..6)).unwrap();
+        mesh
+    }
+
+    /// Copies `bytes` to an address `shift` bytes past a 16 byte boundary.
+    fn at_alignment(bytes: &[u8], shift: usize) -> (Vec<u128>, usize) {
+        let mut storage = vec![0u128; (bytes.len() + shift + 15) / 16];
+        bytemuck::cast_slice_mut::<u128, u8>(&mut storage)[shift..shift + bytes.len()].copy_from_slice(bytes);
+
+        (storage, shift)
+    }
+
+    fn vertex_bytes<V: Vertex>(vertices: &[V]) -> &[u8] {
+        bytemuck::cast_slice(vertices)
+    }
+
+    /// The error parsing `bytes` as standard vertices.
+    fn parse_error(bytes: &[u8]) -> String {
+        let (storage, shift) = at_alignment(bytes, 0);
+        let bytes = &bytemuck::cast_slice::<u128, u8>(&storage)[shift..shift + bytes.len()];
+
+        match BinaryMesh::<StandardVertex>::parse(bytes) {
+            Ok(_) => panic!("Parsing should have failed"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+    }
+
+    #[test]
+    fn round_trips_vertices_indices_submeshes_and_bounds() {
+        let mesh = quad();
+        let bytes = mesh.to_binary();
+
+        let (storage, shift) = at_alignment(&bytes, 0);
+        let binary = BinaryMesh::<StandardVertex>::parse(&bytemuck::cast_slice::<u128, u8>(&storage)[shift..shift + bytes.len()]).unwrap();
+
+        assert!(binary.is_borrowed());
+        assert_eq!(vertex_bytes(binary.get_vertices()), vertex_bytes(&mesh.vertices));
+        assert_eq!(binary.get_indices(), &mesh.indices[..]);
+        assert_eq!(binary.get_submeshes(), mesh.get_submeshes());
+        assert_eq!(binary.get_bounds(), Some(Aabb::new(Vector3::new(0., 0., 0.), Vector3::new(2., 1., 0.))));
+        assert!(binary.get_bounding_sphere().is_some());
+
+        let (bounds, sphere) = (binary.get_bounds(), binary.get_bounding_sphere());
+        let loaded = binary.into_mesh();
+
+        assert_eq!(loaded.get_index_format(), wgpu::IndexFormat::Uint32);
+        assert_eq!(loaded.get_bounds(), bounds);
+        assert_eq!(loaded.get_bounding_sphere(), sphere);
+    }
+
+    #[test]
+    fn copies_misaligned_bytes() {
+        let mesh = quad();
+        let bytes = mesh.to_binary();
+
+        let (storage, shift) = at_alignment(&bytes, 1);
+        let binary = BinaryMesh::<StandardVertex>::parse(&bytemuck::cast_slice::<u128, u8>(&storage)[shift..shift + bytes.len()]).unwrap();
+
+        assert!(!binary.is_borrowed());
+        assert_eq!(vertex_bytes(binary.get_vertices()), vertex_bytes(&mesh.vertices));
+        assert_eq!(binary.get_indices(), &mesh.indices[..]);
+        assert_eq!(binary.get_submeshes(), mesh.get_submeshes());
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = quad().to_binary();
+
+        for length in [0, 3, std::mem::size_of::<Header>() - 1, std::mem::size_of::<Header>() + 5, bytes.len() - 1].iter() {
+            assert!(parse_error(&bytes[..*length]).contains("truncated"), "{} bytes", length);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_versions() {
+        let mut bytes = quad().to_binary();
+        bytes[0] = b'X';
+        assert!(parse_error(&bytes).contains("not a binary mesh"));
+
+        let mut bytes = quad().to_binary();
+        write_u32(&mut bytes, 4, super::MESH_BINARY_VERSION + 1);
+        assert!(parse_error(&bytes).contains("version"));
+    }
+
+    #[test]
+    fn rejects_another_vertex_layout() {
+        let bytes = quad().to_binary();
+
+        assert!(Mesh::<SkinnedVertex>::from_binary(&bytes).is_err());
+
+        let mut bytes = bytes;
+        // The first attribute's shader location.
+        write_u32(&mut bytes, std::mem::size_of::<Header>() + 4, 7);
+        assert!(parse_error(&bytes).contains("layout"));
+    }
+
+    #[test]
+    fn rejects_indices_and_submeshes_out_of_range() {
+        let mut bytes = quad().to_binary();
+        let last_index = bytes.len() - 4;
+        write_u32(&mut bytes, last_index, 4);
+        assert!(parse_error(&bytes).contains("index out of range"));
+
+        // The end of the second submesh, past the 6 indices.
+        let submeshes = std::mem::size_of::<Header>() + std::mem::size_of::<AttributeRecord>() * StandardVertex::ATTRIBUTES.len();
+        let mut bytes = quad().to_binary();
+        write_u32(&mut bytes, submeshes + std::mem::size_of::<SubmeshRecord>() + 4, 7);
+        assert!(parse_error(&bytes).contains("second is out of range"));
+
+        // A name past the end of the data.
+        let mut bytes = quad().to_binary();
+        let length = bytes.len() as u32;
+        write_u32(&mut bytes, submeshes + 8, length);
+        assert!(parse_error(&bytes).contains("truncated"));
+    }
+}
@@ -0,0 +1,308 @@
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+
+/// An axis aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// The smallest box containing every point, or `None` if there are none.
+    pub fn from_points<I: IntoIterator<Item = Vector3<f32>>>(points: I) -> Option<Aabb> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Aabb::new(first, first), |aabb, p| aabb.including(p)))
+    }
+
+    /// This box grown to contain `point`.
+    pub fn including(&self, point: Vector3<f32>) -> Aabb {
+        Aabb {
+            min: Vector3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max: Vector3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        }
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        self.including(other.min).including(other.max)
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// Half the size of the box along each axis.
+    pub fn extents(&self) -> Vector3<f32> {
+        self.size() / 2.
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+
+        [
+            Vector3::new(a.x, a.y, a.z),
+            Vector3::new(b.x, a.y, a.z),
+            Vector3::new(a.x, b.y, a.z),
+            Vector3::new(b.x, b.y, a.z),
+            Vector3::new(a.x, a.y, b.z),
+            Vector3::new(b.x, a.y, b.z),
+            Vector3::new(a.x, b.y, b.z),
+            Vector3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    /// The distance along the ray to where it enters the box, or `None` if
+    /// it misses. Rays starting inside the box hit at 0.
+    pub fn intersect_ray(&self, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<f32> {
+        let mut near = f32::NEG_INFINITY;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis].abs() < f32::EPSILON {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (self.min[axis] - origin[axis]) / direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) / direction[axis];
+
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+
+        if near > far || far < 0. {
+            None
+        } else {
+            Some(near.max(0.))
+        }
+    }
+
+    /// The axis aligned box containing this box after it's been transformed
+    /// by `matrix`.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Aabb {
+        let center = matrix * self.center().extend(1.);
+        let extents = self.extents();
+
+        // Each world axis extent is the sum of the box extents projected onto it.
+        let mut world_extents = Vector3::new(0., 0., 0.);
+
+        for row in 0..3 {
+            world_extents[row] = matrix[0][row].abs() * extents.x
+                + matrix[1][row].abs() * extents.y
+                + matrix[2][row].abs() * extents.z;
+        }
+
+        let center = center.truncate();
+
+        Aabb::new(center - world_extents, center + world_extents)
+    }
+
+    pub fn transformed(&self, transform: &crate::core::Transform) -> Aabb {
+        self.transform(&transform.build_transform_matrix())
+    }
+}
+
+/// A bounding sphere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> BoundingSphere {
+        BoundingSphere { center, radius }
+    }
+
+    /// A sphere around the center of the points' bounding box, just large
+    /// enough to contain all of them.
+    pub fn from_points<I: IntoIterator<Item = Vector3<f32>> + Clone>(points: I) -> Option<BoundingSphere> {
+        let center = Aabb::from_points(points.clone())?.center();
+
+        let radius = covering_radius(points.into_iter().map(|p| (p - center).magnitude2()).fold(0., f32::max));
+
+        Some(BoundingSphere::new(center, radius))
+    }
+
+    /// This sphere grown, about the same center, to contain `point`.
+    pub fn including(&self, point: Vector3<f32>) -> BoundingSphere {
+        BoundingSphere::new(self.center, self.radius.max(covering_radius((point - self.center).magnitude2())))
+    }
+
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        (point - self.center).magnitude2() <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &BoundingSphere) -> bool {
+        let radii = self.radius + other.radius;
+
+        (other.center - self.center).magnitude2() <= radii * radii
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let closest = Vector3::new(
+            self.center.x.max(aabb.min.x).min(aabb.max.x),
+            self.center.y.max(aabb.min.y).min(aabb.max.y),
+            self.center.z.max(aabb.min.z).min(aabb.max.z),
+        );
+
+        self.contains_point(closest)
+    }
+
+    /// The distance along the ray to where it enters the sphere, or `None` if
+    /// it misses. `direction` must be normalized.
+    pub fn intersect_ray(&self, origin: Vector3<f32>, direction: Vector3<f32>) -> Option<f32> {
+        let to_center = self.center - origin;
+        let along = to_center.dot(direction);
+        let distance2 = to_center.magnitude2() - along * along;
+        let radius2 = self.radius * self.radius;
+
+        if distance2 > radius2 {
+            return None;
+        }
+
+        let half_chord = (radius2 - distance2).sqrt();
+
+        if along + half_chord < 0. {
+            None
+        } else {
+            Some((along - half_chord).max(0.))
+        }
+    }
+
+    /// The sphere after it's been transformed by `matrix`. The radius grows
+    /// by the largest scale along any axis.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> BoundingSphere {
+        let center = (matrix * self.center.extend(1.)).truncate();
+
+        let scale = [matrix.x, matrix.y, matrix.z]
+            .iter()
+            .map(|axis: &Vector4<f32>| axis.truncate().magnitude())
+            .fold(0., f32::max);
+
+        BoundingSphere::new(center, self.radius * scale)
+    }
+
+    pub fn transformed(&self, transform: &crate::core::Transform) -> BoundingSphere {
+        self.transform(&transform.build_transform_matrix())
+    }
+}
+
+/// The square root of `distance2`, rounded up so that squaring it again
+/// doesn't come out short of `distance2`.
+fn covering_radius(distance2: f32) -> f32 {
+    distance2.sqrt() * (1. + 2. * f32::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Matrix4, Vector3};
+
+    use super::{Aabb, BoundingSphere};
+
+    /// Whether `a` and `b` are equal to within rounding.
+    fn near(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5 && (a.z - b.z).abs() < 1e-5
+    }
+
+    fn points() -> Vec<Vector3<f32>> {
+        vec![Vector3::new(1., 2., 3.), Vector3::new(-1., 0., 5.), Vector3::new(3., -2., 4.)]
+    }
+
+    #[test]
+    fn boxes_and_spheres_from_points() {
+        let aabb = Aabb::from_points(points()).unwrap();
+        assert_eq!(aabb, Aabb::new(Vector3::new(-1., -2., 3.), Vector3::new(3., 2., 5.)));
+
+        let sphere = BoundingSphere::from_points(points()).unwrap();
+        assert_eq!(sphere.center, aabb.center());
+        assert!(points().into_iter().all(|point| sphere.contains_point(point)));
+        // Only just large enough, so the farthest point is on the surface.
+        assert!(points().into_iter().any(|point| sphere.including(point * 1.01).radius > sphere.radius));
+
+        assert_eq!(Aabb::from_points(Vec::new()), None);
+        assert_eq!(BoundingSphere::from_points(Vec::new()), None);
+    }
+
+    #[test]
+    fn transformed_boxes_contain_the_transformed_corners() {
+        let aabb = Aabb::new(Vector3::new(0., 0., 0.), Vector3::new(2., 1., 1.));
+        let matrix = Matrix4::from_translation(Vector3::new(5., 0., 0.)) * Matrix4::from_angle_y(Deg(90.));
+
+        let transformed = aabb.transform(&matrix);
+        assert!(near(transformed.min, Vector3::new(5., 0., -2.)));
+        assert!(near(transformed.max, Vector3::new(6., 1., 0.)));
+
+        // A 45 degree turn touches the turned corners.
+        let matrix = Matrix4::from_angle_z(Deg(45.));
+        let turned = aabb.transform(&matrix);
+        let corners: Vec<_> = aabb.corners().iter().map(|corner| (matrix * corner.extend(1.)).truncate()).collect();
+        let expected = Aabb::from_points(corners).unwrap();
+
+        assert!(near(turned.min, expected.min));
+        assert!(near(turned.max, expected.max));
+    }
+
+    #[test]
+    fn transformed_spheres_grow_by_the_largest_scale() {
+        let sphere = BoundingSphere::new(Vector3::new(1., 0., 0.), 2.);
+        let matrix = Matrix4::from_translation(Vector3::new(0., 3., 0.)) * Matrix4::from_nonuniform_scale(1., 3., 2.);
+
+        let transformed = sphere.transform(&matrix);
+        assert!(near(transformed.center, Vector3::new(1., 3., 0.)));
+        assert!((transformed.radius - 6.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rays_hit_where_they_enter() {
+        let aabb = Aabb::new(Vector3::new(-1., -1., -1.), Vector3::new(1., 1., 1.));
+        let sphere = BoundingSphere::new(Vector3::new(0., 0., 0.), 1.);
+        let origin = Vector3::new(-5., 0.5, 0.);
+        let direction = Vector3::new(1., 0., 0.);
+
+        assert_eq!(aabb.intersect_ray(origin, direction), Some(4.));
+        assert_eq!(aabb.intersect_ray(origin, -direction), None);
+        assert_eq!(aabb.intersect_ray(Vector3::new(0., 0., 0.), direction), Some(0.));
+        assert_eq!(aabb.intersect_ray(Vector3::new(-5., 2., 0.), direction), None);
+
+        let hit = sphere.intersect_ray(origin, direction).unwrap();
+        assert!((hit - (5. - 0.75f32.sqrt())).abs() < 1e-5);
+        assert_eq!(sphere.intersect_ray(Vector3::new(-5., 2., 0.), direction), None);
+    }
+
+    #[test]
+    fn overlap_tests() {
+        let aabb = Aabb::new(Vector3::new(0., 0., 0.), Vector3::new(1., 1., 1.));
+
+        assert!(aabb.intersects(&Aabb::new(Vector3::new(1., 1., 1.), Vector3::new(2., 2., 2.))));
+        assert!(!aabb.intersects(&Aabb::new(Vector3::new(1.5, 0., 0.), Vector3::new(2., 1., 1.))));
+
+        assert!(BoundingSphere::new(Vector3::new(2., 0.5, 0.5), 1.).intersects_aabb(&aabb));
+        assert!(!BoundingSphere::new(Vector3::new(2., 2., 2.), 1.).intersects_aabb(&aabb));
+        assert!(BoundingSphere::new(Vector3::new(0., 0., 0.), 1.).intersects(&BoundingSphere::new(Vector3::new(2., 0., 0.), 1.)));
+    }
+}
@@ -0,0 +1,240 @@
+use std::io::Write;
+use std::path::Path;
+
+use super::{Mesh, Vertex};
+
+/// How a PLY file's vertex and face data is encoded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+impl<V: Vertex> Mesh<V> {
+    /// Writes the mesh as a Wavefront OBJ, with normals and texture
+    /// coordinates when the vertex type has them. Texture coordinates are
+    /// written unchanged, matching `Mesh::load_from_file`.
+    pub fn write_obj<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_triangles()?;
+
+        let sample = self.vertices.first();
+        let has_normals = sample.map_or(false, |vertex| vertex.normal().is_some());
+        let has_uvs = sample.map_or(false, |vertex| vertex.uv().is_some());
+
+        for vertex in &self.vertices {
+            let position = vertex.position();
+            writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        }
+
+        if has_uvs {
+            for vertex in &self.vertices {
+                let uv = vertex.uv().unwrap();
+                writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+            }
+        }
+
+        if has_normals {
+            for vertex in &self.vertices {
+                let normal = vertex.normal().unwrap();
+                writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+            }
+        }
+
+        // Every attribute is indexed the same way, and OBJ counts from 1.
+        for triangle in self.indices.chunks(3) {
+            write!(writer, "f")?;
+
+            for index in triangle {
+                let index = index + 1;
+
+                match (has_uvs, has_normals) {
+                    (true, true) => write!(writer, " {}/{}/{}", index, index, index)?,
+                    (true, false) => write!(writer, " {}/{}", index, index)?,
+                    (false, true) => write!(writer, " {}//{}", index, index)?,
+                    (false, false) => write!(writer, " {}", index)?,
+                }
+            }
+
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_obj<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+
+        self.write_obj(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes the mesh as a PLY, with normals and texture coordinates when
+    /// the vertex type has them.
+    pub fn write_ply<W: Write>(&self, writer: &mut W, format: PlyFormat) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_triangles()?;
+
+        let sample = self.vertices.first();
+        let has_normals = sample.map_or(false, |vertex| vertex.normal().is_some());
+        let has_uvs = sample.map_or(false, |vertex| vertex.uv().is_some());
+
+        writeln!(writer, "ply")?;
+
+        match format {
+            PlyFormat::Ascii => writeln!(writer, "format ascii 1.0")?,
+            PlyFormat::BinaryLittleEndian => writeln!(writer, "format binary_little_endian 1.0")?,
+        }
+
+        writeln!(writer, "comment Exported by rise")?;
+        writeln!(writer, "element vertex {}", self.vertices.len())?;
+        writeln!(writer, "property float x\nproperty float y\nproperty float z")?;
+
+        if has_normals {
+            writeln!(writer, "property float nx\nproperty float ny\nproperty float nz")?;
+        }
+
+        if has_uvs {
+            writeln!(writer, "property float s\nproperty float t")?;
+        }
+
+        writeln!(writer, "element face {}", self.indices.len() / 3)?;
+        writeln!(writer, "property list uchar uint vertex_indices")?;
+        writeln!(writer, "end_header")?;
+
+        for vertex in &self.vertices {
+            let mut values: Vec<f32> = Vec::with_capacity(8);
+            let position = vertex.position();
+            values.extend(&[position.x, position.y, position.z]);
+
+            if let Some(normal) = vertex.normal().filter(|_| has_normals) {
+                values.extend(&[normal.x, normal.y, normal.z]);
+            }
+
+            if let Some(uv) = vertex.uv().filter(|_| has_uvs) {
+                values.extend(&[uv.x, uv.y]);
+            }
+
+            match format {
+                PlyFormat::Ascii => {
+                    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                    writeln!(writer, "{}", values.join(" "))?;
+                }
+                PlyFormat::BinaryLittleEndian => {
+                    for value in values {
+                        writer.write_all(&value.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        for triangle in self.indices.chunks(3) {
+            match format {
+                PlyFormat::Ascii => writeln!(writer, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?,
+                PlyFormat::BinaryLittleEndian => {
+                    writer.write_all(&[3])?;
+
+                    for index in triangle {
+                        writer.write_all(&index.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save_ply<P: AsRef<Path>>(&self, path: P, format: PlyFormat) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+
+        self.write_ply(&mut writer, format)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Vector2, Vector3};
+
+    use super::PlyFormat;
+    use crate::graphics::{Mesh, StandardVertex};
+
+    fn triangle() -> Mesh {
+        let mut mesh = Mesh::new();
+
+        for (x, y) in [(0., 0.), (1., 0.), (0.5, 1.)].iter() {
+            mesh.add_vertex(StandardVertex::new(Vector3::new(*x, *y, 0.), Vector3::new(0., 0., 1.), Vector2::new(*x, *y)));
+        }
+
+        mesh.set_indices(vec![0, 1, 2]);
+        mesh
+    }
+
+    #[test]
+    fn writes_obj() {
+        let mut out = Vec::new();
+        triangle().write_obj(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "v 0 0 0\nv 1 0 0\nv 0.5 1 0\n\
+             vt 0 0\nvt 1 0\nvt 0.5 1\n\
+             vn 0 0 1\nvn 0 0 1\nvn 0 0 1\n\
+             f 1/1/1 2/2/2 3/3/3\n"
+        );
+    }
+
+    const PLY_HEADER: &str = "element vertex 3\n\
+        property float x\nproperty float y\nproperty float z\n\
+        property float nx\nproperty float ny\nproperty float nz\n\
+        property float s\nproperty float t\n\
+        element face 1\n\
+        property list uchar uint vertex_indices\n\
+        end_header\n";
+
+    #[test]
+    fn writes_ascii_ply() {
+        let mut out = Vec::new();
+        triangle().write_ply(&mut out, PlyFormat::Ascii).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "ply\nformat ascii 1.0\ncomment Exported by rise\n{}{}",
+                PLY_HEADER,
+                "0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n0.5 1 0 0 0 1 0.5 1\n3 0 1 2\n"
+            )
+        );
+    }
+
+    #[test]
+    fn writes_binary_ply() {
+        let mut out = Vec::new();
+        triangle().write_ply(&mut out, PlyFormat::BinaryLittleEndian).unwrap();
+
+        let header = format!("ply\nformat binary_little_endian 1.0\ncomment Exported by rise\n{}", PLY_HEADER);
+        assert!(out.starts_with(header.as_bytes()));
+
+        let body = &out[header.len()..];
+        assert_eq!(body.len(), 3 * 8 * 4 + 1 + 3 * 4);
+
+        let third_vertex: Vec<f32> = body[2 * 32..3 * 32]
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        assert_eq!(third_vertex, vec![0.5, 1., 0., 0., 0., 1., 0.5, 1.]);
+
+        assert_eq!(&body[96..], &[3, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_meshes_that_arent_triangle_lists() {
+        let mut mesh = triangle();
+        mesh.set_indices(vec![0, 1]);
+
+        assert!(mesh.write_obj(&mut Vec::new()).is_err());
+        assert!(mesh.write_ply(&mut Vec::new(), PlyFormat::Ascii).is_err());
+    }
+}
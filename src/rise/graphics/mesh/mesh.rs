@@ -0,0 +1,573 @@
+use log::{debug, error};
+
+use std::fmt;
+
+use super::{Aabb, BoundingSphere, MorphTarget, StandardVertex, Vertex};
+
+#[derive(Debug, Clone)]
+pub struct MeshError {
+    message: String
+}
+
+impl MeshError {
+    pub fn new(message: &str) -> MeshError {
+        MeshError {
+            message: String::from(message)
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error creating mesh: {}", self.message)
+    }
+}
+
+/// How often a mesh's data is expected to change once it's on the gpu.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MeshUsage {
+    /// Uploaded once and rarely changed. Buffers are sized to fit exactly.
+    Static,
+    /// Changed from time to time. Buffers are allocated with room to grow,
+    /// so most updates are written in place.
+    Dynamic,
+    /// Rewritten every frame. Buffers grow like `Dynamic` ones, and updates
+    /// skip recomputing the bounding volumes, call `compute_bounds` when
+    /// they're needed.
+    Stream,
+}
+
+/// A named range of a mesh's indices, such as the part of a model drawn with
+/// one material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Submesh {
+    pub name: String,
+    pub indices: std::ops::Range<u32>,
+}
+
+impl Submesh {
+    pub fn new(name: &str, indices: std::ops::Range<u32>) -> Submesh {
+        Submesh {
+            name: String::from(name),
+            indices,
+        }
+    }
+}
+
+pub struct Mesh<V = StandardVertex> {
+    pub vertices: Vec<V>,
+    pub indices: Vec<u32>,
+    pub(super) index_format: Option<wgpu::IndexFormat>,
+    pub(super) submeshes: Vec<Submesh>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    buffer_index_format: wgpu::IndexFormat,
+    buffer_index_count: u32,
+    usage: MeshUsage,
+    vertex_capacity: wgpu::BufferAddress,
+    index_capacity: wgpu::BufferAddress,
+    pub(super) bounds: Option<Aabb>,
+    pub(super) bounding_sphere: Option<BoundingSphere>,
+    pub(super) morph_targets: Vec<MorphTarget>,
+    pub(super) morph_weights: Vec<f32>
+}
+
+impl Mesh {
+    /// Loads the first model in an OBJ file. Missing texture coordinates are
+    /// left at zero and missing normals are generated.
+    pub fn load_from_file<P: AsRef<std::path::Path> + core::fmt::Debug>(path: P) -> Result<Mesh, Box<dyn std::error::Error>> {
+        let(models, _materials) = tobj::load_obj(&path, true)?;
+
+        //For the time being we're only going to load the first mesh in the model.
+        
+        let model = models
+            .first()
+            .ok_or_else(|| MeshError::new(&format!("{:?} contains no models", path)))?;
+
+        debug!("Loading model {} into mesh", model.name);
+
+        let o_mesh = &model.mesh;
+
+        let num_vertices = (o_mesh.positions.len() / 3) as usize;
+
+        if o_mesh.positions.len() % 3 != 0 {
+            return Err(Box::new(MeshError::new(&format!("{:?} has an incomplete vertex position", path))));
+        }
+
+        let has_normals = o_mesh.normals.len() == num_vertices * 3;
+        let has_texcoords = o_mesh.texcoords.len() == num_vertices * 2;
+
+        let mut vertices : Vec<StandardVertex> = Vec::with_capacity(num_vertices);
+
+        for ind in 0..num_vertices {
+
+            let ind_x = ind * 3 + 0;
+            let ind_y = ind * 3 + 1;
+            let ind_z = ind * 3 + 2;
+
+            let mut pos = cgmath::Vector3::new(0., 0., 0.);
+
+            pos.x = o_mesh.positions[ind_x];
+            pos.y = o_mesh.positions[ind_y];
+            pos.z = o_mesh.positions[ind_z];
+
+            let mut norm = cgmath::Vector3::new(0., 0., 0.);
+
+            if has_normals {
+                norm.x = o_mesh.normals[ind_x];
+                norm.y = o_mesh.normals[ind_y];
+                norm.z = o_mesh.normals[ind_z];
+            }
+
+            let mut uv = cgmath::Vector2::new(0., 0.);
+
+            if has_texcoords {
+                uv.x = o_mesh.texcoords[ind * 2 + 0];
+                uv.y = o_mesh.texcoords[ind * 2 + 1];
+            }
+
+            vertices.push(StandardVertex::new(
+                pos,
+                norm,
+                uv,
+            ));
+        }
+
+        let mut mesh = Mesh::new();
+        mesh.set_vertices(vertices);
+        mesh.set_indices(o_mesh.indices.iter().copied());
+
+        mesh.check_triangles()
+            .map_err(|e| MeshError::new(&format!("{:?}: {}", path, e)))?;
+
+        if !has_normals {
+            mesh.compute_smooth_normals(cgmath::Deg(60.).into())?;
+        }
+
+        Ok(mesh)
+    }
+}
+
+impl<V: Vertex> Mesh<V> {
+    pub fn new() -> Mesh<V> {
+        Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            index_format: None,
+            submeshes: Vec::new(),
+            vertex_buffer: None,
+            index_buffer: None,
+            buffer_index_format: wgpu::IndexFormat::Uint16,
+            buffer_index_count: 0,
+            usage: MeshUsage::Static,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            bounds: None,
+            bounding_sphere: None,
+            morph_targets: Vec::new(),
+            morph_weights: Vec::new(),
+        }
+    }
+
+    pub fn set_vertices<I: IntoIterator<Item=V>>(&mut self, vertices: I) {
+        self.vertices = vertices.into_iter().collect()
+    }
+
+    pub fn get_vertices(&self) -> &Vec<V> {
+        &self.vertices
+    }
+
+    pub fn set_indices<I: IntoIterator<Item=u32>>(&mut self, indices: I) {
+        self.indices = indices.into_iter().collect();
+    }
+
+    pub fn get_indices(&self) -> &Vec<u32> {
+        &self.indices
+    }
+
+    /// Forces the index format used for the index buffer. When left unset the
+    /// smallest format able to address every index is chosen on upload.
+    pub fn set_index_format(&mut self, index_format: Option<wgpu::IndexFormat>) {
+        self.index_format = index_format;
+    }
+
+    /// The index format the mesh will use when it is next uploaded.
+    pub fn get_index_format(&self) -> wgpu::IndexFormat {
+        match self.index_format {
+            Some(index_format) => index_format,
+            None => {
+                let max_index = self.indices.iter().copied().max().unwrap_or(0);
+
+                if max_index > u16::MAX as u32 {
+                    wgpu::IndexFormat::Uint32
+                } else {
+                    wgpu::IndexFormat::Uint16
+                }
+            }
+        }
+    }
+
+    /// The index format of the index buffer currently on the gpu.
+    pub fn get_buffer_index_format(&self) -> wgpu::IndexFormat {
+        self.buffer_index_format
+    }
+
+    /// The number of indices in the index buffer currently on the gpu.
+    pub fn get_buffer_index_count(&self) -> u32 {
+        self.buffer_index_count
+    }
+
+    pub fn add_vertex(&mut self, vertex: V) {
+        self.vertices.push(vertex);
+    }
+
+    pub fn add_index(&mut self, index: u32) {
+        self.indices.push(index);
+    }
+
+    /// Marks a range of the indices as a submesh. Passes that reorder
+    /// triangles across the whole mesh, like `optimize`, don't keep these
+    /// ranges, so add them afterwards.
+    pub fn add_submesh(&mut self, submesh: Submesh) -> Result<(), MeshError> {
+        if submesh.indices.start > submesh.indices.end || submesh.indices.end as usize > self.indices.len() {
+            return Err(MeshError::new(&format!(
+                "Submesh {} covers indices {:?}, but the mesh only has {}", submesh.name, submesh.indices, self.indices.len()
+            )));
+        }
+
+        self.submeshes.push(submesh);
+
+        Ok(())
+    }
+
+    pub fn get_submeshes(&self) -> &Vec<Submesh> {
+        &self.submeshes
+    }
+
+    pub fn find_submesh(&self, name: &str) -> Option<&Submesh> {
+        self.submeshes.iter().find(|submesh| submesh.name == name)
+    }
+
+    /// Sets how the gpu buffers are expected to change, which decides how
+    /// much room they're given to grow. Takes effect the next time a buffer
+    /// is allocated.
+    pub fn set_usage(&mut self, usage: MeshUsage) {
+        self.usage = usage;
+    }
+
+    pub fn get_usage(&self) -> MeshUsage {
+        self.usage
+    }
+
+    pub fn create(&mut self, render_context: &crate::graphics::RenderContext) -> Result<(), MeshError> {
+        if self.index_buffer.is_some() || self.vertex_buffer.is_some() {
+            panic!("Attempted to create mesh twice");
+        }
+
+        self.compute_bounds();
+        self.upload_indices(render_context)?;
+        self.upload_vertices(render_context);
+
+        Ok(())
+    }
+
+    /// Uploads all of the vertices and indices. The existing buffers are
+    /// written in place when the data fits and are only reallocated when it
+    /// has outgrown them.
+    pub fn update(&mut self, render_context: &crate::graphics::RenderContext) -> Result<(), MeshError> {
+        if self.index_buffer.is_none() || self.vertex_buffer.is_none() {
+            error!("Attempted to update mesh before creation.");
+            return Ok(());
+        }
+
+        if self.usage != MeshUsage::Stream {
+            self.compute_bounds();
+        }
+
+        self.upload_indices(render_context)?;
+        self.upload_vertices(render_context);
+
+        Ok(())
+    }
+
+    /// Uploads just the vertices in `range` after they've been changed on the
+    /// cpu. Falls back to a full upload if the vertices have outgrown their
+    /// buffer.
+    ///
+    /// The bounding volumes are grown to fit the new positions but never
+    /// shrink, call `compute_bounds` to tighten them.
+    pub fn update_vertex_range(
+        &mut self,
+        render_context: &crate::graphics::RenderContext,
+        range: std::ops::Range<usize>
+    ) -> Result<(), MeshError> {
+        if range.start > range.end || range.end > self.vertices.len() {
+            return Err(MeshError::new(&format!(
+                "Vertex range {:?} is out of bounds for {} vertices", range, self.vertices.len()
+            )));
+        }
+
+        let vertex_buffer = match &self.vertex_buffer {
+            Some(vertex_buffer) => vertex_buffer,
+            None => {
+                error!("Attempted to update mesh before creation.");
+                return Ok(());
+            }
+        };
+
+        if self.usage != MeshUsage::Stream {
+            for vertex in &self.vertices[range.clone()] {
+                let position = vertex.position();
+
+                self.bounds = Some(match self.bounds {
+                    Some(bounds) => bounds.including(position),
+                    None => Aabb::new(position, position),
+                });
+                self.bounding_sphere = Some(match self.bounding_sphere {
+                    Some(sphere) => sphere.including(position),
+                    None => BoundingSphere::new(position, 0.),
+                });
+            }
+        }
+
+        let data: &[u8] = bytemuck::cast_slice(&self.vertices[..]);
+
+        if data.len() as u64 > self.vertex_capacity {
+            self.upload_vertices(render_context);
+            return Ok(());
+        }
+
+        let stride = std::mem::size_of::<V>();
+        let start = align_down(range.start * stride);
+        let end = align_up(range.end * stride).min(data.len());
+
+        if start < end {
+            write_padded(&render_context.queue, vertex_buffer, start as u64, &data[start..end]);
+        }
+
+        Ok(())
+    }
+
+    /// Uploads just the indices in `range` after they've been changed on the
+    /// cpu. Falls back to a full upload if the indices have outgrown their
+    /// buffer or no longer fit its index format.
+    pub fn update_index_range(
+        &mut self,
+        render_context: &crate::graphics::RenderContext,
+        range: std::ops::Range<usize>
+    ) -> Result<(), MeshError> {
+        if range.start > range.end || range.end > self.indices.len() {
+            return Err(MeshError::new(&format!(
+                "Index range {:?} is out of bounds for {} indices", range, self.indices.len()
+            )));
+        }
+
+        let index_buffer = match &self.index_buffer {
+            Some(index_buffer) => index_buffer,
+            None => {
+                error!("Attempted to update mesh before creation.");
+                return Ok(());
+            }
+        };
+
+        let index_format = self.get_index_format();
+        let index_size = index_size(index_format);
+
+        if index_format != self.buffer_index_format
+            || (self.indices.len() * index_size) as u64 > self.index_capacity
+            || self.indices.len() as u32 != self.buffer_index_count
+        {
+            return self.upload_indices(render_context);
+        }
+
+        // Widen the range so the write starts and ends on a copy alignment.
+        let per_word = wgpu::COPY_BUFFER_ALIGNMENT as usize / index_size;
+        let start = range.start / per_word * per_word;
+        let end = ((range.end + per_word - 1) / per_word * per_word).min(self.indices.len());
+
+        if start < end {
+            let data = index_data(&self.indices[start..end], index_format)?;
+            write_padded(&render_context.queue, index_buffer, (start * index_size) as u64, &data);
+        }
+
+        Ok(())
+    }
+
+    fn upload_vertices(&mut self, render_context: &crate::graphics::RenderContext) {
+        let vertices = std::mem::replace(&mut self.vertices, Vec::new());
+        self.write_vertices(render_context, &vertices);
+        self.vertices = vertices;
+    }
+
+    /// Fills the vertex buffer with `vertices`, growing it if they don't fit.
+    pub(super) fn write_vertices(&mut self, render_context: &crate::graphics::RenderContext, vertices: &[V]) {
+        let data: &[u8] = bytemuck::cast_slice(vertices);
+
+        match &self.vertex_buffer {
+            Some(vertex_buffer) if data.len() as u64 <= self.vertex_capacity => {
+                write_padded(&render_context.queue, vertex_buffer, 0, data);
+            }
+            _ => {
+                let capacity = buffer_capacity(data.len(), self.usage);
+
+                self.vertex_buffer = Some(create_buffer(
+                    render_context,
+                    "Vertex Buffer",
+                    wgpu::BufferUsage::VERTEX,
+                    data,
+                    capacity
+                ));
+                self.vertex_capacity = capacity;
+            }
+        }
+    }
+
+    fn upload_indices(&mut self, render_context: &crate::graphics::RenderContext) -> Result<(), MeshError> {
+        let index_format = self.get_index_format();
+        let data = index_data(&self.indices, index_format)?;
+
+        match &self.index_buffer {
+            Some(index_buffer) if data.len() as u64 <= self.index_capacity => {
+                write_padded(&render_context.queue, index_buffer, 0, &data);
+            }
+            _ => {
+                let capacity = buffer_capacity(data.len(), self.usage);
+
+                self.index_buffer = Some(create_buffer(
+                    render_context,
+                    "Index Buffer",
+                    wgpu::BufferUsage::INDEX,
+                    &data,
+                    capacity
+                ));
+                self.index_capacity = capacity;
+            }
+        }
+
+        self.buffer_index_format = index_format;
+        self.buffer_index_count = self.indices.len() as u32;
+
+        Ok(())
+    }
+
+    /// Recomputes the bounding volumes from the current vertices. This is
+    /// done automatically whenever the mesh is created or updated.
+    pub fn compute_bounds(&mut self) {
+        let positions = self.vertices.iter().map(|vertex| vertex.position());
+
+        self.bounds = Aabb::from_points(positions.clone());
+        self.bounding_sphere = BoundingSphere::from_points(positions);
+    }
+
+    /// The mesh's bounding box as of the last create or update, or `None` if
+    /// it had no vertices.
+    pub fn get_bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// The mesh's bounding sphere as of the last create or update, or `None`
+    /// if it had no vertices.
+    pub fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.bounding_sphere
+    }
+
+    pub fn get_vertex_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.vertex_buffer.as_ref()
+    }
+
+    pub fn get_index_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.index_buffer.as_ref()
+    }
+}
+
+impl<V: Vertex> Default for Mesh<V> {
+    fn default() -> Mesh<V> {
+        Mesh::new()
+    }
+}
+
+/// Packs indices into bytes of the given format, failing rather than
+/// truncating if an index doesn't fit.
+fn index_data(indices: &[u32], index_format: wgpu::IndexFormat) -> Result<Vec<u8>, MeshError> {
+    match index_format {
+        wgpu::IndexFormat::Uint16 => {
+            let mut data: Vec<u16> = Vec::with_capacity(indices.len());
+
+            for index in indices {
+                if *index > u16::MAX as u32 {
+                    return Err(MeshError::new(&format!(
+                        "Index {} does not fit in a 16 bit index buffer", index
+                    )));
+                }
+
+                data.push(*index as u16);
+            }
+
+            Ok(bytemuck::cast_slice(&data[..]).to_vec())
+        }
+        wgpu::IndexFormat::Uint32 => {
+            Ok(bytemuck::cast_slice(indices).to_vec())
+        }
+    }
+}
+
+fn index_size(index_format: wgpu::IndexFormat) -> usize {
+    match index_format {
+        wgpu::IndexFormat::Uint16 => std::mem::size_of::<u16>(),
+        wgpu::IndexFormat::Uint32 => std::mem::size_of::<u32>(),
+    }
+}
+
+/// How many bytes to allocate for a buffer holding `len` bytes.
+fn buffer_capacity(len: usize, usage: MeshUsage) -> wgpu::BufferAddress {
+    let len = match usage {
+        MeshUsage::Static => len,
+        MeshUsage::Dynamic | MeshUsage::Stream => len.next_power_of_two(),
+    };
+
+    align_up(len.max(1)) as wgpu::BufferAddress
+}
+
+fn create_buffer(
+    render_context: &crate::graphics::RenderContext,
+    label: &str,
+    usage: wgpu::BufferUsage,
+    data: &[u8],
+    capacity: wgpu::BufferAddress
+) -> wgpu::Buffer {
+    let buffer = render_context.device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some(label),
+            size: capacity,
+            usage: usage | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: true,
+        }
+    );
+
+    buffer.slice(..).get_mapped_range_mut()[..data.len()].copy_from_slice(data);
+    buffer.unmap();
+
+    buffer
+}
+
+/// Writes `data` into the buffer, padding the end with zeros so the copy is
+/// a whole number of aligned words. `offset` must already be aligned.
+fn write_padded(queue: &wgpu::Queue, buffer: &wgpu::Buffer, offset: wgpu::BufferAddress, data: &[u8]) {
+    if data.len() % wgpu::COPY_BUFFER_ALIGNMENT as usize == 0 {
+        queue.write_buffer(buffer, offset, data);
+    } else {
+        let mut padded = data.to_vec();
+        padded.resize(align_up(data.len()), 0);
+        queue.write_buffer(buffer, offset, &padded);
+    }
+}
+
+fn align_down(offset: usize) -> usize {
+    offset / wgpu::COPY_BUFFER_ALIGNMENT as usize * wgpu::COPY_BUFFER_ALIGNMENT as usize
+}
+
+fn align_up(offset: usize) -> usize {
+    align_down(offset + wgpu::COPY_BUFFER_ALIGNMENT as usize - 1)
+}
@@ -0,0 +1,37 @@
+mod vertex;
+
+pub use vertex::{*};
+
+mod bounds;
+
+pub use bounds::{*};
+
+mod mesh;
+
+pub use mesh::{*};
+
+mod morph;
+
+pub use morph::{*};
+
+mod binary;
+
+pub use binary::{*};
+
+mod export;
+
+pub use export::{*};
+
+mod operations;
+
+pub use operations::{*};
+
+mod optimize;
+
+mod primitives;
+
+mod processing;
+
+mod simplify;
+
+mod tangents;
\ No newline at end of file
@@ -0,0 +1,228 @@
+use cgmath::{InnerSpace, Vector3};
+
+use super::{Mesh, MeshError, Vertex};
+
+/// An alternative shape for a mesh, stored as offsets from its vertices.
+#[derive(Clone, Debug)]
+pub struct MorphTarget {
+    pub name: String,
+    /// How far the target moves each vertex.
+    pub position_deltas: Vec<Vector3<f32>>,
+    /// How the target changes each vertex's normal, if it does.
+    pub normal_deltas: Option<Vec<Vector3<f32>>>,
+}
+
+impl MorphTarget {
+    pub fn new(name: &str, position_deltas: Vec<Vector3<f32>>, normal_deltas: Option<Vec<Vector3<f32>>>) -> MorphTarget {
+        MorphTarget {
+            name: String::from(name),
+            position_deltas,
+            normal_deltas,
+        }
+    }
+
+    /// Makes a target from a copy of the mesh's vertices moved into the new
+    /// shape.
+    pub fn from_shape<V: Vertex>(name: &str, base: &[V], shape: &[V]) -> Result<MorphTarget, MeshError> {
+        if base.len() != shape.len() {
+            return Err(MeshError::new(&format!(
+                "Morph target {} has {} vertices but the mesh has {}", name, shape.len(), base.len()
+            )));
+        }
+
+        let position_deltas = base
+            .iter()
+            .zip(shape)
+            .map(|(base, shape)| shape.position() - base.position())
+            .collect();
+
+        let normal_deltas = base
+            .iter()
+            .zip(shape)
+            .map(|(base, shape)| Some(shape.normal()? - base.normal()?))
+            .collect();
+
+        Ok(MorphTarget::new(name, position_deltas, normal_deltas))
+    }
+}
+
+impl<V: Vertex> Mesh<V> {
+    /// Adds a morph target, starting with a weight of 0. Targets are indexed
+    /// the same way as the vertices, and processing that reorders, copies or
+    /// drops vertices keeps them in step.
+    pub fn add_morph_target(&mut self, target: MorphTarget) -> Result<(), MeshError> {
+        let normals = target.normal_deltas.as_ref().map_or(self.vertices.len(), |deltas| deltas.len());
+
+        if target.position_deltas.len() != self.vertices.len() || normals != self.vertices.len() {
+            return Err(MeshError::new(&format!(
+                "Morph target {} doesn't have one delta for each of the {} vertices", target.name, self.vertices.len()
+            )));
+        }
+
+        self.morph_targets.push(target);
+        self.morph_weights.push(0.);
+
+        Ok(())
+    }
+
+    /// Rebuilds the morph targets after the vertices have been reordered,
+    /// copied or dropped, where the new vertex `i` came from the old vertex
+    /// `sources[i]`.
+    pub(super) fn remap_morph_targets(&mut self, sources: &[usize]) {
+        for target in self.morph_targets.iter_mut() {
+            target.position_deltas = sources.iter().map(|source| target.position_deltas[*source]).collect();
+
+            if let Some(normal_deltas) = &mut target.normal_deltas {
+                *normal_deltas = sources.iter().map(|source| normal_deltas[*source]).collect();
+            }
+        }
+    }
+
+    pub fn get_morph_targets(&self) -> &Vec<MorphTarget> {
+        &self.morph_targets
+    }
+
+    pub fn find_morph_target(&self, name: &str) -> Option<usize> {
+        self.morph_targets.iter().position(|target| target.name == name)
+    }
+
+    /// Sets the weight of every morph target, in the order they were added.
+    /// Missing weights are set to 0.
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        for (index, weight) in self.morph_weights.iter_mut().enumerate() {
+            *weight = weights.get(index).copied().unwrap_or(0.);
+        }
+    }
+
+    pub fn set_morph_weight(&mut self, index: usize, weight: f32) {
+        if let Some(current) = self.morph_weights.get_mut(index) {
+            *current = weight;
+        }
+    }
+
+    pub fn get_morph_weights(&self) -> &Vec<f32> {
+        &self.morph_weights
+    }
+
+    /// The vertices with every morph target blended in by its weight. The
+    /// mesh's own vertices are left as the base shape.
+    pub fn morphed_vertices(&self) -> Vec<V> {
+        let mut vertices = self.vertices.clone();
+
+        for (target, weight) in self.morph_targets.iter().zip(&self.morph_weights) {
+            if *weight == 0. {
+                continue;
+            }
+
+            for (vertex, delta) in vertices.iter_mut().zip(&target.position_deltas) {
+                vertex.set_position(vertex.position() + delta * *weight);
+            }
+
+            if let Some(normal_deltas) = &target.normal_deltas {
+                for (vertex, delta) in vertices.iter_mut().zip(normal_deltas) {
+                    if let Some(normal) = vertex.normal() {
+                        vertex.set_normal(normal + delta * *weight);
+                    }
+                }
+            }
+        }
+
+        for vertex in vertices.iter_mut() {
+            if let Some(normal) = vertex.normal() {
+                if normal.magnitude2() > 0. {
+                    vertex.set_normal(normal.normalize());
+                }
+            }
+        }
+
+        vertices
+    }
+
+    /// Blends the morph targets on the cpu and uploads the result in place of
+    /// the vertex buffer's contents. For meshes that change every frame, set
+    /// their usage to `MeshUsage::Stream`. To blend on the gpu instead, see
+    /// `MorphTargetBuffer`.
+    pub fn apply_morph_targets(&mut self, render_context: &crate::graphics::RenderContext) -> Result<(), MeshError> {
+        if self.get_vertex_buffer().is_none() {
+            return Err(MeshError::new("Attempted to apply morph targets before creation"));
+        }
+
+        let vertices = self.morphed_vertices();
+        self.write_vertices(render_context, &vertices);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use super::MorphTarget;
+    use crate::graphics::{Mesh, Vertex};
+
+    /// How the test target moves a vertex, which only depends on where the
+    /// vertex is so it can be checked after any processing.
+    fn delta(position: Vector3<f32>) -> Vector3<f32> {
+        Vector3::new(position.z, position.x * 2., position.x - position.z)
+    }
+
+    fn morphed_grid() -> Mesh {
+        let mut mesh = Mesh::grid(1., 1., 6, 6);
+        let deltas = mesh.get_vertices().iter().map(|vertex| delta(vertex.position())).collect();
+        let normals = mesh.get_vertices().iter().map(|vertex| delta(vertex.position()) * 0.5).collect();
+
+        mesh.add_morph_target(MorphTarget::new("bend", deltas, Some(normals))).unwrap();
+        mesh.set_morph_weight(0, 1.);
+        mesh
+    }
+
+    fn assert_targets_follow_vertices(mesh: &Mesh) {
+        let target = &mesh.get_morph_targets()[0];
+        let normals = target.normal_deltas.as_ref().unwrap();
+
+        assert_eq!(target.position_deltas.len(), mesh.get_vertices().len());
+        assert_eq!(normals.len(), mesh.get_vertices().len());
+
+        for (index, vertex) in mesh.get_vertices().iter().enumerate() {
+            assert_eq!(target.position_deltas[index], delta(vertex.position()));
+            assert_eq!(normals[index], delta(vertex.position()) * 0.5);
+        }
+    }
+
+    #[test]
+    fn processing_keeps_morph_targets_in_step() {
+        let mut mesh = morphed_grid();
+        mesh.compute_flat_normals().unwrap();
+        assert_targets_follow_vertices(&mesh);
+
+        mesh.weld_vertices(0.).unwrap();
+        assert_targets_follow_vertices(&mesh);
+
+        let mut mesh = morphed_grid();
+        mesh.optimize().unwrap();
+        assert_targets_follow_vertices(&mesh);
+
+        let simplified = morphed_grid().simplify(10, 1.).unwrap();
+        assert!(simplified.get_indices().len() < morphed_grid().get_indices().len());
+        assert_targets_follow_vertices(&simplified);
+        assert_eq!(simplified.get_morph_weights(), &vec![1.]);
+    }
+
+    #[test]
+    fn welding_keeps_vertices_that_morph_differently() {
+        let mut mesh = Mesh::grid(1., 1., 1, 1);
+        let vertex = mesh.get_vertices()[0];
+        mesh.vertices.push(vertex);
+        mesh.indices.extend_from_slice(&[4, 1, 2]);
+
+        let mut deltas = vec![Vector3::new(0., 0., 0.); 5];
+        deltas[4] = Vector3::new(0., 1., 0.);
+        mesh.add_morph_target(MorphTarget::new("lift", deltas, None)).unwrap();
+
+        mesh.weld_vertices(0.).unwrap();
+
+        assert_eq!(mesh.get_vertices().len(), 5);
+        assert_eq!(mesh.get_morph_targets()[0].position_deltas.len(), 5);
+    }
+}
@@ -0,0 +1,516 @@
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector2, Vector3};
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use super::{Aabb, Mesh, MeshError, MorphTarget, Submesh, Vertex};
+
+/// The point a mesh is moved around by `Mesh::recenter`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pivot {
+    /// The center of the bounding box.
+    Center,
+    /// The middle of the bottom of the bounding box, so the mesh stands on
+    /// the origin.
+    Bottom,
+    Point(Vector3<f32>),
+}
+
+/// How `Mesh::project_uvs` maps positions to texture coordinates. `scale` is
+/// how many times the texture repeats per unit of distance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UvProjection {
+    /// Projects along `normal`, as if the texture were shone onto the mesh.
+    Planar { normal: Vector3<f32>, scale: f32 },
+    /// Projects each triangle along whichever axis it faces most, splitting
+    /// vertices where the axis changes.
+    Box { scale: f32 },
+    /// Wraps the texture around the bounding box's center, with u going
+    /// around the y axis and v from top to bottom.
+    Spherical,
+}
+
+impl<V: Vertex> Mesh<V> {
+    /// Moves every vertex by `matrix`. Normals and tangents are transformed
+    /// so they stay perpendicular to the surface, and mirroring matrices
+    /// flip the winding so the front faces stay in front. The bounds are
+    /// recomputed to fit the moved vertices.
+    pub fn transform(&mut self, matrix: &Matrix4<f32>) {
+        let linear = Matrix3::from_cols(matrix.x.truncate(), matrix.y.truncate(), matrix.z.truncate());
+        let normal_matrix = linear.invert().map_or(linear, |inverse| inverse.transpose());
+        let mirrored = linear.determinant() < 0.;
+
+        for vertex in self.vertices.iter_mut() {
+            vertex.set_position((matrix * vertex.position().extend(1.)).truncate());
+
+            if let Some(normal) = vertex.normal() {
+                vertex.set_normal(normalize_or_zero(normal_matrix * normal));
+            }
+
+            if let Some(tangent) = vertex.tangent() {
+                let sign = if mirrored { -tangent.w } else { tangent.w };
+                vertex.set_tangent(normalize_or_zero(linear * tangent.truncate()).extend(sign));
+            }
+        }
+
+        for target in self.morph_targets.iter_mut() {
+            for delta in target.position_deltas.iter_mut() {
+                *delta = linear * *delta;
+            }
+
+            if let Some(normal_deltas) = &mut target.normal_deltas {
+                for delta in normal_deltas.iter_mut() {
+                    *delta = normal_matrix * *delta;
+                }
+            }
+        }
+
+        if mirrored {
+            self.flip_winding();
+        }
+
+        self.compute_bounds();
+    }
+
+    /// Adds another mesh's vertices, triangles and submeshes to this one, for
+    /// batching static geometry into a single draw.
+    pub fn append(&mut self, other: &Mesh<V>) -> Result<(), MeshError> {
+        if !self.morph_targets.is_empty() || !other.morph_targets.is_empty() {
+            return Err(MeshError::new("Meshes with morph targets can't be merged"));
+        }
+
+        other.check_triangles()?;
+
+        let base = self.vertices.len() as u32;
+        let first_index = self.indices.len() as u32;
+
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|index| base + index));
+
+        for submesh in &other.submeshes {
+            self.submeshes.push(Submesh::new(
+                &submesh.name,
+                first_index + submesh.indices.start..first_index + submesh.indices.end
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Like `append`, but moves the other mesh's vertices by `matrix` first,
+    /// such as the transform of the object it belongs to.
+    pub fn append_transformed(&mut self, other: &Mesh<V>, matrix: &Matrix4<f32>) -> Result<(), MeshError> {
+        if !other.morph_targets.is_empty() {
+            return Err(MeshError::new("Meshes with morph targets can't be merged"));
+        }
+
+        let mut transformed = Mesh::new();
+        transformed.vertices = other.vertices.clone();
+        transformed.indices = other.indices.clone();
+        transformed.submeshes = other.submeshes.clone();
+        transformed.transform(matrix);
+
+        self.append(&transformed)
+    }
+
+    /// Combines several meshes into one.
+    pub fn merge<'a, I: IntoIterator<Item = &'a Mesh<V>>>(meshes: I) -> Result<Mesh<V>, MeshError> {
+        let mut merged = Mesh::new();
+
+        for mesh in meshes {
+            merged.append(mesh)?;
+        }
+
+        Ok(merged)
+    }
+
+    /// The total area of every triangle.
+    pub fn surface_area(&self) -> f32 {
+        self.triangle_positions()
+            .map(|[a, b, c]| (b - a).cross(c - a).magnitude() / 2.)
+            .sum()
+    }
+
+    /// The volume enclosed by the mesh, which should be closed with its
+    /// triangles facing outward. Holes make the result meaningless, and
+    /// triangles facing inward count as negative volume.
+    pub fn volume(&self) -> f32 {
+        self.triangle_positions()
+            .map(|[a, b, c]| a.dot(b.cross(c)) / 6.)
+            .sum()
+    }
+
+    /// Splits the mesh into its separate pieces, where triangles are in the
+    /// same piece when they touch. Vertices at the same position count as
+    /// touching, so seams don't split a piece. Submeshes aren't kept.
+    pub fn split_by_connectivity(&self) -> Result<Vec<Mesh<V>>, MeshError> {
+        self.check_triangles()?;
+
+        let mut pieces = DisjointSet::new(self.vertices.len());
+
+        // Join vertices that share a position.
+        let mut by_position: HashMap<[u32; 3], usize> = HashMap::new();
+
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            let p = vertex.position();
+            let key = [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+
+            let first = *by_position.entry(key).or_insert(index);
+            pieces.union(first, index);
+        }
+
+        for triangle in self.indices.chunks(3) {
+            pieces.union(triangle[0] as usize, triangle[1] as usize);
+            pieces.union(triangle[0] as usize, triangle[2] as usize);
+        }
+
+        // Build each piece in the order its first triangle appears.
+        let mut piece_of_root: HashMap<usize, usize> = HashMap::new();
+        let mut remaps: Vec<HashMap<u32, u32>> = Vec::new();
+        let mut meshes: Vec<Mesh<V>> = Vec::new();
+
+        for triangle in self.indices.chunks(3) {
+            let root = pieces.find(triangle[0] as usize);
+
+            let piece = *piece_of_root.entry(root).or_insert_with(|| {
+                meshes.push(Mesh::new());
+                remaps.push(HashMap::new());
+                meshes.len() - 1
+            });
+
+            for index in triangle {
+                let mesh = &mut meshes[piece];
+
+                let new_index = *remaps[piece].entry(*index).or_insert_with(|| {
+                    mesh.vertices.push(self.vertices[*index as usize]);
+                    (mesh.vertices.len() - 1) as u32
+                });
+
+                mesh.indices.push(new_index);
+            }
+        }
+
+        for (mesh, remap) in meshes.iter_mut().zip(&remaps) {
+            let mut old_indices = vec![0; remap.len()];
+
+            for (old, new) in remap {
+                old_indices[*new as usize] = *old as usize;
+            }
+
+            for target in &self.morph_targets {
+                mesh.add_morph_target(MorphTarget::new(
+                    &target.name,
+                    old_indices.iter().map(|old| target.position_deltas[*old]).collect(),
+                    target
+                        .normal_deltas
+                        .as_ref()
+                        .map(|deltas| old_indices.iter().map(|old| deltas[*old]).collect()),
+                ))?;
+            }
+
+            mesh.set_morph_weights(&self.morph_weights);
+        }
+
+        Ok(meshes)
+    }
+
+    /// Reverses the order of every triangle's corners, turning front faces
+    /// into back faces. Normals are left as they are.
+    pub fn flip_winding(&mut self) {
+        for triangle in self.indices.chunks_mut(3) {
+            if triangle.len() == 3 {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+
+    /// Moves the vertices so `pivot` ends up at the origin, and returns where
+    /// the pivot was. Adding that to the position of the mesh's transform
+    /// keeps it in the same place in the world.
+    pub fn recenter(&mut self, pivot: Pivot) -> Vector3<f32> {
+        let bounds = Aabb::from_points(self.vertices.iter().map(|vertex| vertex.position()));
+
+        let offset = match (pivot, bounds) {
+            (Pivot::Point(point), _) => point,
+            (Pivot::Center, Some(bounds)) => bounds.center(),
+            (Pivot::Bottom, Some(bounds)) => {
+                let center = bounds.center();
+                Vector3::new(center.x, bounds.min.y, center.z)
+            }
+            (_, None) => return Vector3::new(0., 0., 0.),
+        };
+
+        for vertex in self.vertices.iter_mut() {
+            vertex.set_position(vertex.position() - offset);
+        }
+
+        if let Some(bounds) = self.bounds.as_mut() {
+            *bounds = Aabb::new(bounds.min - offset, bounds.max - offset);
+        }
+
+        if let Some(sphere) = self.bounding_sphere.as_mut() {
+            sphere.center -= offset;
+        }
+
+        offset
+    }
+
+    /// Replaces the texture coordinates with a projection of the positions.
+    /// Vertices are split where neighbouring triangles need different
+    /// coordinates, so regenerate tangents afterwards.
+    pub fn project_uvs(&mut self, projection: UvProjection) -> Result<(), MeshError> {
+        if self.vertices.first().map_or(false, |vertex| vertex.uv().is_none()) {
+            return Err(MeshError::new("Vertex type has no texture coordinates to project"));
+        }
+
+        self.check_triangles()?;
+
+        let uvs: Vec<Vector2<f32>> = match projection {
+            UvProjection::Planar { normal, scale } => {
+                let (u_axis, v_axis) = plane_axes(normal);
+
+                self.indices
+                    .iter()
+                    .map(|index| planar_uv(self.vertices[*index as usize].position(), u_axis, v_axis, scale))
+                    .collect()
+            }
+            UvProjection::Box { scale } => {
+                let mut uvs = Vec::with_capacity(self.indices.len());
+
+                for ([a, b, c], triangle) in self.triangle_positions().zip(self.indices.chunks(3)) {
+                    let normal = (b - a).cross(c - a);
+                    let (u_axis, v_axis) = plane_axes(dominant_axis(normal));
+
+                    for index in triangle {
+                        uvs.push(planar_uv(self.vertices[*index as usize].position(), u_axis, v_axis, scale));
+                    }
+                }
+
+                uvs
+            }
+            UvProjection::Spherical => {
+                let center = Aabb::from_points(self.vertices.iter().map(|vertex| vertex.position()))
+                    .map_or(Vector3::new(0., 0., 0.), |bounds| bounds.center());
+
+                let mut uvs = Vec::with_capacity(self.indices.len());
+
+                for triangle in self.indices.chunks(3) {
+                    let mut corners: Vec<Vector2<f32>> = triangle
+                        .iter()
+                        .map(|index| spherical_uv(self.vertices[*index as usize].position() - center))
+                        .collect();
+
+                    // Triangles crossing the seam wrap the long way round, so
+                    // bring their corners onto the same side.
+                    let max_u = corners.iter().map(|uv| uv.x).fold(f32::MIN, f32::max);
+
+                    for uv in corners.iter_mut() {
+                        if max_u - uv.x > 0.5 {
+                            uv.x += 1.;
+                        }
+                    }
+
+                    uvs.extend(corners);
+                }
+
+                uvs
+            }
+        };
+
+        super::processing::assign_corners(
+            self,
+            &uvs,
+            |vertex, uv| vertex.uv().map_or(false, |current| current == uv),
+            |vertex, uv| vertex.set_uv(uv),
+        );
+
+        Ok(())
+    }
+
+    fn triangle_positions(&self) -> impl Iterator<Item = [Vector3<f32>; 3]> + '_ {
+        self.indices.chunks_exact(3).map(move |triangle| [
+            self.vertices[triangle[0] as usize].position(),
+            self.vertices[triangle[1] as usize].position(),
+            self.vertices[triangle[2] as usize].position(),
+        ])
+    }
+}
+
+fn normalize_or_zero(vector: Vector3<f32>) -> Vector3<f32> {
+    if vector.magnitude2() > 0. {
+        vector.normalize()
+    } else {
+        vector
+    }
+}
+
+/// The axis `normal` points along most, keeping its sign.
+fn dominant_axis(normal: Vector3<f32>) -> Vector3<f32> {
+    let abs = Vector3::new(normal.x.abs(), normal.y.abs(), normal.z.abs());
+
+    if abs.x >= abs.y && abs.x >= abs.z {
+        Vector3::new(normal.x.signum(), 0., 0.)
+    } else if abs.y >= abs.z {
+        Vector3::new(0., normal.y.signum(), 0.)
+    } else {
+        Vector3::new(0., 0., normal.z.signum())
+    }
+}
+
+/// The directions u and v increase in on a plane facing `normal`, chosen so
+/// the texture reads the right way round when looking at the plane, with v
+/// increasing downwards.
+fn plane_axes(normal: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let normal = normalize_or_zero(normal);
+
+    let up = if normal.y.abs() > 0.999 {
+        Vector3::new(0., 0., -normal.y.signum())
+    } else {
+        Vector3::new(0., 1., 0.)
+    };
+
+    let u_axis = up.cross(normal).normalize();
+    let v_axis = u_axis.cross(normal);
+
+    (u_axis, v_axis)
+}
+
+fn planar_uv(position: Vector3<f32>, u_axis: Vector3<f32>, v_axis: Vector3<f32>, scale: f32) -> Vector2<f32> {
+    Vector2::new(position.dot(u_axis), position.dot(v_axis)) * scale
+}
+
+fn spherical_uv(direction: Vector3<f32>) -> Vector2<f32> {
+    let direction = normalize_or_zero(direction);
+
+    Vector2::new(
+        direction.x.atan2(direction.z) / (2. * PI) + 0.5,
+        direction.y.max(-1.).min(1.).acos() / PI,
+    )
+}
+
+/// Tracks which of a set of elements have been joined together.
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> DisjointSet {
+        DisjointSet {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point everything on the way straight at the root.
+        let mut element = element;
+
+        while self.parents[element] != root {
+            let next = self.parents[element];
+            self.parents[element] = root;
+            element = next;
+        }
+
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+
+        if a != b {
+            self.parents[b] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix4, Vector3};
+
+    use super::Pivot;
+    use crate::graphics::{Aabb, Mesh, Submesh, Vertex};
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn unit_cube_area_and_volume() {
+        let cube = Mesh::cube(1., 2);
+
+        assert!(near(cube.surface_area(), 6.));
+        assert!(near(cube.volume(), 1.));
+
+        let mut inside_out = Mesh::cube(1., 2);
+        inside_out.flip_winding();
+        assert!(near(inside_out.volume(), -1.));
+    }
+
+    #[test]
+    fn splits_disjoint_pieces() {
+        let mut quads = Mesh::grid(1., 1., 1, 1);
+        quads.append_transformed(&Mesh::grid(1., 1., 1, 1), &Matrix4::from_translation(Vector3::new(3., 0., 0.))).unwrap();
+
+        let pieces = quads.split_by_connectivity().unwrap();
+        assert_eq!(pieces.len(), 2);
+
+        for piece in pieces.iter() {
+            assert_eq!((piece.vertices.len(), piece.indices.len()), (4, 6));
+        }
+
+        assert!(pieces[1].vertices.iter().all(|vertex| vertex.position().x > 2.));
+
+        // The cube's faces have their own vertices, but share positions along the edges.
+        assert_eq!(Mesh::cube(1., 1).split_by_connectivity().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn flipping_winding_reverses_each_triangle() {
+        let mut mesh = Mesh::grid(1., 1., 1, 1);
+        let indices = mesh.indices.clone();
+        mesh.flip_winding();
+
+        for (flipped, original) in mesh.indices.chunks(3).zip(indices.chunks(3)) {
+            assert_eq!(flipped, &[original[0], original[2], original[1]]);
+        }
+    }
+
+    #[test]
+    fn transforming_moves_vertices_and_bounds() {
+        let mut cube = Mesh::cube(2., 1);
+        cube.compute_bounds();
+        cube.transform(&(Matrix4::from_translation(Vector3::new(0., 5., 0.)) * Matrix4::from_nonuniform_scale(-1., 1., 2.)));
+
+        assert_eq!(cube.get_bounds(), Some(Aabb::new(Vector3::new(-1., 4., -2.), Vector3::new(1., 6., 2.))));
+        assert_eq!(cube.get_bounding_sphere().map(|sphere| sphere.center), Some(Vector3::new(0., 5., 0.)));
+
+        // Mirroring flips the winding, so the cube still encloses positive volume.
+        assert!(near(cube.volume(), 16.));
+    }
+
+    #[test]
+    fn appending_offsets_indices_and_submeshes() {
+        let mut mesh = Mesh::grid(1., 1., 1, 1);
+        let mut other = Mesh::grid(1., 1., 1, 1);
+        other.add_submesh(Submesh::new("other", 0..6)).unwrap();
+
+        mesh.append(&other).unwrap();
+
+        assert_eq!(mesh.indices[6..], [4, 6, 5, 5, 6, 7]);
+        assert_eq!(mesh.get_submeshes()[0].indices, 6..12);
+    }
+
+    #[test]
+    fn recentering_returns_the_old_pivot() {
+        let mut cube = Mesh::cube(2., 1);
+        cube.transform(&Matrix4::from_translation(Vector3::new(1., 2., 3.)));
+
+        assert_eq!(cube.recenter(Pivot::Bottom), Vector3::new(1., 1., 3.));
+        assert_eq!(cube.get_bounds(), Some(Aabb::new(Vector3::new(-1., 0., -1.), Vector3::new(1., 2., 1.))));
+    }
+}
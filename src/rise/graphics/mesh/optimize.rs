@@ -0,0 +1,376 @@
+use cgmath::{InnerSpace, Vector3};
+
+use super::{Mesh, MeshError, Vertex};
+
+/// The number of vertices the cache optimizer assumes the gpu keeps around.
+const CACHE_SIZE: usize = 32;
+
+/// The cache used to find strip boundaries when ordering for overdraw. It's
+/// kept small so clusters stay small enough to sort usefully.
+const OVERDRAW_CACHE_SIZE: usize = 16;
+
+impl<V: Vertex> Mesh<V> {
+    /// Runs every optimization in the order that gives the best results:
+    /// duplicate vertices and degenerate triangles are removed, triangles are
+    /// reordered for the vertex cache and then for overdraw, and finally the
+    /// vertices are reordered to match.
+    pub fn optimize(&mut self) -> Result<(), MeshError> {
+        self.check_triangles()?;
+
+        self.weld_vertices(0.)?;
+        self.remove_degenerate_triangles();
+        self.optimize_vertex_cache()?;
+        self.optimize_overdraw()?;
+        self.optimize_vertex_fetch()?;
+
+        Ok(())
+    }
+
+    /// Drops triangles that use the same vertex more than once.
+    pub fn remove_degenerate_triangles(&mut self) {
+        let indices: Vec<u32> = self
+            .indices
+            .chunks(3)
+            .filter(|triangle| triangle.len() == 3
+                && triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[2] != triangle[0])
+            .flatten()
+            .copied()
+            .collect();
+
+        self.indices = indices;
+    }
+
+    /// Reorders triangles so vertices are reused while they're still in the
+    /// gpu's post transform cache, using Forsyth's linear speed algorithm.
+    pub fn optimize_vertex_cache(&mut self) -> Result<(), MeshError> {
+        self.check_triangles()?;
+
+        let triangle_count = self.indices.len() / 3;
+        let vertex_count = self.vertices.len();
+
+        // The triangles still to be added that use each vertex.
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+
+        for (triangle, corners) in self.indices.chunks(3).enumerate() {
+            for index in corners {
+                vertex_triangles[*index as usize].push(triangle);
+            }
+        }
+
+        let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+        let mut vertex_scores: Vec<f32> = vertex_triangles
+            .iter()
+            .map(|triangles| vertex_score(None, triangles.len()))
+            .collect();
+
+        let mut added = vec![false; triangle_count];
+
+        let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+        let mut indices = Vec::with_capacity(self.indices.len());
+        let mut next_unadded = 0;
+        let mut best = None;
+
+        for _ in 0..triangle_count {
+            // With nothing useful in the cache, start again from the first
+            // triangle that hasn't been added. The cursor only moves forward,
+            // so these restarts cost linear time over the whole mesh.
+            let triangle = match best {
+                Some(triangle) => triangle,
+                None => {
+                    while added[next_unadded] {
+                        next_unadded += 1;
+                    }
+
+                    next_unadded
+                }
+            };
+
+            added[triangle] = true;
+
+            let corners = [
+                self.indices[triangle * 3],
+                self.indices[triangle * 3 + 1],
+                self.indices[triangle * 3 + 2],
+            ];
+
+            indices.extend_from_slice(&corners);
+
+            for index in &corners {
+                vertex_triangles[*index as usize].retain(|other| *other != triangle);
+            }
+
+            // Move the triangle's vertices to the front of the cache.
+            let mut new_cache = corners.to_vec();
+            new_cache.extend(cache.iter().copied().filter(|index| !corners.contains(index)));
+
+            for index in new_cache.iter().skip(CACHE_SIZE) {
+                cache_position[*index as usize] = None;
+            }
+
+            for (position, index) in new_cache.iter().enumerate().take(CACHE_SIZE) {
+                cache_position[*index as usize] = Some(position);
+            }
+
+            // Rescore everything touched, including vertices that just fell
+            // out of the cache.
+            for index in &new_cache {
+                let index = *index as usize;
+                vertex_scores[index] = vertex_score(cache_position[index], vertex_triangles[index].len());
+            }
+
+            best = None;
+            let mut best_score = f32::NEG_INFINITY;
+
+            for index in &new_cache {
+                for other in &vertex_triangles[*index as usize] {
+                    let score = (0..3)
+                        .map(|corner| vertex_scores[self.indices[other * 3 + corner] as usize])
+                        .sum();
+
+                    if score > best_score && cache_position[*index as usize].is_some() {
+                        best = Some(*other);
+                        best_score = score;
+                    }
+                }
+            }
+
+            new_cache.truncate(CACHE_SIZE);
+            cache = new_cache;
+        }
+
+        self.indices = indices;
+
+        Ok(())
+    }
+
+    /// Reorders clusters of triangles so the ones facing outwards from the
+    /// middle of the mesh are drawn first, which lets the depth test reject
+    /// more of the hidden fragments. Clusters are split where the vertex
+    /// cache would be cold anyway, so this should be run after
+    /// `optimize_vertex_cache` and keeps most of its benefit.
+    pub fn optimize_overdraw(&mut self) -> Result<(), MeshError> {
+        self.check_triangles()?;
+
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+
+        // Split into clusters wherever a triangle misses the cache on every
+        // corner, since the cache has to be refilled there anyway.
+        let mut clusters: Vec<std::ops::Range<usize>> = Vec::new();
+        let mut cache: Vec<u32> = Vec::with_capacity(OVERDRAW_CACHE_SIZE + 3);
+        let mut start = 0;
+
+        for (triangle, corners) in self.indices.chunks(3).enumerate() {
+            let misses = corners.iter().filter(|index| !cache.contains(index)).count();
+
+            if misses == 3 && triangle > start {
+                clusters.push(start..triangle);
+                start = triangle;
+            }
+
+            for index in corners {
+                if !cache.contains(index) {
+                    cache.insert(0, *index);
+                }
+            }
+
+            cache.truncate(OVERDRAW_CACHE_SIZE);
+        }
+
+        clusters.push(start..self.indices.len() / 3);
+
+        let mut mesh_centroid = Vector3::new(0., 0., 0.);
+        let mut mesh_area = 0.;
+
+        let cluster_shapes: Vec<(Vector3<f32>, Vector3<f32>)> = clusters
+            .iter()
+            .map(|triangles| {
+                let mut centroid = Vector3::new(0., 0., 0.);
+                let mut normal = Vector3::new(0., 0., 0.);
+                let mut area = 0.;
+
+                for triangle in triangles.clone() {
+                    let a = self.vertices[self.indices[triangle * 3] as usize].position();
+                    let b = self.vertices[self.indices[triangle * 3 + 1] as usize].position();
+                    let c = self.vertices[self.indices[triangle * 3 + 2] as usize].position();
+
+                    let cross = (b - a).cross(c - a);
+                    let triangle_area = cross.magnitude();
+
+                    centroid += (a + b + c) / 3. * triangle_area;
+                    normal += cross;
+                    area += triangle_area;
+                }
+
+                mesh_centroid += centroid;
+                mesh_area += area;
+
+                let centroid = if area > 0. { centroid / area } else { centroid };
+
+                (centroid, normal)
+            })
+            .collect();
+
+        if mesh_area > 0. {
+            mesh_centroid /= mesh_area;
+        }
+
+        let mut order: Vec<usize> = (0..clusters.len()).collect();
+
+        let sort_key = |cluster: usize| {
+            let (centroid, normal) = cluster_shapes[cluster];
+            (centroid - mesh_centroid).dot(normal)
+        };
+
+        order.sort_by(|a, b| sort_key(*b).partial_cmp(&sort_key(*a)).unwrap_or(std::cmp::Ordering::Equal));
+
+        let indices = order
+            .iter()
+            .flat_map(|cluster| {
+                let triangles = &clusters[*cluster];
+                self.indices[triangles.start * 3..triangles.end * 3].iter().copied()
+            })
+            .collect();
+
+        self.indices = indices;
+
+        Ok(())
+    }
+
+    /// Reorders the vertices into the order the triangles first use them, so
+    /// vertex fetches walk through memory, and drops unused vertices. Morph
+    /// targets are reordered to match.
+    pub fn optimize_vertex_fetch(&mut self) -> Result<(), MeshError> {
+        self.check_triangles()?;
+
+        let mut remap: Vec<Option<u32>> = vec![None; self.vertices.len()];
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        let mut sources = Vec::with_capacity(self.vertices.len());
+
+        for index in self.indices.iter_mut() {
+            let old = *index as usize;
+
+            *index = match remap[old] {
+                Some(new) => new,
+                None => {
+                    vertices.push(self.vertices[old]);
+                    sources.push(old);
+
+                    let new = (vertices.len() - 1) as u32;
+                    remap[old] = Some(new);
+                    new
+                }
+            };
+        }
+
+        self.remap_morph_targets(&sources);
+        self.vertices = vertices;
+
+        Ok(())
+    }
+}
+
+/// How much adding a triangle using this vertex next is worth. Vertices
+/// recently used are cheap, and vertices with few triangles left are worth
+/// finishing off so they can leave the cache.
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.;
+    }
+
+    let cache_score = match cache_position {
+        // The last triangle's vertices get a fixed score so that the next
+        // triangle doesn't just reuse the last one's edge every time.
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1. - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.,
+    };
+
+    cache_score + 2. * (remaining_triangles as f32).powf(-0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CACHE_SIZE;
+    use crate::graphics::Mesh;
+
+    /// The average number of vertices transformed per triangle with a FIFO
+    /// cache of `CACHE_SIZE` vertices.
+    fn acmr(indices: &[u32]) -> f32 {
+        let mut cache: std::collections::VecDeque<u32> = std::collections::VecDeque::new();
+        let mut misses = 0;
+
+        for index in indices {
+            if !cache.contains(index) {
+                misses += 1;
+                cache.push_back(*index);
+
+                if cache.len() > CACHE_SIZE {
+                    cache.pop_front();
+                }
+            }
+        }
+
+        misses as f32 / (indices.len() / 3) as f32
+    }
+
+    /// The triangles of an index list, each rotated to start at its lowest
+    /// index and then sorted, so lists can be compared as sets of triangles.
+    fn sorted_triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+        let mut triangles: Vec<[u32; 3]> = indices
+            .chunks(3)
+            .map(|t| {
+                let first = (0..3).min_by_key(|corner| t[*corner]).unwrap();
+                [t[first], t[(first + 1) % 3], t[(first + 2) % 3]]
+            })
+            .collect();
+
+        triangles.sort();
+        triangles
+    }
+
+    /// A grid with its triangles in a scrambled order.
+    fn scrambled_grid() -> Mesh {
+        let mut mesh = Mesh::grid(1., 1., 40, 40);
+        let mut triangles: Vec<[u32; 3]> = mesh.get_indices().chunks(3).map(|t| [t[0], t[1], t[2]]).collect();
+
+        // A fixed linear congruential shuffle keeps the test repeatable.
+        let mut state: u64 = 12345;
+        for i in (1..triangles.len()).rev() {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            triangles.swap(i, (state >> 33) as usize % (i + 1));
+        }
+
+        mesh.set_indices(triangles.iter().flatten().copied().collect::<Vec<u32>>());
+        mesh
+    }
+
+    #[test]
+    fn vertex_cache_order_is_a_permutation_of_the_triangles() {
+        let mut mesh = scrambled_grid();
+        let before = sorted_triangles(mesh.get_indices());
+
+        mesh.optimize_vertex_cache().unwrap();
+
+        assert_eq!(sorted_triangles(mesh.get_indices()), before);
+    }
+
+    fn assert_acmr_not_raised(mut mesh: Mesh) {
+        let before = acmr(mesh.get_indices());
+
+        mesh.optimize_vertex_cache().unwrap();
+
+        let after = acmr(mesh.get_indices());
+        assert!(after <= before, "ACMR went from {} to {}", before, after);
+    }
+
+    #[test]
+    fn vertex_cache_order_does_not_raise_acmr() {
+        assert_acmr_not_raised(scrambled_grid());
+        assert_acmr_not_raised(Mesh::grid(1., 1., 40, 40));
+        assert_acmr_not_raised(Mesh::uv_sphere(1., 32, 16));
+    }
+}
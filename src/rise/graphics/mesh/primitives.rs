@@ -0,0 +1,535 @@
+use cgmath::{InnerSpace, Vector2, Vector3, Vector4};
+
+use super::{Mesh, StandardVertex};
+
+use std::f32::consts::PI;
+
+/// A point on the profile of a surface of revolution.
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    normal: Vector2<f32>,
+    v: f32,
+}
+
+impl Mesh {
+    /// A flat square on the xz plane facing up.
+    pub fn plane(size: f32) -> Mesh {
+        Mesh::grid(size, size, 1, 1)
+    }
+
+    /// A flat grid on the xz plane facing up, split into `columns` by `rows` quads.
+    pub fn grid(width: f32, depth: f32, columns: u32, rows: u32) -> Mesh {
+        let mut mesh = Mesh::new();
+
+        add_face(
+            &mut mesh,
+            Vector3::new(-width / 2., 0., -depth / 2.),
+            Vector3::new(width, 0., 0.),
+            Vector3::new(0., 0., depth),
+            columns.max(1),
+            rows.max(1),
+        );
+
+        mesh
+    }
+
+    /// An axis aligned cube centered on the origin, with each face split into
+    /// `subdivisions` by `subdivisions` quads.
+    pub fn cube(size: f32, subdivisions: u32) -> Mesh {
+        let mut mesh = Mesh::new();
+        let h = size / 2.;
+        let subdivisions = subdivisions.max(1);
+
+        // Each face is (origin, u axis, v axis), with v cross u pointing outwards.
+        let faces = [
+            (Vector3::new(-h, h, h), Vector3::new(size, 0., 0.), Vector3::new(0., -size, 0.)),
+            (Vector3::new(h, h, -h), Vector3::new(-size, 0., 0.), Vector3::new(0., -size, 0.)),
+            (Vector3::new(h, h, h), Vector3::new(0., 0., -size), Vector3::new(0., -size, 0.)),
+            (Vector3::new(-h, h, -h), Vector3::new(0., 0., size), Vector3::new(0., -size, 0.)),
+            (Vector3::new(-h, h, -h), Vector3::new(size, 0., 0.), Vector3::new(0., 0., size)),
+            (Vector3::new(-h, -h, h), Vector3::new(size, 0., 0.), Vector3::new(0., 0., -size)),
+        ];
+
+        for (origin, u_axis, v_axis) in faces.iter() {
+            add_face(&mut mesh, *origin, *u_axis, *v_axis, subdivisions, subdivisions);
+        }
+
+        mesh
+    }
+
+    /// A sphere built from `segments` slices around the y axis and `rings` stacks
+    /// from pole to pole.
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+        let rings = rings.max(2);
+
+        let profile: Vec<ProfilePoint> = (0..=rings)
+            .map(|ring| {
+                let theta = PI * ring as f32 / rings as f32;
+
+                ProfilePoint {
+                    radius: radius * theta.sin(),
+                    y: radius * theta.cos(),
+                    normal: Vector2::new(theta.sin(), theta.cos()),
+                    v: ring as f32 / rings as f32,
+                }
+            })
+            .collect();
+
+        revolve(&profile, segments.max(3))
+    }
+
+    /// A sphere built by repeatedly subdividing an icosahedron, giving evenly
+    /// sized triangles.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Mesh {
+        use std::collections::HashMap;
+
+        let t = (1. + 5f32.sqrt()) / 2.;
+
+        let mut positions: Vec<Vector3<f32>> = [
+            (-1., t, 0.), (1., t, 0.), (-1., -t, 0.), (1., -t, 0.),
+            (0., -1., t), (0., 1., t), (0., -1., -t), (0., 1., -t),
+            (t, 0., -1.), (t, 0., 1.), (-t, 0., -1.), (-t, 0., 1.),
+        ]
+        .iter()
+        .map(|(x, y, z)| Vector3::new(*x, *y, *z).normalize())
+        .collect();
+
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut next = Vec::with_capacity(triangles.len() * 4);
+
+            let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vector3<f32>>| -> u32 {
+                let key = if a < b { (a, b) } else { (b, a) };
+
+                *midpoints.entry(key).or_insert_with(|| {
+                    let mid = (positions[a as usize] + positions[b as usize]) / 2.;
+                    positions.push(mid.normalize());
+                    (positions.len() - 1) as u32
+                })
+            };
+
+            for [a, b, c] in triangles.iter().copied() {
+                let ab = midpoint(a, b, &mut positions);
+                let bc = midpoint(b, c, &mut positions);
+                let ca = midpoint(c, a, &mut positions);
+
+                next.push([a, ab, ca]);
+                next.push([b, bc, ab]);
+                next.push([c, ca, bc]);
+                next.push([ab, bc, ca]);
+            }
+
+            triangles = next;
+        }
+
+        let uvs: Vec<Vector2<f32>> = positions.iter().map(|p| sphere_uv(*p)).collect();
+
+        let mut mesh = Mesh::new();
+
+        for (position, uv) in positions.iter().zip(uvs.iter()) {
+            mesh.add_vertex(sphere_vertex(*position, radius, *uv));
+        }
+
+        // Triangles straddling the u seam get their low side duplicated with u
+        // shifted by one so the texture doesn't wrap back across the whole sphere.
+        // Pole vertices take the u of the rest of their triangle.
+        for triangle in triangles.iter() {
+            let mut corners = [
+                (triangle[0], uvs[triangle[0] as usize]),
+                (triangle[1], uvs[triangle[1] as usize]),
+                (triangle[2], uvs[triangle[2] as usize]),
+            ];
+
+            let max_u = corners.iter().map(|(_, uv)| uv.x).fold(f32::MIN, f32::max);
+            let min_u = corners.iter().map(|(_, uv)| uv.x).fold(f32::MAX, f32::min);
+            let wraps = max_u - min_u > 0.5;
+
+            let others_u: Vec<f32> = corners
+                .iter()
+                .filter(|(index, _)| !is_pole(positions[*index as usize]))
+                .map(|(_, uv)| if wraps && uv.x < 0.5 { uv.x + 1. } else { uv.x })
+                .collect();
+            let average_u = others_u.iter().sum::<f32>() / others_u.len().max(1) as f32;
+
+            for (index, uv) in corners.iter_mut() {
+                let position = positions[*index as usize];
+
+                let u = if is_pole(position) {
+                    average_u
+                } else if wraps && uv.x < 0.5 {
+                    uv.x + 1.
+                } else {
+                    continue;
+                };
+
+                mesh.add_vertex(sphere_vertex(position, radius, Vector2::new(u, uv.y)));
+                *index = (mesh.vertices.len() - 1) as u32;
+            }
+
+            for (index, _) in corners.iter() {
+                mesh.add_index(*index);
+            }
+        }
+
+        mesh
+    }
+
+    /// A capped cylinder centered on the origin along the y axis.
+    pub fn cylinder(radius: f32, height: f32, segments: u32, stacks: u32) -> Mesh {
+        frustum(radius, radius, height, segments.max(3), stacks.max(1))
+    }
+
+    /// A cone centered on the origin with its tip pointing up the y axis.
+    pub fn cone(radius: f32, height: f32, segments: u32, stacks: u32) -> Mesh {
+        frustum(0., radius, height, segments.max(3), stacks.max(1))
+    }
+
+    /// A capsule along the y axis. `height` is the length of the cylindrical
+    /// section, so the capsule is `height + 2 * radius` tall overall.
+    pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Mesh {
+        let rings = rings.max(1);
+        let half_height = height / 2.;
+
+        // Texture v runs along the length of the profile.
+        let length = PI * radius + height;
+
+        let mut profile = Vec::new();
+
+        for ring in 0..=rings {
+            let theta = PI / 2. * ring as f32 / rings as f32;
+
+            profile.push(ProfilePoint {
+                radius: radius * theta.sin(),
+                y: half_height + radius * theta.cos(),
+                normal: Vector2::new(theta.sin(), theta.cos()),
+                v: radius * theta / length,
+            });
+        }
+
+        for ring in 0..=rings {
+            let theta = PI / 2. + PI / 2. * ring as f32 / rings as f32;
+
+            profile.push(ProfilePoint {
+                radius: radius * theta.sin(),
+                y: -half_height + radius * theta.cos(),
+                normal: Vector2::new(theta.sin(), theta.cos()),
+                v: (height + radius * theta) / length,
+            });
+        }
+
+        revolve(&profile, segments.max(3))
+    }
+
+    /// A torus around the y axis. `major_radius` is the distance from the center
+    /// to the middle of the tube, `minor_radius` the radius of the tube itself.
+    pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Mesh {
+        let minor_segments = minor_segments.max(3);
+
+        let profile: Vec<ProfilePoint> = (0..=minor_segments)
+            .map(|segment| {
+                let psi = 2. * PI * (segment % minor_segments) as f32 / minor_segments as f32;
+
+                ProfilePoint {
+                    radius: major_radius + minor_radius * psi.cos(),
+                    y: -minor_radius * psi.sin(),
+                    normal: Vector2::new(psi.cos(), -psi.sin()),
+                    v: segment as f32 / minor_segments as f32,
+                }
+            })
+            .collect();
+
+        revolve(&profile, major_segments.max(3))
+    }
+}
+
+/// Adds a subdivided quad spanning `origin` to `origin + u_axis + v_axis`.
+/// The face is wound to point along `v_axis x u_axis`.
+fn add_face(
+    mesh: &mut Mesh,
+    origin: Vector3<f32>,
+    u_axis: Vector3<f32>,
+    v_axis: Vector3<f32>,
+    columns: u32,
+    rows: u32,
+) {
+    let normal = v_axis.cross(u_axis).normalize();
+    let tangent = u_axis.normalize().extend(-1.);
+    let base = mesh.vertices.len() as u32;
+
+    for row in 0..=rows {
+        for column in 0..=columns {
+            let u = column as f32 / columns as f32;
+            let v = row as f32 / rows as f32;
+
+            mesh.add_vertex(StandardVertex::with_tangent(
+                origin + u_axis * u + v_axis * v,
+                normal,
+                Vector2::new(u, v),
+                tangent,
+            ));
+        }
+    }
+
+    add_quads(mesh, base, columns, rows, |_| false, |_| false);
+}
+
+/// Indexes a `columns` by `rows` grid of quads whose vertices start at `base`.
+/// The closures say whether the top or bottom edge of a row collapses to a
+/// point, in which case the degenerate triangle is skipped.
+fn add_quads<T: Fn(u32) -> bool, B: Fn(u32) -> bool>(
+    mesh: &mut Mesh,
+    base: u32,
+    columns: u32,
+    rows: u32,
+    top_collapsed: T,
+    bottom_collapsed: B,
+) {
+    let stride = columns + 1;
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let i0 = base + row * stride + column;
+            let i1 = i0 + 1;
+            let i2 = i0 + stride;
+            let i3 = i2 + 1;
+
+            if !top_collapsed(row) {
+                mesh.add_index(i0);
+                mesh.add_index(i2);
+                mesh.add_index(i1);
+            }
+
+            if !bottom_collapsed(row) {
+                mesh.add_index(i1);
+                mesh.add_index(i2);
+                mesh.add_index(i3);
+            }
+        }
+    }
+}
+
+/// Sweeps a profile (given top to bottom) around the y axis.
+fn revolve(profile: &[ProfilePoint], segments: u32) -> Mesh {
+    let mut mesh = Mesh::new();
+
+    for point in profile {
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            // The last column closes the seam, so it reuses the first's angle
+            // to land on exactly the same positions.
+            let phi = 2. * PI * (segment % segments) as f32 / segments as f32;
+
+            // Poles sit exactly on the axis, whatever rounding left in the radius.
+            let position = if point.radius.abs() < f32::EPSILON {
+                Vector3::new(0., point.y, 0.)
+            } else {
+                Vector3::new(point.radius * phi.cos(), point.y, -point.radius * phi.sin())
+            };
+
+            mesh.add_vertex(StandardVertex::with_tangent(
+                position,
+                Vector3::new(point.normal.x * phi.cos(), point.normal.y, -point.normal.x * phi.sin()).normalize(),
+                Vector2::new(u, point.v),
+                revolve_tangent(phi),
+            ));
+        }
+    }
+
+    add_quads(
+        &mut mesh,
+        0,
+        segments,
+        (profile.len() - 1) as u32,
+        |row| profile[row as usize].radius.abs() < f32::EPSILON,
+        |row| profile[row as usize + 1].radius.abs() < f32::EPSILON,
+    );
+
+    mesh
+}
+
+/// A cylinder whose top and bottom radii can differ, with caps on any end that
+/// isn't a point.
+fn frustum(top_radius: f32, bottom_radius: f32, height: f32, segments: u32, stacks: u32) -> Mesh {
+    let half_height = height / 2.;
+    let normal = Vector2::new(height, bottom_radius - top_radius).normalize();
+
+    let profile: Vec<ProfilePoint> = (0..=stacks)
+        .map(|stack| {
+            let v = stack as f32 / stacks as f32;
+
+            ProfilePoint {
+                radius: top_radius + (bottom_radius - top_radius) * v,
+                y: half_height - height * v,
+                normal,
+                v,
+            }
+        })
+        .collect();
+
+    let mut mesh = revolve(&profile, segments);
+
+    if top_radius > 0. {
+        add_cap(&mut mesh, top_radius, half_height, 1., segments);
+    }
+
+    if bottom_radius > 0. {
+        add_cap(&mut mesh, bottom_radius, -half_height, -1., segments);
+    }
+
+    mesh
+}
+
+/// Adds a disc at height `y` facing up (`facing` of 1) or down (`facing` of -1).
+fn add_cap(mesh: &mut Mesh, radius: f32, y: f32, facing: f32, segments: u32) {
+    let normal = Vector3::new(0., facing, 0.);
+    let tangent = Vector4::new(1., 0., 0., -1.);
+    let center = mesh.vertices.len() as u32;
+
+    mesh.add_vertex(StandardVertex::with_tangent(Vector3::new(0., y, 0.), normal, Vector2::new(0.5, 0.5), tangent));
+
+    for segment in 0..=segments {
+        let phi = 2. * PI * (segment % segments) as f32 / segments as f32;
+
+        mesh.add_vertex(StandardVertex::with_tangent(
+            Vector3::new(radius * phi.cos(), y, -radius * phi.sin()),
+            normal,
+            Vector2::new(0.5 + phi.cos() / 2., 0.5 - facing * phi.sin() / 2.),
+            tangent,
+        ));
+    }
+
+    for segment in 0..segments {
+        let a = center + 1 + segment;
+        let b = a + 1;
+
+        mesh.add_index(center);
+
+        if facing > 0. {
+            mesh.add_index(a);
+            mesh.add_index(b);
+        } else {
+            mesh.add_index(b);
+            mesh.add_index(a);
+        }
+    }
+}
+
+/// The tangent of a surface of revolution at angle `phi`, pointing along
+/// increasing u. Texture v runs down the profile, which makes the bitangent
+/// sign negative.
+fn revolve_tangent(phi: f32) -> Vector4<f32> {
+    Vector4::new(-phi.sin(), 0., -phi.cos(), -1.)
+}
+
+/// A vertex on a sphere from its direction from the center.
+fn sphere_vertex(direction: Vector3<f32>, radius: f32, uv: Vector2<f32>) -> StandardVertex {
+    StandardVertex::with_tangent(direction * radius, direction, uv, revolve_tangent(2. * PI * uv.x))
+}
+
+/// Spherical texture coordinates for a point on the unit sphere, matching
+/// the layout used by `Mesh::uv_sphere`.
+fn sphere_uv(p: Vector3<f32>) -> Vector2<f32> {
+    let mut u = (-p.z).atan2(p.x) / (2. * PI);
+
+    if u < 0. {
+        u += 1.;
+    }
+
+    Vector2::new(u, p.y.max(-1.).min(1.).acos() / PI)
+}
+
+fn is_pole(p: Vector3<f32>) -> bool {
+    p.x.abs() < 1e-6 && p.z.abs() < 1e-6
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::graphics::{Mesh, Vertex};
+
+    fn all_primitives() -> Vec<(&'static str, Mesh)> {
+        vec![
+            ("grid", Mesh::grid(2., 3., 4, 5)),
+            ("cube", Mesh::cube(1., 3)),
+            ("uv sphere", Mesh::uv_sphere(1., 16, 8)),
+            ("icosphere", Mesh::icosphere(1., 2)),
+            ("cylinder", Mesh::cylinder(1., 2., 12, 2)),
+            ("cone", Mesh::cone(1., 2., 12, 2)),
+            ("capsule", Mesh::capsule(0.5, 1., 12, 4)),
+            ("torus", Mesh::torus(1., 0.25, 24, 12)),
+        ]
+    }
+
+    #[test]
+    fn subdivisions_give_the_expected_counts() {
+        let grid = Mesh::grid(2., 3., 4, 5);
+        assert_eq!((grid.vertices.len(), grid.indices.len() / 3), (5 * 6, 4 * 5 * 2));
+
+        let cube = Mesh::cube(1., 3);
+        assert_eq!((cube.vertices.len(), cube.indices.len() / 3), (6 * 4 * 4, 6 * 3 * 3 * 2));
+
+        // The triangles touching each pole collapse to one per segment.
+        let sphere = Mesh::uv_sphere(1., 16, 8);
+        assert_eq!((sphere.vertices.len(), sphere.indices.len() / 3), (9 * 17, 16 * 8 * 2 - 2 * 16));
+
+        let icosphere = Mesh::icosphere(1., 2);
+        assert_eq!(icosphere.indices.len() / 3, 20 * 4 * 4);
+    }
+
+    #[test]
+    fn triangles_face_along_their_unit_normals() {
+        for (name, mesh) in all_primitives() {
+            assert_eq!(mesh.indices.len() % 3, 0, "{}", name);
+
+            for triangle in mesh.indices.chunks(3) {
+                let vertices: Vec<_> = triangle.iter().map(|index| mesh.vertices[*index as usize]).collect();
+                let normals: Vec<_> = vertices.iter().map(|vertex| vertex.normal().unwrap()).collect();
+
+                for normal in normals.iter() {
+                    assert!((normal.magnitude() - 1.).abs() < 1e-4, "{} has a normal of length {}", name, normal.magnitude());
+                }
+
+                let face = (vertices[1].position() - vertices[0].position()).cross(vertices[2].position() - vertices[0].position());
+                assert!(face.magnitude() > 1e-7, "{} has a degenerate triangle", name);
+                assert!(face.dot(normals[0] + normals[1] + normals[2]) > 0., "{} has a triangle wound inwards", name);
+            }
+        }
+    }
+
+    #[test]
+    fn seams_close_on_exactly_the_same_vertices_apart_from_uvs() {
+        // Once uvs are ignored, only the seam columns (and rows, for a torus) weld.
+        let mut meshes = [(Mesh::uv_sphere(1., 16, 8), 9 * 16), (Mesh::torus(1., 0.25, 24, 12), 24 * 12)];
+
+        for (mesh, unique) in meshes.iter_mut() {
+            for vertex in mesh.vertices.iter_mut() {
+                vertex.set_uv(Vector2::new(0., 0.));
+            }
+
+            mesh.weld_vertices(0.).unwrap();
+
+            assert_eq!(mesh.vertices.len(), *unique);
+        }
+    }
+
+    #[test]
+    fn radius_and_size_are_respected() {
+        for vertex in Mesh::uv_sphere(2., 8, 4).vertices.iter().chain(Mesh::icosphere(2., 1).vertices.iter()) {
+            assert!((vertex.position().magnitude() - 2.).abs() < 1e-4);
+        }
+
+        for vertex in Mesh::cube(2., 1).vertices.iter() {
+            let position = vertex.position();
+            assert!((position.x.abs().max(position.y.abs()).max(position.z.abs()) - 1.).abs() < 1e-6);
+        }
+    }
+}
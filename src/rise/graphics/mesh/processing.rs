@@ -0,0 +1,414 @@
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use std::collections::HashMap;
+
+use super::{Mesh, MeshError, Vertex};
+
+impl<V: Vertex> Mesh<V> {
+    /// Gives every triangle its own vertices, each with the triangle's normal.
+    pub fn compute_flat_normals(&mut self) -> Result<(), MeshError> {
+        self.check_triangles()?;
+        self.check_normals()?;
+
+        let mut vertices = Vec::with_capacity(self.indices.len());
+
+        for triangle in self.indices.chunks(3) {
+            let normal = face_normal(
+                self.vertices[triangle[0] as usize].position(),
+                self.vertices[triangle[1] as usize].position(),
+                self.vertices[triangle[2] as usize].position(),
+            );
+
+            for index in triangle {
+                let mut vertex = self.vertices[*index as usize];
+                vertex.set_normal(normal);
+                vertices.push(vertex);
+            }
+        }
+
+        let sources: Vec<usize> = self.indices.iter().map(|index| *index as usize).collect();
+        self.remap_morph_targets(&sources);
+
+        self.indices = (0..vertices.len() as u32).collect();
+        self.vertices = vertices;
+
+        Ok(())
+    }
+
+    /// Computes smooth normals by averaging the normals of the triangles
+    /// around each position, weighted by the angle of each triangle's corner.
+    ///
+    /// Triangles whose normals differ by more than `crease_angle` don't
+    /// smooth into each other, so hard edges stay hard. Vertices at the same
+    /// position are treated as one (so uv seams don't show), and a vertex is
+    /// split when it sits on both sides of a crease.
+    pub fn compute_smooth_normals(&mut self, crease_angle: cgmath::Rad<f32>) -> Result<(), MeshError> {
+        self.check_triangles()?;
+        self.check_normals()?;
+
+        let triangle_count = self.indices.len() / 3;
+
+        let mut face_normals = Vec::with_capacity(triangle_count);
+        let mut corner_angles = Vec::with_capacity(self.indices.len());
+
+        for triangle in self.indices.chunks(3) {
+            let p = [
+                self.vertices[triangle[0] as usize].position(),
+                self.vertices[triangle[1] as usize].position(),
+                self.vertices[triangle[2] as usize].position(),
+            ];
+
+            face_normals.push(face_normal(p[0], p[1], p[2]));
+
+            for corner in 0..3 {
+                let a = p[(corner + 1) % 3] - p[corner];
+                let b = p[(corner + 2) % 3] - p[corner];
+
+                corner_angles.push(if a.magnitude2() > 0. && b.magnitude2() > 0. {
+                    a.angle(b).0
+                } else {
+                    0.
+                });
+            }
+        }
+
+        // Every corner that sits at a given position.
+        let mut corners_at: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+
+        for (corner, index) in self.indices.iter().enumerate() {
+            corners_at
+                .entry(position_key(self.vertices[*index as usize].position()))
+                .or_insert_with(Vec::new)
+                .push(corner);
+        }
+
+        let cos_crease = crease_angle.0.cos();
+
+        let normals: Vec<Vector3<f32>> = (0..self.indices.len())
+            .map(|corner| {
+                let face = face_normals[corner / 3];
+                let position = self.vertices[self.indices[corner] as usize].position();
+
+                let mut normal = Vector3::new(0., 0., 0.);
+
+                for other in &corners_at[&position_key(position)] {
+                    let other_face = face_normals[other / 3];
+
+                    if face.dot(other_face) >= cos_crease {
+                        normal += other_face * corner_angles[*other];
+                    }
+                }
+
+                if normal.magnitude2() > 0. {
+                    normal.normalize()
+                } else {
+                    face
+                }
+            })
+            .collect();
+
+        assign_corners(
+            self,
+            &normals,
+            |vertex, normal| vertex.normal().map_or(false, |n| n.dot(normal) > 0.9999),
+            |vertex, normal| vertex.set_normal(normal),
+        );
+
+        Ok(())
+    }
+
+    /// Gives the mesh texture coordinates by projecting it onto the plane of
+    /// the two longest sides of its bounding box, scaled to fill 0 to 1.
+    pub fn fill_default_uvs(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let mut min = self.vertices[0].position();
+        let mut max = min;
+
+        for vertex in &self.vertices {
+            let p = vertex.position();
+            min = Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            max = Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+        }
+
+        let size = max - min;
+
+        // The two longest axes, longest first.
+        let mut axes = [0, 1, 2];
+        axes.sort_by(|a, b| size[*b].partial_cmp(&size[*a]).unwrap_or(std::cmp::Ordering::Equal));
+
+        let (u_axis, v_axis) = (axes[0], axes[1]);
+
+        for vertex in self.vertices.iter_mut() {
+            let p = vertex.position();
+
+            let u = if size[u_axis] > 0. { (p[u_axis] - min[u_axis]) / size[u_axis] } else { 0. };
+            let v = if size[v_axis] > 0. { (max[v_axis] - p[v_axis]) / size[v_axis] } else { 0. };
+
+            vertex.set_uv(Vector2::new(u, v));
+        }
+    }
+
+    /// Merges vertices that are identical apart from positions closer than
+    /// `epsilon`, and drops any vertices no longer referenced. Vertices that
+    /// morph targets move differently are kept apart.
+    pub fn weld_vertices(&mut self, epsilon: f32) -> Result<(), MeshError> {
+        self.check_triangles()?;
+
+        let mut remap: HashMap<Vec<u8>, u32> = HashMap::new();
+        let mut vertices: Vec<V> = Vec::new();
+        let mut sources: Vec<usize> = Vec::new();
+
+        let new_indices: Vec<u32> = self
+            .indices
+            .iter()
+            .map(|index| {
+                let vertex = self.vertices[*index as usize];
+
+                let mut key = vertex;
+                if epsilon > 0. {
+                    let p = vertex.position();
+                    key.set_position(Vector3::new(
+                        (p.x / epsilon).round(),
+                        (p.y / epsilon).round(),
+                        (p.z / epsilon).round(),
+                    ));
+                }
+
+                let mut key = bytemuck::bytes_of(&key).to_vec();
+
+                for target in &self.morph_targets {
+                    let deltas = std::iter::once(target.position_deltas[*index as usize])
+                        .chain(target.normal_deltas.as_ref().map(|deltas| deltas[*index as usize]));
+
+                    for delta in deltas {
+                        key.extend_from_slice(bytemuck::cast_slice(&[delta.x, delta.y, delta.z]));
+                    }
+                }
+
+                *remap.entry(key).or_insert_with(|| {
+                    vertices.push(vertex);
+                    sources.push(*index as usize);
+                    (vertices.len() - 1) as u32
+                })
+            })
+            .collect();
+
+        self.remap_morph_targets(&sources);
+        self.vertices = vertices;
+        self.indices = new_indices;
+
+        Ok(())
+    }
+
+    fn check_normals(&self) -> Result<(), MeshError> {
+        match self.vertices.first() {
+            Some(vertex) if vertex.normal().is_none() => {
+                Err(MeshError::new("Vertex type has no normal to compute"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes sure the mesh is a valid triangle list.
+    pub fn check_triangles(&self) -> Result<(), MeshError> {
+        if self.indices.len() % 3 != 0 {
+            return Err(MeshError::new(&format!(
+                "Mesh has {} indices, which isn't a whole number of triangles", self.indices.len()
+            )));
+        }
+
+        if let Some(index) = self.indices.iter().find(|index| **index as usize >= self.vertices.len()) {
+            return Err(MeshError::new(&format!(
+                "Index {} is out of range for {} vertices", index, self.vertices.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Gives each triangle corner its own value of some attribute. Where corners
+/// sharing a vertex disagree, the vertex is duplicated so each keeps its own.
+pub(crate) fn assign_corners<V: Vertex, T: Copy>(
+    mesh: &mut Mesh<V>,
+    values: &[T],
+    same: impl Fn(&V, T) -> bool,
+    set: impl Fn(&mut V, T),
+) {
+    // For each original vertex, every copy of it that has been given a value.
+    let mut copies: Vec<Vec<u32>> = vec![Vec::new(); mesh.vertices.len()];
+
+    for (corner, value) in values.iter().enumerate() {
+        let index = mesh.indices[corner] as usize;
+
+        if copies[index].is_empty() {
+            set(&mut mesh.vertices[index], *value);
+            copies[index].push(index as u32);
+            continue;
+        }
+
+        let existing = copies[index]
+            .iter()
+            .copied()
+            .find(|copy| same(&mesh.vertices[*copy as usize], *value));
+
+        mesh.indices[corner] = match existing {
+            Some(copy) => copy,
+            None => {
+                let mut vertex = mesh.vertices[index];
+                set(&mut vertex, *value);
+                mesh.vertices.push(vertex);
+
+                // Copies move with the morph targets like the vertex they
+                // were split from.
+                for target in mesh.morph_targets.iter_mut() {
+                    let delta = target.position_deltas[index];
+                    target.position_deltas.push(delta);
+
+                    if let Some(normal_deltas) = &mut target.normal_deltas {
+                        let delta = normal_deltas[index];
+                        normal_deltas.push(delta);
+                    }
+                }
+
+                let copy = (mesh.vertices.len() - 1) as u32;
+                copies[index].push(copy);
+                copy
+            }
+        };
+    }
+}
+
+pub(crate) fn face_normal(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Vector3<f32> {
+    let normal = (b - a).cross(c - a);
+
+    if normal.magnitude2() > 0. {
+        // Adding zero turns -0 into 0, so welding sees equal normals as equal.
+        normal.normalize() + Vector3::new(0., 0., 0.)
+    } else {
+        Vector3::new(0., 0., 0.)
+    }
+}
+
+fn position_key(p: Vector3<f32>) -> [u32; 3] {
+    [(p.x + 0.).to_bits(), (p.y + 0.).to_bits(), (p.z + 0.).to_bits()]
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, InnerSpace, Vector2, Vector3};
+
+    use super::face_normal;
+    use crate::graphics::{Mesh, StandardVertex};
+
+    fn vertex(x: f32, y: f32, u: f32) -> StandardVertex {
+        StandardVertex::new(Vector3::new(x, y, 0.), Vector3::new(0., 0., 1.), Vector2::new(u, y))
+    }
+
+    /// A unit quad as two triangles that don't share vertices.
+    fn unwelded_quad() -> Mesh {
+        let mut mesh = Mesh::new();
+
+        for (x, y) in [(0., 0.), (1., 0.), (1., 1.), (0., 0.), (1., 1.), (0., 1.)].iter() {
+            mesh.add_vertex(vertex(*x, *y, *x));
+        }
+
+        mesh.set_indices(0..6);
+        mesh
+    }
+
+    fn triangle_normals(mesh: &Mesh) -> Vec<Vector3<f32>> {
+        mesh.indices
+            .chunks(3)
+            .map(|triangle| {
+                let p: Vec<_> = triangle.iter().map(|index| mesh.vertices[*index as usize].get_position()).collect();
+                face_normal(p[0], p[1], p[2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn welding_merges_duplicates() {
+        let mut mesh = unwelded_quad();
+        mesh.weld_vertices(0.).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+        assert_eq!(mesh.indices[0], mesh.indices[3]);
+        assert_eq!(mesh.indices[2], mesh.indices[4]);
+    }
+
+    #[test]
+    fn welding_merges_within_epsilon_but_keeps_other_attributes_apart() {
+        let mut mesh = unwelded_quad();
+        mesh.vertices[3] = vertex(0.00001, 0., 0.);
+        mesh.weld_vertices(0.001).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+
+        let mut mesh = unwelded_quad();
+        mesh.vertices[3] = vertex(0., 0., 0.5);
+        mesh.weld_vertices(0.001).unwrap();
+        assert_eq!(mesh.vertices.len(), 5);
+    }
+
+    #[test]
+    fn flat_normals_match_each_triangle() {
+        let mut mesh = Mesh::uv_sphere(1., 8, 4);
+        mesh.compute_flat_normals().unwrap();
+
+        assert_eq!(mesh.vertices.len(), mesh.indices.len());
+
+        for (triangle, normal) in mesh.indices.chunks(3).zip(triangle_normals(&mesh)) {
+            for index in triangle {
+                assert!(mesh.vertices[*index as usize].get_normal().dot(normal) > 0.9999);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_normals_respect_the_crease_angle() {
+        // The faces of a cube meet at 90 degrees, so a smaller crease keeps them flat.
+        let mut cube = Mesh::cube(1., 1);
+        cube.compute_smooth_normals(Deg(60.).into()).unwrap();
+
+        assert_eq!(cube.vertices.len(), 24);
+        for (triangle, normal) in cube.indices.chunks(3).zip(triangle_normals(&cube)) {
+            for index in triangle {
+                assert!(cube.vertices[*index as usize].get_normal().dot(normal) > 0.9999);
+            }
+        }
+
+        // A larger one rounds off every corner.
+        cube.compute_smooth_normals(Deg(100.).into()).unwrap();
+
+        for vertex in cube.vertices.iter() {
+            let diagonal = vertex.get_position().normalize();
+            assert!(vertex.get_normal().dot(diagonal) > 0.9999);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_of_a_sphere_point_outwards() {
+        let mut sphere = Mesh::uv_sphere(1., 16, 8);
+        sphere.compute_flat_normals().unwrap();
+        sphere.compute_smooth_normals(Deg(60.).into()).unwrap();
+
+        for vertex in sphere.vertices.iter() {
+            assert!(vertex.get_normal().dot(vertex.get_position()) > 0.99);
+        }
+    }
+
+    #[test]
+    fn rejects_meshes_that_arent_triangle_lists() {
+        let mut mesh = unwelded_quad();
+        mesh.set_indices(vec![0, 1, 2, 3]);
+        assert!(mesh.weld_vertices(0.).is_err());
+
+        mesh.set_indices(vec![0, 1, 6]);
+        assert!(mesh.compute_flat_normals().is_err());
+        assert!(mesh.compute_smooth_normals(Deg(60.).into()).is_err());
+    }
+}
@@ -0,0 +1,605 @@
+use cgmath::{InnerSpace, Vector3};
+
+use std::collections::HashMap;
+
+use super::{Aabb, Mesh, MeshError, Vertex};
+
+/// The most of the remaining triangles a single pass will try to remove.
+/// Collapses within a pass use stale costs, so passes are kept short.
+const PASS_FRACTION: f32 = 0.2;
+
+/// The cosine of the furthest a surviving triangle's normal may turn, both
+/// in a single collapse and from where it started.
+const MAX_NORMAL_TURN_COS: f64 = 0.25;
+
+/// How strongly open borders resist being pulled inwards.
+const BORDER_WEIGHT: f64 = 10.;
+
+impl<V: Vertex> Mesh<V> {
+    /// Builds a simplified copy of the mesh by repeatedly collapsing the edge
+    /// that moves the surface least (the quadric error metric of Garland and
+    /// Heckbert), until it's down to `target_triangles` or the next collapse
+    /// would move the surface further than `max_error`.
+    ///
+    /// `max_error` is relative to the size of the mesh, so 0.01 allows the
+    /// surface to move by 1% of its bounding box's longest side.
+    ///
+    /// Edges are collapsed onto one of their existing vertices, so every
+    /// vertex keeps its original attributes. Vertices on uv or normal seams
+    /// stay where they are so the result doesn't tear, and open borders only
+    /// collapse along themselves. Duplicate vertices count as seams, so weld
+    /// the mesh first if it has any. Morph targets are carried over, but
+    /// the collapses only look at the base shape.
+    pub fn simplify(&self, target_triangles: usize, max_error: f32) -> Result<Mesh<V>, MeshError> {
+        self.check_triangles()?;
+
+        let mut simplifier = Simplifier::new(self);
+
+        let scale = Aabb::from_points(self.vertices.iter().map(|vertex| vertex.position()))
+            .map(|bounds| {
+                let size = bounds.size();
+                size.x.max(size.y).max(size.z) as f64
+            })
+            .unwrap_or(0.);
+
+        let max_error = max_error as f64 * scale;
+
+        while simplifier.triangle_count > target_triangles {
+            if !simplifier.pass(target_triangles, max_error * max_error) {
+                break;
+            }
+        }
+
+        let mut mesh = Mesh::new();
+        mesh.set_usage(self.get_usage());
+        mesh.set_vertices(self.vertices.iter().copied());
+        mesh.set_indices(simplifier.indices());
+        mesh.morph_targets = self.morph_targets.clone();
+        mesh.morph_weights = self.morph_weights.clone();
+        mesh.optimize_vertex_fetch()?;
+
+        Ok(mesh)
+    }
+
+    /// Builds a chain of levels of detail, each with `ratio` times as many
+    /// triangles as the one before. Every level is simplified from this mesh
+    /// rather than the previous level so errors don't accumulate, and is
+    /// optimized for drawing. Levels that can't get any simpler within
+    /// `max_error` end the chain early.
+    pub fn generate_lods(&self, levels: usize, ratio: f32, max_error: f32) -> Result<Vec<Mesh<V>>, MeshError> {
+        let mut lods: Vec<Mesh<V>> = Vec::with_capacity(levels);
+        let mut target = self.indices.len() as f32 / 3.;
+
+        for _ in 0..levels {
+            target *= ratio;
+
+            let mut lod = self.simplify(target as usize, max_error)?;
+
+            let previous_triangles = lods.last().map_or(self.indices.len(), |lod| lod.indices.len());
+
+            if lod.indices.len() >= previous_triangles {
+                break;
+            }
+
+            lod.optimize()?;
+            lods.push(lod);
+        }
+
+        Ok(lods)
+    }
+}
+
+/// A symmetric 4x4 matrix measuring the summed squared distance of a point
+/// from a set of planes, along with the total weight of those planes.
+#[derive(Copy, Clone)]
+struct Quadric {
+    a: [f64; 10],
+    weight: f64,
+}
+
+impl Quadric {
+    fn zero() -> Quadric {
+        Quadric {
+            a: [0.; 10],
+            weight: 0.,
+        }
+    }
+
+    /// The plane through `point` with unit `normal`, scaled by `weight`.
+    fn plane(point: Vector3<f64>, normal: Vector3<f64>, weight: f64) -> Quadric {
+        let (x, y, z) = (normal.x, normal.y, normal.z);
+        let w = -normal.dot(point);
+
+        let mut a = [
+            x * x, x * y, x * z, x * w,
+            y * y, y * z, y * w,
+            z * z, z * w,
+            w * w,
+        ];
+
+        for value in a.iter_mut() {
+            *value *= weight;
+        }
+
+        Quadric { a, weight }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for i in 0..10 {
+            self.a[i] += other.a[i];
+        }
+
+        self.weight += other.weight;
+    }
+
+    /// The mean squared distance of `p` from the planes.
+    fn error(&self, p: Vector3<f64>) -> f64 {
+        if self.weight <= 0. {
+            return 0.;
+        }
+
+        let a = &self.a;
+        let (x, y, z) = (p.x, p.y, p.z);
+
+        let error = a[0] * x * x + 2. * a[1] * x * y + 2. * a[2] * x * z + 2. * a[3] * x
+            + a[4] * y * y + 2. * a[5] * y * z + 2. * a[6] * y
+            + a[7] * z * z + 2. * a[8] * z
+            + a[9];
+
+        error.max(0.) / self.weight
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    /// Surrounded by triangles and with a single vertex, free to move.
+    Manifold,
+    /// On an open edge of the mesh, may only move along it.
+    Border,
+    /// On a seam or a non-manifold edge, never moves.
+    Locked,
+}
+
+/// The working state of a simplification. Vertices that share a position are
+/// grouped so the mesh is simplified as one surface across seams.
+struct Simplifier {
+    /// The position group of each vertex.
+    groups: Vec<usize>,
+    positions: Vec<Vector3<f64>>,
+    /// How many distinct vertices sit at each position.
+    wedges: Vec<usize>,
+    quadrics: Vec<Quadric>,
+    /// The live triangles touching each position.
+    group_triangles: Vec<Vec<usize>>,
+    triangles: Vec<[u32; 3]>,
+    /// The direction each triangle faced before simplifying started.
+    original_normals: Vec<Vector3<f64>>,
+    alive: Vec<bool>,
+    triangle_count: usize,
+}
+
+impl Simplifier {
+    fn new<V: Vertex>(mesh: &Mesh<V>) -> Simplifier {
+        let mut group_of: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let mut wedges = Vec::new();
+
+        let groups: Vec<usize> = mesh
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let p = vertex.position();
+                let key = [(p.x + 0.).to_bits(), (p.y + 0.).to_bits(), (p.z + 0.).to_bits()];
+
+                *group_of.entry(key).or_insert_with(|| {
+                    positions.push(Vector3::new(p.x as f64, p.y as f64, p.z as f64));
+                    wedges.push(0);
+                    positions.len() - 1
+                })
+            })
+            .collect();
+
+        // Only count vertices that are actually used, so stray duplicates
+        // don't lock a position.
+        let mut used = vec![false; mesh.vertices.len()];
+
+        for index in &mesh.indices {
+            if !used[*index as usize] {
+                used[*index as usize] = true;
+                wedges[groups[*index as usize]] += 1;
+            }
+        }
+
+        let triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks(3)
+            .map(|corners| [corners[0], corners[1], corners[2]])
+            .collect();
+
+        let mut simplifier = Simplifier {
+            quadrics: vec![Quadric::zero(); positions.len()],
+            group_triangles: vec![Vec::new(); positions.len()],
+            original_normals: vec![Vector3::new(0., 0., 0.); triangles.len()],
+            alive: vec![true; triangles.len()],
+            triangle_count: triangles.len(),
+            groups,
+            positions,
+            wedges,
+            triangles,
+        };
+
+        for triangle in 0..simplifier.triangles.len() {
+            let g = simplifier.triangle_groups(triangle);
+
+            if g[0] == g[1] || g[1] == g[2] || g[2] == g[0] {
+                simplifier.alive[triangle] = false;
+                simplifier.triangle_count -= 1;
+                continue;
+            }
+
+            for group in &g {
+                simplifier.group_triangles[*group].push(triangle);
+            }
+
+            let (a, b, c) = (simplifier.positions[g[0]], simplifier.positions[g[1]], simplifier.positions[g[2]]);
+            let cross = (b - a).cross(c - a);
+            let area = cross.magnitude();
+
+            simplifier.original_normals[triangle] = cross;
+
+            if area > 0. {
+                let quadric = Quadric::plane(a, cross / area, area);
+
+                for group in &g {
+                    simplifier.quadrics[*group].add(&quadric);
+                }
+            }
+        }
+
+        // Borders get an extra plane through each open edge, at right angles
+        // to the surface, so collapses don't eat into the outline.
+        let edges = simplifier.edge_counts();
+
+        for triangle in 0..simplifier.triangles.len() {
+            if !simplifier.alive[triangle] {
+                continue;
+            }
+
+            let g = simplifier.triangle_groups(triangle);
+            let (a, b, c) = (simplifier.positions[g[0]], simplifier.positions[g[1]], simplifier.positions[g[2]]);
+            let normal = (b - a).cross(c - a);
+
+            for corner in 0..3 {
+                let (from, to) = (g[corner], g[(corner + 1) % 3]);
+
+                if edges[&edge_key(from, to)] != 1 {
+                    continue;
+                }
+
+                let edge = simplifier.positions[to] - simplifier.positions[from];
+                let plane_normal = edge.cross(normal);
+
+                if plane_normal.magnitude2() > 0. {
+                    let quadric = Quadric::plane(
+                        simplifier.positions[from],
+                        plane_normal.normalize(),
+                        edge.magnitude2() * BORDER_WEIGHT
+                    );
+
+                    simplifier.quadrics[from].add(&quadric);
+                    simplifier.quadrics[to].add(&quadric);
+                }
+            }
+        }
+
+        simplifier
+    }
+
+    fn triangle_groups(&self, triangle: usize) -> [usize; 3] {
+        let t = self.triangles[triangle];
+        [self.groups[t[0] as usize], self.groups[t[1] as usize], self.groups[t[2] as usize]]
+    }
+
+    /// How many live triangles use each edge between two positions.
+    fn edge_counts(&self) -> HashMap<(usize, usize), usize> {
+        let mut edges = HashMap::new();
+
+        for triangle in 0..self.triangles.len() {
+            if !self.alive[triangle] {
+                continue;
+            }
+
+            let g = self.triangle_groups(triangle);
+
+            for corner in 0..3 {
+                *edges.entry(edge_key(g[corner], g[(corner + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+
+        edges
+    }
+
+    /// Collapses the cheapest edges, returning false if nothing could be
+    /// collapsed.
+    fn pass(&mut self, target_triangles: usize, max_error: f64) -> bool {
+        let edges = self.edge_counts();
+
+        let mut kinds = vec![Kind::Manifold; self.positions.len()];
+
+        for (group, wedges) in self.wedges.iter().enumerate() {
+            if *wedges > 1 {
+                kinds[group] = Kind::Locked;
+            }
+        }
+
+        for ((a, b), count) in &edges {
+            for group in &[*a, *b] {
+                kinds[*group] = match (kinds[*group], count) {
+                    (Kind::Locked, _) => Kind::Locked,
+                    (_, 1) => Kind::Border,
+                    (kind, 2) => kind,
+                    _ => Kind::Locked,
+                };
+            }
+        }
+
+        // Every allowed collapse, as (cost, from, to).
+        let mut collapses: Vec<(f64, usize, usize)> = Vec::new();
+
+        for ((a, b), count) in &edges {
+            let mut best: Option<(f64, usize, usize)> = None;
+
+            for (from, to) in &[(*a, *b), (*b, *a)] {
+                let allowed = match kinds[*from] {
+                    Kind::Manifold => true,
+                    Kind::Border => kinds[*to] != Kind::Manifold && *count == 1,
+                    Kind::Locked => false,
+                };
+
+                if !allowed {
+                    continue;
+                }
+
+                let mut quadric = self.quadrics[*from];
+                quadric.add(&self.quadrics[*to]);
+
+                let cost = quadric.error(self.positions[*to]);
+
+                if best.map_or(true, |(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, *from, *to));
+                }
+            }
+
+            if let Some(collapse) = best {
+                if collapse.0 <= max_error {
+                    collapses.push(collapse);
+                }
+            }
+        }
+
+        collapses.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let pass_target = target_triangles.max(
+            self.triangle_count - (self.triangle_count as f32 * PASS_FRACTION).ceil() as usize
+        );
+
+        let mut touched = vec![false; self.positions.len()];
+        let mut collapsed = false;
+
+        for (_, from, to) in collapses {
+            if self.triangle_count <= pass_target {
+                break;
+            }
+
+            if touched[from] || touched[to] {
+                continue;
+            }
+
+            if self.collapse(from, to) {
+                collapsed = true;
+
+                // Neighbours' costs are now stale, leave them for the next pass.
+                for triangle in &self.group_triangles[to] {
+                    for group in &self.triangle_groups(*triangle) {
+                        touched[*group] = true;
+                    }
+                }
+            }
+        }
+
+        collapsed
+    }
+
+    /// Moves position `from` onto position `to`, unless doing so would flip
+    /// a triangle, fold the surface onto itself, or there's no single vertex
+    /// at `to` to take over.
+    fn collapse(&mut self, from: usize, to: usize) -> bool {
+        // Positions next to both ends of the edge must all be the far corners
+        // of the triangles on the edge, otherwise the collapse would pinch
+        // the surface into a fin.
+        let from_neighbours = self.neighbours(from);
+        let to_neighbours = self.neighbours(to);
+
+        let shared = from_neighbours.iter().filter(|group| to_neighbours.contains(group)).count();
+        let edge_triangles = self.group_triangles[from]
+            .iter()
+            .filter(|triangle| self.triangle_groups(**triangle).contains(&to))
+            .count();
+
+        if shared != edge_triangles {
+            return false;
+        }
+
+        let mut replacement: Option<u32> = None;
+
+        for triangle in &self.group_triangles[from] {
+            let t = self.triangles[*triangle];
+            let g = self.triangle_groups(*triangle);
+
+            if let Some(corner) = g.iter().position(|group| *group == to) {
+                match replacement {
+                    Some(vertex) if vertex != t[corner] => return false,
+                    _ => replacement = Some(t[corner]),
+                }
+
+                continue;
+            }
+
+            // The triangle survives the collapse, make sure it doesn't swing
+            // round too far, either in this collapse or in total after
+            // several smaller turns.
+            let p = [self.positions[g[0]], self.positions[g[1]], self.positions[g[2]]];
+            let mut moved = p;
+
+            for corner in 0..3 {
+                if g[corner] == from {
+                    moved[corner] = self.positions[to];
+                }
+            }
+
+            let before = (p[1] - p[0]).cross(p[2] - p[0]);
+            let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+            let original = self.original_normals[*triangle];
+
+            if before.dot(after) <= MAX_NORMAL_TURN_COS * before.magnitude() * after.magnitude()
+                || original.dot(after) <= MAX_NORMAL_TURN_COS * original.magnitude() * after.magnitude()
+            {
+                return false;
+            }
+        }
+
+        let replacement = match replacement {
+            Some(replacement) => replacement,
+            None => return false,
+        };
+
+        let triangles = std::mem::replace(&mut self.group_triangles[from], Vec::new());
+
+        for triangle in triangles {
+            let g = self.triangle_groups(triangle);
+
+            if g.contains(&to) {
+                self.alive[triangle] = false;
+                self.triangle_count -= 1;
+
+                for group in &g {
+                    self.group_triangles[*group].retain(|other| *other != triangle);
+                }
+
+                continue;
+            }
+
+            for corner in 0..3 {
+                if g[corner] == from {
+                    self.triangles[triangle][corner] = replacement;
+                }
+            }
+
+            self.group_triangles[to].push(triangle);
+        }
+
+        let quadric = self.quadrics[from];
+        self.quadrics[to].add(&quadric);
+
+        true
+    }
+
+    fn neighbours(&self, group: usize) -> Vec<usize> {
+        let mut neighbours = Vec::new();
+
+        for triangle in &self.group_triangles[group] {
+            for other in &self.triangle_groups(*triangle) {
+                if *other != group && !neighbours.contains(other) {
+                    neighbours.push(*other);
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    fn indices(&self) -> Vec<u32> {
+        self.triangles
+            .iter()
+            .zip(&self.alive)
+            .filter(|(_, alive)| **alive)
+            .flat_map(|(triangle, _)| triangle.iter().copied())
+            .collect()
+    }
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::InnerSpace;
+
+    use crate::graphics::{Aabb, Mesh, Vertex};
+
+    /// Whether every triangle faces away from the origin.
+    fn faces_outwards(mesh: &Mesh) -> bool {
+        mesh.indices.chunks(3).all(|triangle| {
+            let p: Vec<_> = triangle.iter().map(|index| mesh.vertices[*index as usize].position()).collect();
+            (p[1] - p[0]).cross(p[2] - p[0]).dot(p[0] + p[1] + p[2]) > 0.
+        })
+    }
+
+    #[test]
+    fn reaches_the_target_triangle_count() {
+        let sphere = Mesh::icosphere(1., 3);
+        let simplified = sphere.simplify(320, 1.).unwrap();
+
+        simplified.check_triangles().unwrap();
+        assert!(simplified.indices.len() / 3 <= 320);
+        assert!(simplified.indices.len() / 3 > 100);
+        assert!(faces_outwards(&simplified));
+    }
+
+    #[test]
+    fn stops_at_the_error_limit() {
+        let sphere = Mesh::icosphere(1., 2);
+        assert_eq!(sphere.simplify(0, 1e-6).unwrap().indices.len(), sphere.indices.len());
+
+        // A flat grid can lose every interior vertex without moving.
+        let grid = Mesh::grid(2., 2., 8, 8);
+        let simplified = grid.simplify(0, 1e-6).unwrap();
+        let bounds = |mesh: &Mesh| Aabb::from_points(mesh.indices.iter().map(|index| mesh.vertices[*index as usize].position()));
+
+        assert!(simplified.indices.len() < grid.indices.len() / 4);
+        assert_eq!(bounds(&simplified), bounds(&grid));
+    }
+
+    #[test]
+    fn seams_stay_in_place() {
+        let sphere = Mesh::uv_sphere(1., 32, 16);
+        let simplified = sphere.simplify(50, 1.).unwrap();
+
+        assert!(simplified.indices.len() / 3 < sphere.indices.len() / 3);
+
+        // The u seam runs down the x axis side, where u is 0 on one side and 1 on the other.
+        for vertex in sphere.vertices.iter().filter(|vertex| vertex.get_uv().x == 1. && vertex.get_uv().y > 0. && vertex.get_uv().y < 1.) {
+            let kept = simplified.indices.iter().any(|index| {
+                let other = simplified.vertices[*index as usize];
+                other.get_uv() == vertex.get_uv() && (other.position() - vertex.position()).magnitude() == 0.
+            });
+
+            assert!(kept, "{:?} was collapsed", vertex.position());
+        }
+    }
+
+    #[test]
+    fn levels_of_detail_get_simpler() {
+        let sphere = Mesh::icosphere(1., 3);
+        let lods = sphere.generate_lods(3, 0.5, 1.).unwrap();
+
+        assert_eq!(lods.len(), 3);
+
+        let mut previous = sphere.indices.len();
+        for lod in lods.iter() {
+            assert!(lod.indices.len() < previous);
+            previous = lod.indices.len();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::{Mesh, MeshError, Vertex};
+
+/// Adapts a mesh's triangle list to the `mikktspace` geometry interface,
+/// collecting one tangent per triangle corner.
+struct TangentGeometry<'a, V> {
+    vertices: &'a [V],
+    indices: &'a [u32],
+    tangents: Vec<[f32; 4]>,
+}
+
+impl<'a, V: Vertex> TangentGeometry<'a, V> {
+    fn vertex(&self, face: usize, vert: usize) -> &V {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl<'a, V: Vertex> mikktspace::Geometry for TangentGeometry<'a, V> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position().into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal().unwrap().into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.vertex(face, vert).uv().unwrap().into()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.tangents[face * 3 + vert] = tangent;
+    }
+}
+
+impl<V: Vertex> Mesh<V> {
+    /// Generates MikkTSpace tangents for every vertex, so normal maps baked by
+    /// other tools line up. The vertex type needs normals, texture coordinates
+    /// and tangents.
+    ///
+    /// Vertices shared between triangles that end up with different tangents
+    /// (e.g. across a uv mirror) are split so each keeps its own.
+    pub fn generate_tangents(&mut self) -> Result<(), MeshError> {
+        let sample = self.vertices.first();
+
+        if sample.map_or(false, |v| v.normal().is_none() || v.uv().is_none() || v.tangent().is_none()) {
+            return Err(MeshError::new("Tangent generation requires normals, texture coordinates and tangents"));
+        }
+
+        self.check_triangles()?;
+
+        let tangents = {
+            let mut geometry = TangentGeometry {
+                vertices: &self.vertices[..],
+                indices: &self.indices[..],
+                tangents: vec![[0.; 4]; self.indices.len()],
+            };
+
+            if !mikktspace::generate_tangents(&mut geometry) {
+                return Err(MeshError::new("Unable to generate tangents"));
+            }
+
+            geometry.tangents
+        };
+
+        let tangents: Vec<cgmath::Vector4<f32>> = tangents.iter().map(|t| cgmath::Vector4::from(*t)).collect();
+
+        super::processing::assign_corners(
+            self,
+            &tangents,
+            |vertex, tangent| vertex.tangent().map_or(false, |t| same_tangent(t, tangent)),
+            |vertex, tangent| vertex.set_tangent(tangent),
+        );
+
+        Ok(())
+    }
+}
+
+fn same_tangent(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) -> bool {
+    use cgmath::InnerSpace;
+
+    a.w == b.w && a.truncate().dot(b.truncate()) > 0.999
+}
@@ -0,0 +1,252 @@
+use cgmath::{Vector2, Vector3, Vector4};
+
+pub use wgpu::{VertexAttributeDescriptor, VertexFormat};
+
+pub use rise_derive::Vertex;
+
+/// Describes the memory layout of a vertex type, so meshes and material
+/// pipelines can be built for it.
+///
+/// This is normally implemented with `#[derive(Vertex)]`, which lays out one
+/// attribute per field at consecutive shader locations. The struct must be
+/// `#[repr(C)]` and tightly packed. Each field's format comes from its type
+/// (see `VertexAttributeType`) and can be overridden or relocated with
+/// `#[vertex(format = "Uchar4Norm", location = 5)]`.
+///
+/// Fields named `position`, `normal`, `uv` and `tangent` are also exposed
+/// through the accessors below, which the cpu side mesh utilities use. Every
+/// vertex type needs a `position`.
+///
+/// Like `StandardVertex`, the struct also needs the `bytemuck` impls that
+/// let it be copied into a vertex buffer.
+///
+/// ```
+/// #[repr(C)]
+/// #[derive(Copy, Clone, rise::graphics::Vertex)]
+/// struct ColoredVertex {
+///     position: cgmath::Vector3<f32>,
+///     #[vertex(format = "Uchar4Norm")]
+///     color: [u8; 4],
+/// }
+///
+/// unsafe impl bytemuck::Zeroable for ColoredVertex {}
+/// unsafe impl bytemuck::Pod for ColoredVertex {}
+/// ```
+pub trait Vertex: bytemuck::Pod {
+    /// The attributes of the vertex, in the order they're laid out in memory.
+    const ATTRIBUTES: &'static [VertexAttributeDescriptor];
+
+    fn desc<'a>() -> wgpu::VertexBufferDescriptor<'a> {
+        wgpu::VertexBufferDescriptor {
+            stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+
+    fn position(&self) -> Vector3<f32>;
+
+    fn set_position(&mut self, position: Vector3<f32>);
+
+    fn normal(&self) -> Option<Vector3<f32>> {
+        None
+    }
+
+    fn set_normal(&mut self, _normal: Vector3<f32>) {}
+
+    fn uv(&self) -> Option<Vector2<f32>> {
+        None
+    }
+
+    fn set_uv(&mut self, _uv: Vector2<f32>) {}
+
+    fn tangent(&self) -> Option<Vector4<f32>> {
+        None
+    }
+
+    fn set_tangent(&mut self, _tangent: Vector4<f32>) {}
+}
+
+/// Used by `#[derive(Vertex)]` to build attribute descriptors in a constant.
+pub const fn vertex_attribute(offset: u64, shader_location: u32, format: VertexFormat) -> VertexAttributeDescriptor {
+    VertexAttributeDescriptor {
+        offset,
+        shader_location,
+        format,
+    }
+}
+
+/// A type that can be used as a vertex attribute, along with the format it
+/// is read with by default.
+pub trait VertexAttributeType {
+    const FORMAT: VertexFormat;
+}
+
+macro_rules! attribute_type {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexAttributeType for $ty {
+                const FORMAT: VertexFormat = VertexFormat::$format;
+            }
+        )*
+    };
+}
+
+attribute_type! {
+    f32 => Float,
+    [f32; 2] => Float2,
+    [f32; 3] => Float3,
+    [f32; 4] => Float4,
+    Vector2<f32> => Float2,
+    Vector3<f32> => Float3,
+    Vector4<f32> => Float4,
+    cgmath::Point3<f32> => Float3,
+    u32 => Uint,
+    [u32; 2] => Uint2,
+    [u32; 3] => Uint3,
+    [u32; 4] => Uint4,
+    i32 => Int,
+    [i32; 2] => Int2,
+    [i32; 3] => Int3,
+    [i32; 4] => Int4,
+    [u16; 2] => Ushort2,
+    [u16; 4] => Ushort4,
+    [i16; 2] => Short2,
+    [i16; 4] => Short4,
+    [u8; 2] => Uchar2,
+    [u8; 4] => Uchar4,
+    [i8; 2] => Char2,
+    [i8; 4] => Char4,
+}
+
+/// The vertex layout used by loaded models and the built in shaders.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Vertex)]
+pub struct StandardVertex {
+    position: cgmath::Vector3<f32>,
+    normal: cgmath::Vector3<f32>,
+    uv: cgmath::Vector2<f32>,
+    /// The tangent direction, with the bitangent sign (1 or -1) stored in `w`
+    /// so that `bitangent = cross(normal, tangent.xyz) * tangent.w`.
+    tangent: cgmath::Vector4<f32>,
+}
+
+impl StandardVertex {
+    pub fn new(position: cgmath::Vector3<f32>, normal: cgmath::Vector3<f32>, uv: cgmath::Vector2<f32>) -> StandardVertex {
+        StandardVertex {
+            position,
+            normal,
+            uv,
+            tangent: cgmath::Vector4::new(0., 0., 0., 1.)
+        }
+    }
+
+    pub fn with_tangent(
+        position: cgmath::Vector3<f32>,
+        normal: cgmath::Vector3<f32>,
+        uv: cgmath::Vector2<f32>,
+        tangent: cgmath::Vector4<f32>
+    ) -> StandardVertex {
+        StandardVertex {
+            position,
+            normal,
+            uv,
+            tangent
+        }
+    }
+
+    pub fn get_position(&self) -> cgmath::Vector3<f32> {
+        self.position
+    }
+
+    pub fn get_normal(&self) -> cgmath::Vector3<f32> {
+        self.normal
+    }
+
+    pub fn get_uv(&self) -> cgmath::Vector2<f32> {
+        self.uv
+    }
+
+    pub fn get_tangent(&self) -> cgmath::Vector4<f32> {
+        self.tangent
+    }
+
+    pub fn get_bitangent(&self) -> cgmath::Vector3<f32> {
+        self.normal.cross(self.tangent.truncate()) * self.tangent.w
+    }
+}
+
+unsafe impl bytemuck::Zeroable for StandardVertex {}
+unsafe impl bytemuck::Pod for StandardVertex {}
+
+/// A `StandardVertex` that also follows up to four joints of a skeleton.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Vertex)]
+pub struct SkinnedVertex {
+    position: cgmath::Vector3<f32>,
+    normal: cgmath::Vector3<f32>,
+    uv: cgmath::Vector2<f32>,
+    tangent: cgmath::Vector4<f32>,
+    /// Indices into the skeleton's joints.
+    joints: [u16; 4],
+    /// How much each joint moves the vertex, summing to 1.
+    weights: cgmath::Vector4<f32>,
+}
+
+impl SkinnedVertex {
+    pub fn new(
+        position: cgmath::Vector3<f32>,
+        normal: cgmath::Vector3<f32>,
+        uv: cgmath::Vector2<f32>,
+        tangent: cgmath::Vector4<f32>,
+        joints: [u16; 4],
+        weights: cgmath::Vector4<f32>
+    ) -> SkinnedVertex {
+        SkinnedVertex {
+            position,
+            normal,
+            uv,
+            tangent,
+            joints,
+            weights
+        }
+    }
+
+    pub fn get_position(&self) -> cgmath::Vector3<f32> {
+        self.position
+    }
+
+    pub fn get_normal(&self) -> cgmath::Vector3<f32> {
+        self.normal
+    }
+
+    pub fn get_uv(&self) -> cgmath::Vector2<f32> {
+        self.uv
+    }
+
+    pub fn get_tangent(&self) -> cgmath::Vector4<f32> {
+        self.tangent
+    }
+
+    pub fn get_joints(&self) -> [u16; 4] {
+        self.joints
+    }
+
+    pub fn get_weights(&self) -> cgmath::Vector4<f32> {
+        self.weights
+    }
+}
+
+unsafe impl bytemuck::Zeroable for SkinnedVertex {}
+unsafe impl bytemuck::Pod for SkinnedVertex {}
+
+#[macro_export]
+macro_rules! point {
+    ($x:expr, $y:expr, $z:expr) => {
+        $crate::graphics::StandardVertex::new(
+            cgmath::Vector3::new($x, $y, $z),
+            cgmath::Vector3::new(0., 0., 0.),
+            cgmath::Vector2::new(0., 0.)
+        );
+    }
+}
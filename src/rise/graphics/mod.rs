@@ -12,4 +12,28 @@ pub use mesh::{*};
 
 mod texture;
 
-pub use texture::{*};
\ No newline at end of file
+pub use texture::{*};
+
+mod skinning;
+
+pub use skinning::{*};
+
+mod morph_buffer;
+
+pub use morph_buffer::{*};
+
+mod debug_draw;
+
+pub use debug_draw::{*};
+
+mod skybox;
+
+pub use skybox::{*};
+
+mod capture;
+
+pub use capture::{*};
+
+mod render_target;
+
+pub use render_target::{*};
@@ -0,0 +1,112 @@
+use super::{Mesh, MeshError, RenderContext, Vertex};
+
+/// A mesh's morph targets uploaded to the gpu, so they can be blended in the
+/// vertex shader rather than on the cpu. Bound to a material's
+/// `morph_targets` uniform with `MaterialInstance::use_morph_targets`.
+///
+/// The shader sees two storage buffers: the deltas, as a position and a
+/// normal `vec4` for every vertex of the first target, then the second, and
+/// so on, followed by the target and vertex counts and the weights.
+pub struct MorphTargetBuffer {
+    deltas: wgpu::Buffer,
+    weights: wgpu::Buffer,
+    target_count: usize,
+}
+
+impl MorphTargetBuffer {
+    /// Uploads the mesh's morph targets and weights. Fails if a target
+    /// doesn't have one delta for each of the mesh's vertices.
+    pub fn new<V: Vertex>(render_context: &RenderContext, mesh: &Mesh<V>) -> Result<MorphTargetBuffer, MeshError> {
+        use wgpu::util::DeviceExt;
+
+        let targets = mesh.get_morph_targets();
+        let vertex_count = mesh.get_vertices().len();
+
+        for target in targets {
+            let normals = target.normal_deltas.as_ref().map_or(vertex_count, |deltas| deltas.len());
+
+            if target.position_deltas.len() != vertex_count || normals != vertex_count {
+                return Err(MeshError::new(&format!(
+                    "Morph target {} doesn't have one delta for each of the {} vertices", target.name, vertex_count
+                )));
+            }
+        }
+
+        let mut deltas: Vec<[f32; 4]> = Vec::with_capacity((targets.len() * vertex_count * 2).max(1));
+
+        for target in targets {
+            for (index, position) in target.position_deltas.iter().enumerate() {
+                let normal = target
+                    .normal_deltas
+                    .as_ref()
+                    .map_or(cgmath::Vector3::new(0., 0., 0.), |normals| normals[index]);
+
+                deltas.push([position.x, position.y, position.z, 0.]);
+                deltas.push([normal.x, normal.y, normal.z, 0.]);
+            }
+        }
+
+        // Storage buffers can't be empty.
+        if deltas.is_empty() {
+            deltas.push([0.; 4]);
+        }
+
+        let deltas = render_context.device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Morph Target Deltas"),
+                contents: bytemuck::cast_slice(&deltas[..]),
+                usage: wgpu::BufferUsage::STORAGE,
+            }
+        );
+
+        let weights = render_context.device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Morph Target Weights"),
+                contents: &weight_data(targets.len(), vertex_count, mesh.get_morph_weights()),
+                usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            }
+        );
+
+        Ok(MorphTargetBuffer {
+            deltas,
+            weights,
+            target_count: targets.len(),
+        })
+    }
+
+    /// Uploads new weights, one per target.
+    pub fn set_weights(&self, render_context: &RenderContext, weights: &[f32]) {
+        let mut padded = vec![0.; self.target_count.max(1)];
+
+        for (weight, value) in padded.iter_mut().zip(weights) {
+            *weight = *value;
+        }
+
+        // Skip the counts at the start of the buffer.
+        render_context.queue.write_buffer(&self.weights, 8, bytemuck::cast_slice(&padded[..]));
+    }
+
+    pub fn get_deltas_buffer(&self) -> &wgpu::Buffer {
+        &self.deltas
+    }
+
+    pub fn get_weights_buffer(&self) -> &wgpu::Buffer {
+        &self.weights
+    }
+
+    pub fn get_target_count(&self) -> usize {
+        self.target_count
+    }
+}
+
+fn weight_data(target_count: usize, vertex_count: usize, weights: &[f32]) -> Vec<u8> {
+    let mut data: Vec<u32> = vec![target_count as u32, vertex_count as u32];
+
+    data.extend(weights.iter().map(|weight| weight.to_bits()));
+
+    if weights.is_empty() {
+        data.push(0);
+    }
+
+    bytemuck::cast_slice(&data[..]).to_vec()
+}
@@ -15,6 +15,8 @@ pub struct RenderContext {
     pub sc_desc: SwapChainDescriptor,
     swap_chain: SwapChain,
     size: PhysicalSize<u32>,
+    fixed_timestep: Option<f32>,
+    frame_count: u64,
 }
 
 impl RenderContext {
@@ -41,10 +43,14 @@ impl RenderContext {
         .await
         .unwrap();*/
 
+        // Block compressed textures are used when the adapter has them, and
+        // decompressed on the cpu when it doesn't.
+        let features = adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC;
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
+                    features,
                     limits: wgpu::Limits::default(),
                     shader_validation: true,
                 },
@@ -71,6 +77,8 @@ impl RenderContext {
             sc_desc,
             swap_chain,
             size,
+            fixed_timestep: None,
+            frame_count: 0,
         })
     }
 
@@ -81,6 +89,26 @@ impl RenderContext {
         self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
     }
 
+    /// The optional features the device was created with.
+    pub fn get_features(&self) -> wgpu::Features {
+        self.device.features()
+    }
+
+    /// Makes the application's update get `timestep` seconds every frame
+    /// instead of the measured frame time, or goes back to measuring it.
+    pub fn set_fixed_timestep(&mut self, timestep: Option<f32>) {
+        self.fixed_timestep = timestep;
+    }
+
+    pub fn get_fixed_timestep(&self) -> Option<f32> {
+        self.fixed_timestep
+    }
+
+    /// How many frames have been begun.
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
     pub fn create_drawable(&self) -> DrawableBuilder {
         DrawableBuilder::new(&self)
     }
@@ -92,11 +120,19 @@ impl RenderContext {
     pub fn create_texture(&self) -> crate::graphics::TextureBuilder {
         crate::graphics::TextureBuilder::new(&self)
     }
+
+    pub fn create_sampler(&self) -> crate::graphics::SamplerBuilder {
+        crate::graphics::SamplerBuilder::new(&self)
+    }
+
+    pub fn create_render_target(&self) -> crate::graphics::RenderTargetBuilder {
+        crate::graphics::RenderTargetBuilder::new(&self)
+    }
 }
 
-pub struct DrawableBuilder<'a> {
+pub struct DrawableBuilder<'a, V = crate::graphics::StandardVertex> {
     render_context: &'a RenderContext,
-    mesh: Option<crate::graphics::Mesh>,
+    mesh: Option<crate::graphics::Mesh<V>>,
     material: Option<crate::graphics::MaterialInstance>,
 }
 
@@ -108,10 +144,15 @@ impl<'a> DrawableBuilder<'a> {
             material: Option::<crate::graphics::MaterialInstance>::None,
         }
     }
+}
 
-    pub fn with_mesh(mut self, mesh: crate::graphics::Mesh) -> Self {
-        self.mesh = Some(mesh);
-        self
+impl<'a, V: crate::graphics::Vertex + 'static> DrawableBuilder<'a, V> {
+    pub fn with_mesh<W: crate::graphics::Vertex>(self, mesh: crate::graphics::Mesh<W>) -> DrawableBuilder<'a, W> {
+        DrawableBuilder {
+            render_context: self.render_context,
+            mesh: Some(mesh),
+            material: self.material,
+        }
     }
 
     pub fn with_material(mut self, material: crate::graphics::MaterialInstance) -> Self {
@@ -119,23 +160,76 @@ impl<'a> DrawableBuilder<'a> {
         self
     }
 
-    pub fn build(self) -> Drawable {
+    pub fn build(self) -> Result<Drawable<V>, Box<dyn std::error::Error>> {
         let mut mesh = self.mesh.unwrap();
-        mesh.create(&self.render_context);
+        let material = self.material.unwrap();
 
-        Drawable {
-            material: self.material.unwrap(),
-            mesh,
+        if material.get_base_material().get_vertex_type() != std::any::TypeId::of::<V>() {
+            return Err(Box::new(crate::graphics::MaterialError::new(
+                "Material was built for a different vertex layout than the mesh uses"
+            )));
         }
+
+        mesh.create(&self.render_context)?;
+
+        Ok(Drawable {
+            material,
+            mesh,
+        })
     }
 }
 
-pub struct Drawable {
+/// Anything `Frame::render` knows how to draw.
+pub trait Renderable {
+    fn get_material(&self) -> &crate::graphics::MaterialInstance;
+    fn get_vertex_buffer(&self) -> &wgpu::Buffer;
+    fn get_index_buffer(&self) -> &wgpu::Buffer;
+    fn num_indices(&self) -> u32;
+    fn get_index_format(&self) -> wgpu::IndexFormat;
+    fn get_bounds(&self) -> Option<crate::graphics::Aabb>;
+    fn get_bounding_sphere(&self) -> Option<crate::graphics::BoundingSphere>;
+}
+
+pub struct Drawable<V = crate::graphics::StandardVertex> {
     material: crate::graphics::MaterialInstance,
-    mesh: crate::graphics::Mesh,
+    mesh: crate::graphics::Mesh<V>,
 }
 
-impl Drawable {
+impl<V: crate::graphics::Vertex> Drawable<V> {
+    pub fn get_mesh(&self) -> &crate::graphics::Mesh<V> {
+        &self.mesh
+    }
+
+    /// The drawable's mesh, for changing its geometry. Changes are only seen
+    /// once the mesh is updated.
+    pub fn get_mesh_mut(&mut self) -> &mut crate::graphics::Mesh<V> {
+        &mut self.mesh
+    }
+
+    /// Sets the weights of the mesh's morph targets and blends them on the
+    /// cpu. When the material blends them on the gpu, update its
+    /// `MorphTargetBuffer` instead.
+    pub fn set_morph_weights(
+        &mut self,
+        render_context: &RenderContext,
+        weights: &[f32]
+    ) -> Result<(), crate::graphics::MeshError> {
+        self.mesh.set_morph_weights(weights);
+        self.mesh.apply_morph_targets(render_context)
+    }
+
+    /// The drawable's bounding box once placed with `transform`.
+    pub fn get_world_bounds(&self, transform: &crate::core::Transform) -> Option<crate::graphics::Aabb> {
+        self.mesh.get_bounds().map(|bounds| bounds.transformed(transform))
+    }
+
+    /// The drawable's bounding sphere once placed with `transform`.
+    pub fn get_world_bounding_sphere(&self, transform: &crate::core::Transform) -> Option<crate::graphics::BoundingSphere> {
+        self.mesh.get_bounding_sphere().map(|sphere| sphere.transformed(transform))
+    }
+}
+
+impl<V: crate::graphics::Vertex> Renderable for Drawable<V> {
     fn get_material(&self) -> &crate::graphics::MaterialInstance {
         &self.material
     }
@@ -146,7 +240,16 @@ impl Drawable {
         self.mesh.get_index_buffer().unwrap()
     }
     fn num_indices(&self) -> u32 {
-        self.mesh.get_indices().len() as u32
+        self.mesh.get_buffer_index_count()
+    }
+    fn get_index_format(&self) -> wgpu::IndexFormat {
+        self.mesh.get_buffer_index_format()
+    }
+    fn get_bounds(&self) -> Option<crate::graphics::Aabb> {
+        self.mesh.get_bounds()
+    }
+    fn get_bounding_sphere(&self) -> Option<crate::graphics::BoundingSphere> {
+        self.mesh.get_bounding_sphere()
     }
 }
 
@@ -154,12 +257,25 @@ pub struct Frame<'r> {
     render_context: &'r RenderContext,
     frame: SwapChainTexture,
     encoder: CommandEncoder,
+    capture: Option<&'r crate::graphics::FrameCapture>,
+}
+
+/// The view a frame renders into, which is the capture's texture while
+/// capturing.
+fn target_view<'a>(
+    frame: &'a SwapChainTexture,
+    capture: Option<&'a crate::graphics::FrameCapture>,
+) -> &'a wgpu::TextureView {
+    match capture {
+        Some(capture) => capture.get_view(),
+        None => &frame.view,
+    }
 }
 
 impl<'r> Frame<'r> {
     pub fn render<C: crate::core::Camera>(
         &mut self,
-        objects: &[&Drawable],
+        objects: &[&dyn Renderable],
         camera: &'r C,
     ) {
         //let base_material = material.get_base_material();
@@ -167,7 +283,7 @@ impl<'r> Frame<'r> {
 
         let mut render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &self.frame.view,
+                attachment: target_view(&self.frame, self.capture),
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -209,16 +325,173 @@ impl<'r> Frame<'r> {
                     render_pass.set_bind_group(i as u32, &bind_group, &[]);
                 }
             }
-            render_pass.set_pipeline(base_material.get_render_pipeline());
+            render_pass.set_pipeline(base_material.get_render_pipeline_for(obj.get_index_format()));
             render_pass.set_vertex_buffer(0, obj.get_vertex_buffer().slice(..));
             render_pass.set_index_buffer(obj.get_index_buffer().slice(..));
 
             render_pass.draw_indexed(0..obj.num_indices(), 0, 0..1);
         }
     }
+
+    /// Renders objects from a camera into a render target instead of the
+    /// window. Render targets before anything that samples them, and give
+    /// each target its own camera, since a camera holds one set of matrices
+    /// per frame.
+    pub fn render_to_target<C: crate::core::Camera>(
+        &mut self,
+        target: &crate::graphics::RenderTarget,
+        objects: &[&dyn Renderable],
+        camera: &C,
+    ) {
+        camera.update(self.render_context);
+
+        let camera_binding = camera.get_binding().unwrap();
+
+        // Pipelines for the target's formats are made before the pass, which
+        // needs them to outlive it.
+        let pipelines: Vec<_> = objects
+            .iter()
+            .map(|obj| {
+                obj.get_material().get_base_material().get_target_pipeline(
+                    self.render_context,
+                    target.get_format(),
+                    target.get_depth_texture().is_some(),
+                    obj.get_index_format(),
+                )
+            })
+            .collect();
+
+        let mut render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: target.get_color_texture().get_view(),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(target.get_clear_color()),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: target.get_depth_texture().map(|depth_texture| {
+                wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: depth_texture.get_view(),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+        });
+
+        for (obj, pipeline) in objects.iter().zip(&pipelines) {
+            let material = obj.get_material();
+            let base_material = material.get_base_material();
+
+            let camera_bind_index = *base_material.get_binding_by_name("camera").expect("Shader must have a camera uniform.");
+
+            render_pass.set_bind_group(camera_bind_index as u32, camera_binding.get_bind_group(), &[]);
+
+            for i in 0..base_material.get_uniforms().len() {
+                if let Some(binding) = material.get_uniforms().get(&i) {
+                    render_pass.set_bind_group(i as u32, binding.get_bind_group(), &[]);
+                }
+            }
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_vertex_buffer(0, obj.get_vertex_buffer().slice(..));
+            render_pass.set_index_buffer(obj.get_index_buffer().slice(..));
+
+            render_pass.draw_indexed(0..obj.num_indices(), 0, 0..1);
+        }
+    }
+
+    /// Draws the skybox behind what's been rendered so far. Call it after
+    /// `render` with the same camera, so the sky only fills the background.
+    /// It can be called more than once a frame, e.g. once per viewport.
+    pub fn render_skybox<C: crate::core::Camera>(
+        &mut self,
+        skybox: &crate::graphics::Skybox,
+        camera: &C,
+    ) {
+        let camera_index = skybox.prepare(self.render_context, camera);
+        let cameras = skybox.get_cameras();
+
+        let mut render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: target_view(&self.frame, self.capture),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: camera.get_depth_texture().get_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(skybox.get_pipeline());
+        render_pass.set_bind_group(0, &cameras[camera_index].1, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Draws every shape added to `debug_draw` on top of what's been rendered
+    /// so far, in a single line list. Call it after `render` with the same
+    /// camera, so depth tested lines are hidden by the scene. It can be
+    /// called more than once a frame, e.g. once per viewport, and each call
+    /// draws the same shapes.
+    pub fn render_debug<C: crate::core::Camera>(
+        &mut self,
+        debug_draw: &crate::graphics::DebugDraw,
+        camera: &C,
+    ) {
+        let (vertex_count, camera_index) = debug_draw.prepare(self.render_context, camera);
+
+        if vertex_count == 0 {
+            return;
+        }
+
+        let cameras = debug_draw.get_cameras();
+        let vertex_buffer = debug_draw.get_vertex_buffer();
+
+        let vertex_buffer = match vertex_buffer.as_ref() {
+            Some(vertex_buffer) => vertex_buffer,
+            None => return,
+        };
+
+        let mut render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: target_view(&self.frame, self.capture),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: camera.get_depth_texture().get_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(debug_draw.get_pipeline());
+        render_pass.set_bind_group(0, &cameras[camera_index].1, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..vertex_count, 0..1);
+    }
 }
 
 pub fn begin_frame<'frame>(render_context: &'frame mut RenderContext) -> Frame {
+    render_context.frame_count += 1;
+
     let encoder = render_context
         .device
         .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -235,10 +508,29 @@ pub fn begin_frame<'frame>(render_context: &'frame mut RenderContext) -> Frame {
         render_context,
         frame,
         encoder,
+        capture: None,
     }
 }
 
-pub fn end_frame(frame: Frame) {
+/// Begins a frame that's also rendered into `capture`, so it can be read
+/// back or saved after `end_frame`. The capture is resized first if the
+/// window has changed size since it was made.
+pub fn begin_captured_frame<'frame>(
+    render_context: &'frame mut RenderContext,
+    capture: &'frame mut crate::graphics::FrameCapture,
+) -> Frame<'frame> {
+    capture.resize(render_context);
+
+    let mut frame = begin_frame(render_context);
+    frame.capture = Some(capture);
+    frame
+}
+
+pub fn end_frame(mut frame: Frame) {
+    if let Some(capture) = frame.capture {
+        capture.finish(&mut frame.encoder, &frame.frame.view);
+    }
+
     let command_buffer = frame.encoder.finish();
 
     frame
@@ -0,0 +1,137 @@
+use super::{RenderContext, Texture, TextureError};
+
+/// A texture that cameras can render into with `Frame::render_to_target`
+/// and materials can then sample like any other texture, for things like
+/// mirrors, in-game screens, minimaps and thumbnails.
+pub struct RenderTarget {
+    color_texture: Texture,
+    depth_texture: Option<Texture>,
+    clear_color: wgpu::Color,
+}
+
+impl RenderTarget {
+    /// The texture to bind with `MaterialInstance::use_texture`.
+    pub fn get_color_texture(&self) -> &Texture {
+        &self.color_texture
+    }
+
+    pub fn get_depth_texture(&self) -> Option<&Texture> {
+        self.depth_texture.as_ref()
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        let size = self.color_texture.get_size();
+
+        (size.width, size.height)
+    }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        self.color_texture.get_format()
+    }
+
+    /// The aspect ratio cameras rendering into the target should use.
+    pub fn get_aspect_ratio(&self) -> f32 {
+        let (width, height) = self.get_size();
+
+        width as f32 / height as f32
+    }
+
+    pub fn get_clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: wgpu::Color) {
+        self.clear_color = clear_color;
+    }
+}
+
+pub struct RenderTargetBuilder<'a> {
+    render_context: &'a RenderContext,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    depth: bool,
+    clear_color: wgpu::Color,
+}
+
+impl<'a> RenderTargetBuilder<'a> {
+    /// Starts a target the size and format of the window, with a depth
+    /// buffer.
+    pub fn new(r: &'a RenderContext) -> Self {
+        RenderTargetBuilder {
+            render_context: r,
+            width: r.sc_desc.width,
+            height: r.sc_desc.height,
+            format: r.sc_desc.format,
+            depth: true,
+            clear_color: wgpu::Color::BLACK,
+        }
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the color format, which has to be one the gpu can render to.
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets whether the target has its own depth buffer. Without one,
+    /// everything drawn into it is drawn in order with no depth test.
+    pub fn with_depth(mut self, depth: bool) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn with_clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    pub fn build(self) -> Result<RenderTarget, TextureError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(TextureError::new("Render targets must be at least 1x1"));
+        }
+
+        if super::bytes_per_block(self.format).is_some() || self.format == wgpu::TextureFormat::Depth32Float {
+            return Err(TextureError::new(&format!("{:?} can't be used as a render target's color", self.format)));
+        }
+
+        let color_texture = self
+            .render_context
+            .create_texture()
+            .with_size(self.width, self.height)
+            .with_format(self.format)
+            .with_usage(
+                wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                    | wgpu::TextureUsage::SAMPLED
+                    | wgpu::TextureUsage::COPY_SRC,
+            )
+            .with_address_mode(wgpu::AddressMode::ClampToEdge)
+            .with_label("Render Target")
+            .build();
+
+        let depth_texture = if self.depth {
+            Some(
+                self.render_context
+                    .create_texture()
+                    .make_depth_texture()
+                    .with_size(self.width, self.height)
+                    .with_label("Render Target Depth")
+                    .build(),
+            )
+        } else {
+            None
+        };
+
+        Ok(RenderTarget {
+            color_texture,
+            depth_texture,
+            clear_color: self.clear_color,
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use super::RenderContext;
+use crate::animation::AnimationError;
+
+/// A gpu buffer of joint matrices for skinning, bound to a material's
+/// `joints` uniform with `MaterialInstance::use_joints`.
+pub struct JointBuffer {
+    buffer: wgpu::Buffer,
+    joint_count: usize,
+}
+
+impl JointBuffer {
+    /// Makes a buffer with room for `joint_count` matrices, all identity.
+    pub fn new(render_context: &RenderContext, joint_count: usize) -> JointBuffer {
+        use cgmath::SquareMatrix;
+        use wgpu::util::DeviceExt;
+
+        let identity: [[f32; 4]; 4] = cgmath::Matrix4::<f32>::identity().into();
+
+        // Storage buffers can't be empty.
+        let matrices = vec![identity; joint_count.max(1)];
+
+        let buffer = render_context.device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Joint Buffer"),
+                contents: bytemuck::cast_slice(&matrices[..]),
+                usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            }
+        );
+
+        JointBuffer {
+            buffer,
+            joint_count,
+        }
+    }
+
+    /// Uploads new joint matrices, usually from `Skeleton::joint_matrices`.
+    /// Fewer matrices than the buffer holds update the first joints only.
+    pub fn update(&self, render_context: &RenderContext, matrices: &[cgmath::Matrix4<f32>]) -> Result<(), AnimationError> {
+        if matrices.len() > self.joint_count {
+            return Err(AnimationError::new(&format!(
+                "Can't upload {} joint matrices to a buffer made for {}",
+                matrices.len(),
+                self.joint_count
+            )));
+        }
+
+        let matrices: Vec<[[f32; 4]; 4]> = matrices.iter().map(|matrix| (*matrix).into()).collect();
+
+        render_context.queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&matrices[..]));
+
+        Ok(())
+    }
+
+    pub fn get_buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn get_joint_count(&self) -> usize {
+        self.joint_count
+    }
+}
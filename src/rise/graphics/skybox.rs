@@ -0,0 +1,199 @@
+use cgmath::{Matrix4, SquareMatrix, Vector4};
+
+use std::cell::{Cell, RefCell};
+
+use super::{RenderContext, Texture, TextureError};
+
+/// A cubemap drawn behind everything else, seen from the camera's rotation
+/// so it never gets closer. `Frame::render_skybox` draws it after the scene
+/// and only fills pixels nothing else was drawn to.
+pub struct Skybox {
+    texture: Texture,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// A camera buffer and bind group for each time it's drawn in a frame,
+    /// since each draw may use a different camera.
+    cameras: RefCell<Vec<(wgpu::Buffer, wgpu::BindGroup)>>,
+    cameras_used: Cell<usize>,
+    prepared_frame: Cell<Option<u64>>,
+}
+
+impl Skybox {
+    /// Creates a skybox from a texture with a `Cube` view dimension, like
+    /// those made by `TextureBuilder::load_cube_files` and
+    /// `TextureBuilder::load_equirectangular`.
+    pub fn new(render_context: &RenderContext, texture: Texture) -> Result<Skybox, Box<dyn std::error::Error>> {
+        if texture.get_view_dimension() != wgpu::TextureViewDimension::Cube {
+            return Err(Box::new(TextureError::new("A skybox needs a cube texture")));
+        }
+
+        let vs_spirv = std::fs::read("res/shader/skybox.vert.spv")?;
+        let fs_spirv = std::fs::read("res/shader/skybox.frag.spv")?;
+
+        let vs_module = render_context.device.create_shader_module(wgpu::util::make_spirv(&vs_spirv));
+        let fs_module = render_context.device.create_shader_module(wgpu::util::make_spirv(&fs_spirv));
+
+        let bind_group_layout = render_context.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::VERTEX,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        dimension: wgpu::TextureViewDimension::Cube,
+                        component_type: wgpu::TextureComponentType::Float,
+                    },
+                    count: None
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        comparison: false
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Skybox Layout"),
+        });
+
+        let pipeline_layout = render_context.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            push_constant_ranges: &[],
+            bind_group_layouts: &[&bind_group_layout],
+        });
+
+        let pipeline = render_context.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: &vs_module,
+                entry_point: "main",
+            },
+            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                module: &fs_module,
+                entry_point: "main",
+            }),
+            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            }),
+            color_states: &[wgpu::ColorStateDescriptor {
+                format: render_context.sc_desc.format,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+            // The sky sits on the far plane, so it passes only where the
+            // depth buffer still holds its cleared value.
+            depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilStateDescriptor::default(),
+            }),
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: wgpu::IndexFormat::Uint16,
+                vertex_buffers: &[],
+            },
+            sample_count: 1,
+            sample_mask: !0,
+            alpha_to_coverage_enabled: false,
+        });
+
+        Ok(Skybox {
+            texture,
+            pipeline,
+            bind_group_layout,
+            cameras: RefCell::new(Vec::new()),
+            cameras_used: Cell::new(0),
+            prepared_frame: Cell::new(None),
+        })
+    }
+
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Uploads the camera's rotation and projection, leaving out where the
+    /// camera is, into the next of this frame's camera slots. Returns the
+    /// slot to draw with.
+    pub(crate) fn prepare<C: crate::core::Camera>(&self, render_context: &RenderContext, camera: &C) -> usize {
+        let frame = render_context.get_frame_count();
+
+        if self.prepared_frame.get() != Some(frame) {
+            self.prepared_frame.set(Some(frame));
+            self.cameras_used.set(0);
+        }
+
+        let index = self.cameras_used.get();
+        self.cameras_used.set(index + 1);
+
+        let mut view = camera.view_matrix();
+        view.w = Vector4::new(0., 0., 0., 1.);
+
+        let inverse_view_proj = (camera.proj_matrix() * view)
+            .invert()
+            .unwrap_or_else(Matrix4::identity);
+        let inverse_view_proj: [[f32; 4]; 4] = inverse_view_proj.into();
+
+        let mut cameras = self.cameras.borrow_mut();
+
+        match cameras.get(index) {
+            Some((buffer, _)) => render_context.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&inverse_view_proj)),
+            None => {
+                use wgpu::util::DeviceExt;
+
+                let buffer = render_context.device.create_buffer_init(
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Skybox Buffer"),
+                        contents: bytemuck::cast_slice(&inverse_view_proj),
+                        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                    }
+                );
+
+                let bind_group = render_context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(self.texture.get_view()),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Sampler(self.texture.get_sampler()),
+                        }
+                    ],
+                    label: Some("Skybox Bind Group"),
+                });
+
+                cameras.push((buffer, bind_group));
+            }
+        }
+
+        index
+    }
+
+    pub(crate) fn get_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub(crate) fn get_cameras(&self) -> std::cell::Ref<Vec<(wgpu::Buffer, wgpu::BindGroup)>> {
+        self.cameras.borrow()
+    }
+}
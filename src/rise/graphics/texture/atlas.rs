@@ -0,0 +1,534 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use cgmath::Vector2;
+
+use super::{MipmapGeneration, Texture, TextureBuilder, TextureError};
+
+/// A named rectangle of an atlas or sprite sheet, in pixels and in texture
+/// coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub uv_min: Vector2<f32>,
+    pub uv_max: Vector2<f32>,
+}
+
+impl AtlasRegion {
+    pub fn new(name: &str, x: u32, y: u32, width: u32, height: u32, (atlas_width, atlas_height): (u32, u32)) -> AtlasRegion {
+        AtlasRegion {
+            name: String::from(name),
+            x,
+            y,
+            width,
+            height,
+            uv_min: Vector2::new(x as f32 / atlas_width as f32, y as f32 / atlas_height as f32),
+            uv_max: Vector2::new(
+                (x + width) as f32 / atlas_width as f32,
+                (y + height) as f32 / atlas_height as f32,
+            ),
+        }
+    }
+
+    /// Maps texture coordinates of the original image into the atlas.
+    pub fn map_uv(&self, uv: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            self.uv_min.x + (self.uv_max.x - self.uv_min.x) * uv.x,
+            self.uv_min.y + (self.uv_max.y - self.uv_min.y) * uv.y,
+        )
+    }
+}
+
+/// A texture holding many images, each found by name or by the order they
+/// were added in.
+pub struct TextureAtlas {
+    texture: Texture,
+    regions: Vec<AtlasRegion>,
+    names: HashMap<String, usize>,
+}
+
+impl TextureAtlas {
+    pub fn new(texture: Texture, regions: Vec<AtlasRegion>) -> TextureAtlas {
+        let names = regions
+            .iter()
+            .enumerate()
+            .map(|(index, region)| (region.name.clone(), index))
+            .collect();
+
+        TextureAtlas {
+            texture,
+            regions,
+            names,
+        }
+    }
+
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn get_region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.names.get(name).map(|index| &self.regions[*index])
+    }
+
+    /// A region by its position, which for sprite sheets is its frame number.
+    pub fn get_frame(&self, index: usize) -> Option<&AtlasRegion> {
+        self.regions.get(index)
+    }
+
+    pub fn get_regions(&self) -> &[AtlasRegion] {
+        &self.regions
+    }
+}
+
+/// Packs images into one larger image, leaving space between them so
+/// filtering doesn't blend neighbours together.
+pub struct AtlasPacker {
+    images: Vec<(String, image::RgbaImage)>,
+    padding: u32,
+    gutters: bool,
+    mip_safe_levels: u32,
+    max_size: u32,
+}
+
+impl AtlasPacker {
+    pub fn new() -> Self {
+        AtlasPacker {
+            images: Vec::new(),
+            padding: 1,
+            gutters: false,
+            mip_safe_levels: 0,
+            max_size: 4096,
+        }
+    }
+
+    /// Sets the space left on every side of each image. Defaults to 1.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Fills the padding around each image with copies of its edge pixels
+    /// instead of leaving it transparent, so sampling at the edges of a
+    /// region doesn't fade out.
+    pub fn with_gutters(mut self, gutters: bool) -> Self {
+        self.gutters = gutters;
+        self
+    }
+
+    /// Keeps regions apart in the first `levels` mip levels by placing
+    /// them on multiples of `2^levels` pixels, with padding to match.
+    /// Textures made from the atlas clamp sampling to those levels.
+    pub fn with_mip_safe_levels(mut self, levels: u32) -> Self {
+        self.mip_safe_levels = levels;
+        self
+    }
+
+    /// Sets the largest width or height the atlas can grow to. Defaults
+    /// to 4096.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn add_image(mut self, name: &str, image: image::RgbaImage) -> Self {
+        self.images.push((String::from(name), image));
+        self
+    }
+
+    /// Adds an image file, named after the file without its extension.
+    pub fn add_file<P: AsRef<Path>>(self, path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| TextureError::new(&format!("{:?} has no file name", path)))?;
+
+        let image = image::open(path)?.to_rgba();
+
+        Ok(self.add_image(&name, image))
+    }
+
+    /// Packs the images into an atlas whose sides are powers of two, as
+    /// small as they'll fit in.
+    pub fn pack(self) -> Result<PackedAtlas, TextureError> {
+        if self.images.is_empty() {
+            return Err(TextureError::new("An atlas needs at least one image"));
+        }
+
+        let mut names = std::collections::HashSet::new();
+
+        for (name, _) in &self.images {
+            if !names.insert(name) {
+                return Err(TextureError::new(&format!("The atlas already has an image named {}", name)));
+            }
+        }
+
+        if let Some((name, _)) = self.images.iter().find(|(_, image)| image.width() == 0 || image.height() == 0) {
+            return Err(TextureError::new(&format!("Image {} is empty", name)));
+        }
+
+        // Regions sit on multiples of the alignment, so it has to fit in the
+        // atlas.
+        let alignment = match 1u32.checked_shl(self.mip_safe_levels) {
+            Some(alignment) if alignment <= self.max_size => alignment,
+            _ => {
+                return Err(TextureError::new(&format!(
+                    "Keeping {} mip levels apart needs more room than the {}x{} maximum size",
+                    self.mip_safe_levels, self.max_size, self.max_size
+                )))
+            }
+        };
+
+        let padding = self.padding.max(alignment / 2);
+        let align = |size: u32| (size + alignment - 1) / alignment * alignment;
+
+        let cells: Vec<(u32, u32)> = self
+            .images
+            .iter()
+            .map(|(_, image)| (align(image.width() + 2 * padding), align(image.height() + 2 * padding)))
+            .collect();
+
+        // Taller images first leaves a flatter skyline for the rest.
+        let mut order: Vec<usize> = (0..cells.len()).collect();
+        order.sort_by(|a, b| (cells[*b].1, cells[*b].0).cmp(&(cells[*a].1, cells[*a].0)));
+
+        let area: u64 = cells.iter().map(|(width, height)| *width as u64 * *height as u64).sum();
+        let widest = cells.iter().map(|cell| cell.0).max().unwrap_or(1);
+        let tallest = cells.iter().map(|cell| cell.1).max().unwrap_or(1);
+
+        let side = ((area as f64).sqrt() as u32).next_power_of_two();
+        let mut width = side.max(widest.next_power_of_two());
+        let mut height = (side / 2).max(tallest.next_power_of_two());
+
+        let positions = loop {
+            if width > self.max_size || height > self.max_size {
+                return Err(TextureError::new(&format!(
+                    "The images don't fit in a {}x{} atlas",
+                    self.max_size, self.max_size
+                )));
+            }
+
+            if let Some(positions) = pack_skyline(&cells, &order, width, height) {
+                break positions;
+            }
+
+            if width <= height {
+                width *= 2;
+            } else {
+                height *= 2;
+            }
+        };
+
+        let mut atlas = image::RgbaImage::new(width, height);
+        let mut regions = Vec::with_capacity(self.images.len());
+
+        for ((name, image), (x, y)) in self.images.iter().zip(positions) {
+            let (cell_width, cell_height) = (image.width() + 2 * padding, image.height() + 2 * padding);
+
+            for cell_y in 0..cell_height {
+                for cell_x in 0..cell_width {
+                    let inside = cell_x >= padding
+                        && cell_y >= padding
+                        && cell_x < padding + image.width()
+                        && cell_y < padding + image.height();
+
+                    if !inside && !self.gutters {
+                        continue;
+                    }
+
+                    let source_x = (cell_x.max(padding) - padding).min(image.width() - 1);
+                    let source_y = (cell_y.max(padding) - padding).min(image.height() - 1);
+
+                    atlas.put_pixel(x + cell_x, y + cell_y, *image.get_pixel(source_x, source_y));
+                }
+            }
+
+            regions.push(AtlasRegion::new(
+                name,
+                x + padding,
+                y + padding,
+                image.width(),
+                image.height(),
+                (width, height),
+            ));
+        }
+
+        Ok(PackedAtlas {
+            image: atlas,
+            regions,
+            mip_safe_levels: self.mip_safe_levels,
+        })
+    }
+}
+
+impl Default for AtlasPacker {
+    fn default() -> AtlasPacker {
+        AtlasPacker::new()
+    }
+}
+
+/// Places each cell, in the given order, at the lowest spot along the top
+/// edge of the cells placed so far. Returns the top left corner of each
+/// cell, or `None` if they don't all fit.
+fn pack_skyline(cells: &[(u32, u32)], order: &[usize], width: u32, height: u32) -> Option<Vec<(u32, u32)>> {
+    // Segments of the skyline as (x, y, width), left to right.
+    let mut skyline = vec![(0, 0, width)];
+    let mut positions = vec![(0, 0); cells.len()];
+
+    for index in order {
+        let (cell_width, cell_height) = cells[*index];
+
+        let mut best: Option<(usize, u32, u32)> = None;
+
+        for start in 0..skyline.len() {
+            let x = skyline[start].0;
+
+            if x + cell_width > width {
+                break;
+            }
+
+            // The cell rests on the highest segment it spans.
+            let mut y = 0;
+            let mut covered = 0;
+
+            for segment in &skyline[start..] {
+                if covered >= cell_width {
+                    break;
+                }
+
+                y = y.max(segment.1);
+                covered += segment.2;
+            }
+
+            if y + cell_height > height {
+                continue;
+            }
+
+            if best.map_or(true, |(_, best_x, best_y)| (y, x) < (best_y, best_x)) {
+                best = Some((start, x, y));
+            }
+        }
+
+        let (start, x, y) = best?;
+
+        positions[*index] = (x, y);
+        skyline.insert(start, (x, y + cell_height, cell_width));
+
+        // Trims the segments the cell now covers.
+        let right = x + cell_width;
+
+        while start + 1 < skyline.len() && skyline[start + 1].0 < right {
+            let segment = &mut skyline[start + 1];
+            let overlap = right - segment.0;
+
+            if segment.2 <= overlap {
+                skyline.remove(start + 1);
+            } else {
+                segment.0 += overlap;
+                segment.2 -= overlap;
+            }
+        }
+
+        skyline.dedup_by(|next, previous| {
+            if next.1 == previous.1 {
+                previous.2 += next.2;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    Some(positions)
+}
+
+/// The result of packing an atlas, which can be made into a texture or
+/// saved to be loaded later as a sprite sheet.
+pub struct PackedAtlas {
+    pub image: image::RgbaImage,
+    pub regions: Vec<AtlasRegion>,
+    pub mip_safe_levels: u32,
+}
+
+impl PackedAtlas {
+    /// Saves the atlas image and a JSON description of its regions that
+    /// `TextureBuilder::load_sprite_sheet` can read.
+    pub fn save<P: AsRef<Path>, Q: AsRef<Path>>(&self, image_path: P, metadata_path: Q) -> Result<(), Box<dyn std::error::Error>> {
+        let image_path = image_path.as_ref();
+        let metadata_path = metadata_path.as_ref();
+
+        self.image.save(image_path)?;
+
+        // The image is found relative to the metadata when it's next to it.
+        let relative_path = metadata_path
+            .parent()
+            .and_then(|directory| image_path.strip_prefix(directory).ok())
+            .unwrap_or(image_path);
+
+        let frames: serde_json::Map<String, serde_json::Value> = self
+            .regions
+            .iter()
+            .map(|region| {
+                let frame = serde_json::json!({
+                    "frame": {
+                        "x": region.x,
+                        "y": region.y,
+                        "w": region.width,
+                        "h": region.height,
+                    }
+                });
+
+                (region.name.clone(), frame)
+            })
+            .collect();
+
+        let metadata = serde_json::json!({
+            "frames": frames,
+            "meta": {
+                "image": relative_path.to_string_lossy(),
+                "size": {
+                    "w": self.image.width(),
+                    "h": self.image.height(),
+                },
+            },
+        });
+
+        std::fs::write(metadata_path, serde_json::to_string_pretty(&metadata)?)?;
+
+        Ok(())
+    }
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Uploads a packed atlas. Its mip levels stop at the ones it was
+    /// packed to keep separate, unless a sampler was given.
+    pub fn from_atlas(mut self, atlas: PackedAtlas) -> Result<TextureAtlas, Box<dyn std::error::Error>> {
+        if self.mipmaps != MipmapGeneration::None && self.sampler.is_none() {
+            self.sampler_builder.sampler_desc.lod_max_clamp = atlas.mip_safe_levels as f32;
+        }
+
+        let texture = self.create_rgba_texture(&atlas.image)?;
+
+        Ok(TextureAtlas::new(texture, atlas.regions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack_skyline, AtlasPacker, AtlasRegion};
+
+    fn solid(width: u32, height: u32, value: u8) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba([value, value, value, 255]))
+    }
+
+    fn overlaps((x, y, width, height): (u32, u32, u32, u32), (other_x, other_y, other_width, other_height): (u32, u32, u32, u32)) -> bool {
+        x < other_x + other_width && other_x < x + width && y < other_y + other_height && other_y < y + height
+    }
+
+    /// The region with its padding around it.
+    fn padded(region: &AtlasRegion, padding: u32) -> (u32, u32, u32, u32) {
+        (region.x - padding, region.y - padding, region.width + 2 * padding, region.height + 2 * padding)
+    }
+
+    #[test]
+    fn skyline_places_cells_apart_and_inside() {
+        let cells = [(4, 4), (2, 6), (6, 2), (3, 3), (8, 1)];
+        let order: Vec<usize> = (0..cells.len()).collect();
+
+        let positions = pack_skyline(&cells, &order, 16, 16).unwrap();
+        let rects: Vec<(u32, u32, u32, u32)> = positions.iter().zip(&cells).map(|((x, y), (w, h))| (*x, *y, *w, *h)).collect();
+
+        for (i, rect) in rects.iter().enumerate() {
+            assert!(rect.0 + rect.2 <= 16 && rect.1 + rect.3 <= 16, "cell {} is outside", i);
+
+            for (j, other) in rects.iter().enumerate().skip(i + 1) {
+                assert!(!overlaps(*rect, *other), "cells {} and {} overlap", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn skyline_fails_when_cells_dont_fit() {
+        assert_eq!(pack_skyline(&[(8, 8), (8, 8), (8, 8)], &[0, 1, 2], 16, 8), None);
+        assert_eq!(pack_skyline(&[(17, 1)], &[0], 16, 16), None);
+    }
+
+    #[test]
+    fn packed_regions_keep_their_padding_apart() {
+        let atlas = AtlasPacker::new()
+            .with_padding(2)
+            .add_image("a", solid(10, 3, 10))
+            .add_image("b", solid(5, 7, 20))
+            .add_image("c", solid(1, 1, 30))
+            .add_image("d", solid(6, 6, 40))
+            .pack()
+            .unwrap();
+
+        for (i, region) in atlas.regions.iter().enumerate() {
+            assert!(region.x >= 2 && region.y >= 2);
+            assert!(region.x + region.width + 2 <= atlas.image.width());
+            assert!(region.y + region.height + 2 <= atlas.image.height());
+
+            for other in atlas.regions.iter().skip(i + 1) {
+                assert!(!overlaps(padded(region, 2), padded(other, 2)), "{} and {} overlap", region.name, other.name);
+            }
+        }
+
+        // The image lands in its region, and without gutters the padding stays clear.
+        let b = &atlas.regions[1];
+        assert_eq!(atlas.image.get_pixel(b.x, b.y).0, [20, 20, 20, 255]);
+        assert_eq!(atlas.image.get_pixel(b.x - 1, b.y).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn gutters_repeat_the_edge_pixels() {
+        let atlas = AtlasPacker::new().with_padding(2).with_gutters(true).add_image("a", solid(3, 3, 50)).pack().unwrap();
+        let region = &atlas.regions[0];
+
+        assert_eq!(atlas.image.get_pixel(region.x - 2, region.y - 2).0, [50, 50, 50, 255]);
+        assert_eq!(atlas.image.get_pixel(region.x + 4, region.y + 1).0, [50, 50, 50, 255]);
+    }
+
+    #[test]
+    fn mip_safe_regions_are_aligned() {
+        let atlas = AtlasPacker::new()
+            .with_mip_safe_levels(2)
+            .add_image("a", solid(5, 3, 10))
+            .add_image("b", solid(2, 9, 20))
+            .add_image("c", solid(7, 7, 30))
+            .pack()
+            .unwrap();
+
+        // The padding grows to half the alignment of 4.
+        for region in &atlas.regions {
+            assert_eq!((region.x - 2) % 4, 0, "{}", region.name);
+            assert_eq!((region.y - 2) % 4, 0, "{}", region.name);
+        }
+    }
+
+    #[test]
+    fn images_that_dont_fit_the_max_size_are_an_error() {
+        let result = AtlasPacker::new().with_max_size(16).add_image("a", solid(20, 4, 0)).pack();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn duplicate_names_are_an_error() {
+        let result = AtlasPacker::new().add_image("a", solid(2, 2, 0)).add_image("a", solid(3, 3, 0)).pack();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn empty_images_and_too_many_mip_safe_levels_are_errors() {
+        assert!(AtlasPacker::new().with_gutters(true).add_image("a", solid(0, 4, 0)).pack().is_err());
+        assert!(AtlasPacker::new().with_mip_safe_levels(32).add_image("a", solid(2, 2, 0)).pack().is_err());
+        assert!(AtlasPacker::new().with_mip_safe_levels(13).add_image("a", solid(2, 2, 0)).pack().is_err());
+    }
+}
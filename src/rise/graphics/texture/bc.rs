@@ -0,0 +1,1057 @@
+use super::TextureError;
+
+/// Decodes one block into its 16 pixels.
+type BlockDecoder<P> = fn(&[u8], &mut [P; 16]);
+
+/// Decodes block compressed pixels for adapters without BC support, and
+/// returns the uncompressed format they're in. BC4 and BC5 fill the red and
+/// green channels, and BC6H decodes to `Rgba16Float`.
+pub(crate) fn decompress_bc(
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<(wgpu::TextureFormat, Vec<u8>), TextureError> {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        Bc6hRgbUfloat => return Ok((Rgba16Float, decode_blocks(width, height, data, 16, decode_bc6h_ufloat)?)),
+        Bc6hRgbSfloat => return Ok((Rgba16Float, decode_blocks(width, height, data, 16, decode_bc6h_sfloat)?)),
+        _ => {}
+    }
+
+    let (output, block_size, decode): (wgpu::TextureFormat, usize, BlockDecoder<[u8; 4]>) = match format {
+        Bc1RgbaUnorm => (Rgba8Unorm, 8, decode_bc1),
+        Bc1RgbaUnormSrgb => (Rgba8UnormSrgb, 8, decode_bc1),
+        Bc2RgbaUnorm => (Rgba8Unorm, 16, decode_bc2),
+        Bc2RgbaUnormSrgb => (Rgba8UnormSrgb, 16, decode_bc2),
+        Bc3RgbaUnorm => (Rgba8Unorm, 16, decode_bc3),
+        Bc3RgbaUnormSrgb => (Rgba8UnormSrgb, 16, decode_bc3),
+        Bc4RUnorm => (Rgba8Unorm, 8, decode_bc4_unorm),
+        Bc4RSnorm => (Rgba8Snorm, 8, decode_bc4_snorm),
+        Bc5RgUnorm => (Rgba8Unorm, 16, decode_bc5_unorm),
+        Bc5RgSnorm => (Rgba8Snorm, 16, decode_bc5_snorm),
+        Bc7RgbaUnorm => (Rgba8Unorm, 16, decode_bc7),
+        Bc7RgbaUnormSrgb => (Rgba8UnormSrgb, 16, decode_bc7),
+        _ => return Err(TextureError::new(&format!("{:?} isn't block compressed", format))),
+    };
+
+    Ok((output, decode_blocks(width, height, data, block_size, decode)?))
+}
+
+/// Decodes every block of an image into pixels of type `P`, cropping the
+/// blocks that hang off its edges.
+fn decode_blocks<P: Copy + Default + AsRef<[u8]>>(
+    width: u32,
+    height: u32,
+    data: &[u8],
+    block_size: usize,
+    decode: BlockDecoder<P>,
+) -> Result<Vec<u8>, TextureError> {
+    let pixel_size = std::mem::size_of::<P>();
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = (width + 3) / 4;
+    let blocks_high = (height + 3) / 4;
+
+    if data.len() < blocks_wide * blocks_high * block_size {
+        return Err(TextureError::new("Block compressed data ended early"));
+    }
+
+    let mut pixels = vec![0; width * height * pixel_size];
+    let mut block = [P::default(); 16];
+
+    for block_y in 0..blocks_high {
+        for block_x in 0..blocks_wide {
+            let offset = (block_y * blocks_wide + block_x) * block_size;
+
+            decode(&data[offset..offset + block_size], &mut block);
+
+            for (i, pixel) in block.iter().enumerate() {
+                let x = block_x * 4 + i % 4;
+                let y = block_y * 4 + i / 4;
+
+                // Blocks on the right and bottom edges can hang off the image.
+                if x < width && y < height {
+                    let start = (y * width + x) * pixel_size;
+                    pixels[start..start + pixel_size].copy_from_slice(pixel.as_ref());
+                }
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+fn rgb565(color: u16) -> [u32; 3] {
+    let r = (color >> 11) as u32 & 0x1f;
+    let g = (color >> 5) as u32 & 0x3f;
+    let b = color as u32 & 0x1f;
+
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+}
+
+/// Decodes the color half of a BC1, BC2 or BC3 block. Only BC1 can use the
+/// three color mode with transparent black.
+fn decode_colors(block: &[u8], allow_transparent: bool, out: &mut [[u8; 4]; 16]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+
+    let (a, b) = (rgb565(c0), rgb565(c1));
+
+    let mix = |weight_a: u32, weight_b: u32| {
+        let channel = |i: usize| ((a[i] * weight_a + b[i] * weight_b) / (weight_a + weight_b)) as u8;
+
+        [channel(0), channel(1), channel(2), 255]
+    };
+
+    let palette = if c0 > c1 || !allow_transparent {
+        [mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+    } else {
+        [mix(1, 0), mix(0, 1), mix(1, 1), [0, 0, 0, 0]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = palette[(indices >> (2 * i)) as usize & 3];
+    }
+}
+
+/// Decodes a BC4 style block of one unsigned channel.
+fn decode_channel_unorm(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+
+    let mut palette = [a0, a1, 0, 0, 0, 0, 0, 255];
+
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i as u32) * a0 + i as u32 * a1 + 3) / 7;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i as u32) * a0 + i as u32 * a1 + 2) / 5;
+        }
+    }
+
+    let indices = channel_indices(block);
+    let mut values = [0; 16];
+
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[indices[i]] as u8;
+    }
+
+    values
+}
+
+/// Decodes a BC4 style block of one signed channel.
+fn decode_channel_snorm(block: &[u8]) -> [i8; 16] {
+    // -128 and -127 both mean -1.
+    let (a0, a1) = ((block[0] as i8).max(-127) as f32, (block[1] as i8).max(-127) as f32);
+
+    let mut palette = [a0, a1, 0., 0., 0., 0., -127., 127.];
+
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = ((7 - i) as f32 * a0 + i as f32 * a1) / 7.;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((5 - i) as f32 * a0 + i as f32 * a1) / 5.;
+        }
+    }
+
+    let indices = channel_indices(block);
+    let mut values = [0; 16];
+
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[indices[i]].round() as i8;
+    }
+
+    values
+}
+
+/// The 3 bit palette indices of a BC4 style block.
+fn channel_indices(block: &[u8]) -> [usize; 16] {
+    let bits = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, byte| (bits << 8) | *byte as u64);
+
+    let mut indices = [0; 16];
+
+    for (i, index) in indices.iter_mut().enumerate() {
+        *index = (bits >> (3 * i)) as usize & 7;
+    }
+
+    indices
+}
+
+fn decode_bc1(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_colors(block, true, out);
+}
+
+fn decode_bc2(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_colors(&block[8..], false, out);
+
+    let alpha = u64::from_le_bytes([block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7]]);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        pixel[3] = ((alpha >> (4 * i)) & 0xf) as u8 * 17;
+    }
+}
+
+fn decode_bc3(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    decode_colors(&block[8..], false, out);
+
+    for (pixel, alpha) in out.iter_mut().zip(decode_channel_unorm(&block[..8]).iter()) {
+        pixel[3] = *alpha;
+    }
+}
+
+fn decode_bc4_unorm(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    for (pixel, red) in out.iter_mut().zip(decode_channel_unorm(block).iter()) {
+        *pixel = [*red, 0, 0, 255];
+    }
+}
+
+fn decode_bc4_snorm(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    for (pixel, red) in out.iter_mut().zip(decode_channel_snorm(block).iter()) {
+        *pixel = [*red as u8, 0, 0, 127];
+    }
+}
+
+fn decode_bc5_unorm(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let red = decode_channel_unorm(&block[..8]);
+    let green = decode_channel_unorm(&block[8..]);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = [red[i], green[i], 0, 255];
+    }
+}
+
+fn decode_bc5_snorm(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let red = decode_channel_snorm(&block[..8]);
+    let green = decode_channel_snorm(&block[8..]);
+
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = [red[i] as u8, green[i] as u8, 0, 127];
+    }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    /// A p-bit for every endpoint.
+    endpoint_p_bits: bool,
+    /// A p-bit for both endpoints of each subset.
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+/// Which of two subsets each pixel is in, one bit per pixel.
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Which of three subsets each pixel is in.
+const BC7_PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+/// The pixel whose index has its top bit left out, for the second subset
+/// of two.
+const BC7_ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The same for the second and third subsets of three.
+const BC7_ANCHORS_3: [[usize; 64]; 2] = [
+    [
+        3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3,
+        3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15,
+        8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15,
+        3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+    ],
+    [
+        15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8,
+        15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10, 8,
+        15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8,
+        15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+    ],
+];
+
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Reads a block's bits from least to most significant.
+struct BitReader {
+    bits: u128,
+    position: u32,
+}
+
+impl BitReader {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+fn bc7_subset(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => (BC7_PARTITIONS_2[partition] >> pixel) as usize & 1,
+        3 => BC7_PARTITIONS_3[partition][pixel] as usize,
+        _ => 0,
+    }
+}
+
+fn bc7_is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    match subsets {
+        2 => pixel == 0 || pixel == BC7_ANCHORS_2[partition],
+        3 => pixel == 0 || pixel == BC7_ANCHORS_3[0][partition] || pixel == BC7_ANCHORS_3[1][partition],
+        _ => pixel == 0,
+    }
+}
+
+fn bc7_interpolate(e0: u32, e1: u32, index_bits: u32, index: u32) -> u8 {
+    let weight = match index_bits {
+        2 => BC7_WEIGHTS_2[index as usize],
+        3 => BC7_WEIGHTS_3[index as usize],
+        _ => BC7_WEIGHTS_4[index as usize],
+    };
+
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+/// Widens an endpoint to 8 bits by repeating its top bits.
+fn bc7_expand(value: u32, bits: u32) -> u32 {
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+fn decode_bc7(block: &[u8], out: &mut [[u8; 4]; 16]) {
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(block);
+
+    let mut reader = BitReader {
+        bits: u128::from_le_bytes(bytes),
+        position: 0,
+    };
+
+    // The mode is the number of zero bits before the first one.
+    let mode = match (0..8).find(|_| reader.read(1) == 1) {
+        Some(mode) => &BC7_MODES[mode],
+        None => {
+            *out = [[0; 4]; 16];
+            return;
+        }
+    };
+
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = reader.read(mode.color_bits);
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        endpoint[3] = reader.read(mode.alpha_bits);
+    }
+
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let mut p_bits = [0; 6];
+
+        if mode.endpoint_p_bits {
+            for p_bit in p_bits.iter_mut().take(endpoint_count) {
+                *p_bit = reader.read(1);
+            }
+        } else {
+            for subset in 0..mode.subsets {
+                let p_bit = reader.read(1);
+                p_bits[subset * 2] = p_bit;
+                p_bits[subset * 2 + 1] = p_bit;
+            }
+        }
+
+        for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits.iter()).take(endpoint_count) {
+            for channel in endpoint.iter_mut() {
+                *channel = (*channel << 1) | p_bit;
+            }
+        }
+
+        color_bits += 1;
+
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for channel in endpoint.iter_mut().take(3) {
+            *channel = bc7_expand(*channel, color_bits);
+        }
+
+        endpoint[3] = if alpha_bits > 0 {
+            bc7_expand(endpoint[3], alpha_bits)
+        } else {
+            255
+        };
+    }
+
+    let mut indices = [0; 16];
+    let mut secondary_indices = [0; 16];
+
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = bc7_is_anchor(mode.subsets, partition, pixel);
+        *index = reader.read(mode.index_bits - anchor as u32);
+    }
+
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = reader.read(mode.secondary_index_bits - (pixel == 0) as u32);
+        }
+    }
+
+    for (pixel, out) in out.iter_mut().enumerate() {
+        let subset = bc7_subset(mode.subsets, partition, pixel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        // Modes with two sets of indices pick which one the colors use.
+        let ((color_bits, color_index), (alpha_bits, alpha_index)) = if mode.secondary_index_bits == 0 {
+            ((mode.index_bits, indices[pixel]), (mode.index_bits, indices[pixel]))
+        } else if index_selection == 0 {
+            ((mode.index_bits, indices[pixel]), (mode.secondary_index_bits, secondary_indices[pixel]))
+        } else {
+            ((mode.secondary_index_bits, secondary_indices[pixel]), (mode.index_bits, indices[pixel]))
+        };
+
+        let mut color = [0; 4];
+
+        for channel in 0..3 {
+            color[channel] = bc7_interpolate(e0[channel], e1[channel], color_bits, color_index);
+        }
+
+        color[3] = bc7_interpolate(e0[3], e1[3], alpha_bits, alpha_index);
+
+        match rotation {
+            1 => color.swap(0, 3),
+            2 => color.swap(1, 3),
+            3 => color.swap(2, 3),
+            _ => {}
+        }
+
+        *out = color;
+    }
+}
+
+// The fields of a BC6H block's endpoints. 0 and 1 are the first subset's
+// endpoints and 2 and 3 the second's.
+const R0: usize = 0;
+const G0: usize = 1;
+const B0: usize = 2;
+const R1: usize = 3;
+const G1: usize = 4;
+const B1: usize = 5;
+const R2: usize = 6;
+const G2: usize = 7;
+const B2: usize = 8;
+const R3: usize = 9;
+const G3: usize = 10;
+const B3: usize = 11;
+
+struct Bc6hMode {
+    subsets: usize,
+    /// Whether the other endpoints are stored as deltas from the first.
+    transformed: bool,
+    endpoint_bits: u32,
+    /// The bits in each channel of the other endpoints.
+    delta_bits: [u32; 3],
+    /// Where the endpoint bits are after the mode, as runs written like the
+    /// spec's `r0[9:0]`: the field and the bits, read from the second bit
+    /// towards the first.
+    layout: &'static [(usize, u32, u32)],
+}
+
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5],
+        layout: &[
+            (G2, 4, 4), (B2, 4, 4), (B3, 4, 4), (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0), (G3, 4, 4),
+            (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0),
+            (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6],
+        layout: &[
+            (G2, 5, 5), (G3, 4, 4), (G3, 5, 5), (R0, 6, 0), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 6, 0),
+            (B2, 5, 5), (B3, 2, 2), (G2, 4, 4), (B0, 6, 0), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 5, 0),
+            (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0), (R0, 10, 10), (G2, 3, 0), (G1, 3, 0), (G0, 10, 10),
+            (B3, 0, 0), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2),
+            (R3, 4, 0), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (G3, 4, 4), (G2, 3, 0), (G1, 4, 0),
+            (G0, 10, 10), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0), (R2, 3, 0), (B3, 0, 0),
+            (B3, 2, 2), (R3, 3, 0), (G2, 4, 4), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (B2, 4, 4), (G2, 3, 0), (G1, 3, 0),
+            (G0, 10, 10), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B0, 10, 10), (B2, 3, 0), (R2, 3, 0), (B3, 1, 1),
+            (B3, 2, 2), (R3, 3, 0), (B3, 4, 4), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5],
+        layout: &[
+            (R0, 8, 0), (B2, 4, 4), (G0, 8, 0), (G2, 4, 4), (B0, 8, 0), (B3, 4, 4), (R1, 4, 0), (G3, 4, 4),
+            (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0),
+            (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5],
+        layout: &[
+            (R0, 7, 0), (G3, 4, 4), (B2, 4, 4), (G0, 7, 0), (B3, 2, 2), (G2, 4, 4), (B0, 7, 0), (B3, 3, 3),
+            (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1),
+            (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5],
+        layout: &[
+            (R0, 7, 0), (B3, 0, 0), (B2, 4, 4), (G0, 7, 0), (G2, 5, 5), (G2, 4, 4), (B0, 7, 0), (G3, 5, 5),
+            (B3, 4, 4), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1),
+            (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6],
+        layout: &[
+            (R0, 7, 0), (B3, 1, 1), (B2, 4, 4), (G0, 7, 0), (B2, 5, 5), (G2, 4, 4), (B0, 7, 0), (B3, 5, 5),
+            (B3, 4, 4), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 5, 0),
+            (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+        ],
+    },
+    Bc6hMode {
+        subsets: 2, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6],
+        layout: &[
+            (R0, 5, 0), (G3, 4, 4), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 5, 0), (G2, 5, 5), (B2, 5, 5),
+            (B3, 2, 2), (G2, 4, 4), (B0, 5, 0), (G3, 5, 5), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 5, 0),
+            (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+        ],
+    },
+    Bc6hMode {
+        subsets: 1, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10],
+        layout: &[(R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 9, 0), (G1, 9, 0), (B1, 9, 0)],
+    },
+    Bc6hMode {
+        subsets: 1, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 8, 0), (R0, 10, 10), (G1, 8, 0), (G0, 10, 10), (B1, 8, 0),
+            (B0, 10, 10),
+        ],
+    },
+    Bc6hMode {
+        subsets: 1, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 7, 0), (R0, 10, 11), (G1, 7, 0), (G0, 10, 11), (B1, 7, 0),
+            (B0, 10, 11),
+        ],
+    },
+    Bc6hMode {
+        subsets: 1, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4],
+        layout: &[
+            (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 15), (G1, 3, 0), (G0, 10, 15), (B1, 3, 0),
+            (B0, 10, 15),
+        ],
+    },
+];
+
+/// The mode a BC6H block's mode bits select, or `None` for the reserved
+/// ones.
+fn bc6h_mode(mode_bits: u32) -> Option<&'static Bc6hMode> {
+    let index = match mode_bits {
+        0b00 => 0,
+        0b01 => 1,
+        0b00010 => 2,
+        0b00110 => 3,
+        0b01010 => 4,
+        0b01110 => 5,
+        0b10010 => 6,
+        0b10110 => 7,
+        0b11010 => 8,
+        0b11110 => 9,
+        0b00011 => 10,
+        0b00111 => 11,
+        0b01011 => 12,
+        0b01111 => 13,
+        _ => return None,
+    };
+
+    Some(&BC6H_MODES[index])
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    (value << (32 - bits)) >> (32 - bits)
+}
+
+/// Spreads an endpoint over the full 16 bit range before interpolating.
+fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if signed {
+        if bits >= 16 {
+            return value;
+        }
+
+        let magnitude = value.abs();
+
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7fff
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+
+        if value < 0 {
+            -unquantized
+        } else {
+            unquantized
+        }
+    } else if bits >= 15 {
+        value
+    } else if value == 0 {
+        0
+    } else if value == (1 << bits) - 1 {
+        0xffff
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+/// Scales an interpolated value to the bits of a half float, which are
+/// sign and magnitude when signed.
+fn bc6h_half(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        0x8000 | ((-value * 31) >> 5) as u16
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+fn decode_bc6h_ufloat(block: &[u8], out: &mut [[u8; 8]; 16]) {
+    decode_bc6h(block, false, out);
+}
+
+fn decode_bc6h_sfloat(block: &[u8], out: &mut [[u8; 8]; 16]) {
+    decode_bc6h(block, true, out);
+}
+
+/// Decodes a BC6H block into half float pixels with an alpha of 1.
+fn decode_bc6h(block: &[u8], signed: bool, out: &mut [[u8; 8]; 16]) {
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(block);
+
+    let mut reader = BitReader {
+        bits: u128::from_le_bytes(bytes),
+        position: 0,
+    };
+
+    let pixel = |[r, g, b]: [u16; 3]| {
+        let mut pixel = [0; 8];
+
+        for (channel, value) in [r, g, b, 0x3c00].iter().enumerate() {
+            pixel[channel * 2..channel * 2 + 2].copy_from_slice(&value.to_le_bytes());
+        }
+
+        pixel
+    };
+
+    // Two bit modes are 0 and 1, and the rest have three more bits.
+    let mut mode_bits = reader.read(2);
+
+    if mode_bits > 1 {
+        mode_bits |= reader.read(3) << 2;
+    }
+
+    let mode = match bc6h_mode(mode_bits) {
+        Some(mode) => mode,
+        None => {
+            *out = [pixel([0, 0, 0]); 16];
+            return;
+        }
+    };
+
+    let mut fields = [0; 12];
+
+    for (field, first, last) in mode.layout {
+        if first >= last {
+            for bit in *last..=*first {
+                fields[*field] |= reader.read(1) << bit;
+            }
+        } else {
+            for bit in (*first..=*last).rev() {
+                fields[*field] |= reader.read(1) << bit;
+            }
+        }
+    }
+
+    let partition = reader.read(if mode.subsets == 2 { 5 } else { 0 }) as usize;
+
+    let endpoint_count = mode.subsets * 2;
+    let mask = (1 << mode.endpoint_bits) - 1;
+    let mut endpoints = [[0i32; 3]; 4];
+
+    for channel in 0..3 {
+        let mut first = fields[channel] as i32;
+
+        if signed {
+            first = sign_extend(first, mode.endpoint_bits);
+        }
+
+        endpoints[0][channel] = first;
+
+        for endpoint in 1..endpoint_count {
+            let mut value = fields[endpoint * 3 + channel] as i32;
+
+            if mode.transformed {
+                value = (first + sign_extend(value, mode.delta_bits[channel])) & mask;
+            }
+
+            if signed {
+                value = sign_extend(value, mode.endpoint_bits);
+            }
+
+            endpoints[endpoint][channel] = value;
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for channel in endpoint.iter_mut() {
+            *channel = bc6h_unquantize(*channel, mode.endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if mode.subsets == 2 { 3 } else { 4 };
+
+    for (index, out) in out.iter_mut().enumerate() {
+        let anchor = bc7_is_anchor(mode.subsets, partition, index);
+        let weight = match index_bits {
+            3 => BC7_WEIGHTS_3[reader.read(3 - anchor as u32) as usize],
+            _ => BC7_WEIGHTS_4[reader.read(4 - anchor as u32) as usize],
+        } as i32;
+
+        let subset = bc7_subset(mode.subsets, partition, index);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let mut color = [0; 3];
+
+        for channel in 0..3 {
+            let value = ((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6;
+            color[channel] = bc6h_half(value, signed);
+        }
+
+        *out = pixel(color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompress_bc;
+
+    /// Indices that pick each entry of a 4 color palette in turn.
+    const COLOR_INDICES: [u8; 4] = [0xe4, 0xe4, 0xe4, 0xe4];
+
+    /// Indices that pick each entry of an 8 value palette in turn.
+    const CHANNEL_INDICES: [u8; 6] = [0x88, 0xc6, 0xfa, 0x88, 0xc6, 0xfa];
+
+    /// The palettes from the BC4 spec for endpoints of 255 and 0, and of 0
+    /// and 255, which switches to 6 interpolated values and then 0 and 255.
+    const EIGHT_VALUES: [u8; 8] = [255, 0, 219, 182, 146, 109, 73, 36];
+    const SIX_VALUES: [u8; 8] = [0, 255, 51, 102, 153, 204, 0, 255];
+
+    fn decode(format: wgpu::TextureFormat, block: &[u8]) -> Vec<[u8; 4]> {
+        let (_, pixels) = decompress_bc(format, 4, 4, block).unwrap();
+
+        pixels.chunks(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect()
+    }
+
+    fn color_block(c0: u16, c1: u16) -> Vec<u8> {
+        let mut block = Vec::new();
+        block.extend_from_slice(&c0.to_le_bytes());
+        block.extend_from_slice(&c1.to_le_bytes());
+        block.extend_from_slice(&COLOR_INDICES);
+        block
+    }
+
+    fn channel_block(a0: u8, a1: u8) -> Vec<u8> {
+        let mut block = vec![a0, a1];
+        block.extend_from_slice(&CHANNEL_INDICES);
+        block
+    }
+
+    #[test]
+    fn bc1_decodes_four_colors() {
+        // Red then blue, so the two thirds blends come next.
+        let pixels = decode(wgpu::TextureFormat::Bc1RgbaUnorm, &color_block(0xf800, 0x001f));
+        let palette = [[255, 0, 0, 255], [0, 0, 255, 255], [170, 0, 85, 255], [85, 0, 170, 255]];
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, palette[i % 4], "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn bc1_decodes_three_colors_and_transparent_black() {
+        // Blue then red, which picks the mode with a transparent entry.
+        let pixels = decode(wgpu::TextureFormat::Bc1RgbaUnorm, &color_block(0x001f, 0xf800));
+        let palette = [[0, 0, 255, 255], [255, 0, 0, 255], [127, 0, 127, 255], [0, 0, 0, 0]];
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, palette[i % 4], "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn bc3_decodes_interpolated_alpha_with_four_colors() {
+        let mut block = channel_block(255, 0);
+        block.extend(color_block(0x001f, 0xf800));
+
+        let pixels = decode(wgpu::TextureFormat::Bc3RgbaUnorm, &block);
+
+        // BC3 colors always use 4 colors, whatever order the endpoints are in.
+        let colors = [[0, 0, 255], [255, 0, 0], [85, 0, 170], [170, 0, 85]];
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            let [r, g, b] = colors[i % 4];
+            assert_eq!(*pixel, [r, g, b, EIGHT_VALUES[i % 8]], "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn bc4_decodes_both_palette_modes() {
+        let pixels = decode(wgpu::TextureFormat::Bc4RUnorm, &channel_block(255, 0));
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, [EIGHT_VALUES[i % 8], 0, 0, 255], "pixel {}", i);
+        }
+
+        let pixels = decode(wgpu::TextureFormat::Bc4RUnorm, &channel_block(0, 255));
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, [SIX_VALUES[i % 8], 0, 0, 255], "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn bc5_decodes_red_and_green() {
+        let mut block = channel_block(255, 0);
+        block.extend(channel_block(0, 255));
+
+        let pixels = decode(wgpu::TextureFormat::Bc5RgUnorm, &block);
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, [EIGHT_VALUES[i % 8], SIX_VALUES[i % 8], 0, 255], "pixel {}", i);
+        }
+    }
+
+    /// Decodes a BC6H block to the half float bits of each pixel's rgb.
+    fn decode_bc6h(format: wgpu::TextureFormat, bits: u128) -> Vec<[u16; 3]> {
+        let (output, pixels) = decompress_bc(format, 4, 4, &bits.to_le_bytes()).unwrap();
+
+        assert_eq!(output, wgpu::TextureFormat::Rgba16Float);
+
+        pixels
+            .chunks(8)
+            .map(|pixel| {
+                assert_eq!(&pixel[6..8], &0x3c00u16.to_le_bytes(), "alpha should be 1");
+
+                let half = |channel: usize| u16::from_le_bytes([pixel[channel * 2], pixel[channel * 2 + 1]]);
+                [half(0), half(1), half(2)]
+            })
+            .collect()
+    }
+
+    /// A one subset block with 10 bit endpoints and pixel i at index i,
+    /// except the anchor pixel 0.
+    fn bc6h_single_subset(e0: u128, e1: u128) -> u128 {
+        let mut bits = 0b00011;
+
+        for (channel, value) in [e0, e0, e0, e1, e1, e1].iter().enumerate() {
+            bits |= value << (5 + 10 * channel);
+        }
+
+        for pixel in 1..16 {
+            bits |= (pixel as u128) << (65 + 3 + 4 * (pixel - 1));
+        }
+
+        bits
+    }
+
+    #[test]
+    fn bc6h_decodes_unsigned_endpoints_to_the_half_range() {
+        let pixels = decode_bc6h(wgpu::TextureFormat::Bc6hRgbUfloat, bc6h_single_subset(0, 1023));
+
+        // 0 and the largest endpoint map to 0 and the largest finite half.
+        assert_eq!(pixels[0], [0; 3]);
+        assert_eq!(pixels[15], [0x7bff; 3]);
+
+        for pair in pixels.windows(2) {
+            assert!(pair[1][0] > pair[0][0], "{:?} should increase", pixels);
+        }
+    }
+
+    #[test]
+    fn bc6h_decodes_signed_endpoints() {
+        // 10 bit endpoints of -511 and 511.
+        let pixels = decode_bc6h(wgpu::TextureFormat::Bc6hRgbSfloat, bc6h_single_subset(0x201, 0x1ff));
+
+        assert_eq!(pixels[0], [0xfbff; 3]);
+        assert_eq!(pixels[15], [0x7bff; 3]);
+    }
+
+    #[test]
+    fn bc6h_decodes_deltas_and_partitions() {
+        // Mode 1 with the first endpoint at 0, and deltas of -16 (wrapping to
+        // 1008) for both endpoints of the second subset, whose bits are
+        // spread through the block. Partition 0 puts the right two columns in
+        // the second subset.
+        let bits = [2, 3, 4, 40, 69, 75].iter().fold(0u128, |bits, bit| bits | 1 << bit);
+
+        let pixels = decode_bc6h(wgpu::TextureFormat::Bc6hRgbUfloat, bits);
+
+        // 1008 unquantizes to 64544, which scales to 0x7a1f.
+        for (i, pixel) in pixels.iter().enumerate() {
+            let expected = if i % 4 >= 2 { 0x7a1f } else { 0 };
+            assert_eq!(*pixel, [expected; 3], "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn bc6h_reserved_modes_decode_to_black() {
+        let pixels = decode_bc6h(wgpu::TextureFormat::Bc6hRgbUfloat, 0b10011 | 0xffff << 5);
+
+        assert!(pixels.iter().all(|pixel| *pixel == [0; 3]));
+    }
+
+    #[test]
+    fn blocks_hanging_off_the_edge_are_cropped() {
+        let block = color_block(0xf800, 0x001f);
+        let (_, pixels) = decompress_bc(wgpu::TextureFormat::Bc1RgbaUnorm, 3, 2, &block).unwrap();
+
+        assert_eq!(pixels.len(), 3 * 2 * 4);
+        assert_eq!(&pixels[12..16], &[255, 0, 0, 255]);
+    }
+}
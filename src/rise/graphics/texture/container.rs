@@ -0,0 +1,29 @@
+use super::dds::{is_dds, parse_dds};
+use super::ktx2::{is_ktx2, parse_ktx2};
+use super::TextureError;
+
+/// A texture read from a DDS or KTX2 file, with its mip levels still in
+/// the format they're stored in.
+pub(crate) struct ContainerImage<'a> {
+    pub(crate) format: wgpu::TextureFormat,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) levels: Vec<&'a [u8]>,
+}
+
+/// Reads a DDS or KTX2 file, or returns `None` for any other kind of image.
+pub(crate) fn parse_container(bytes: &[u8]) -> Result<Option<ContainerImage<'_>>, TextureError> {
+    let image = if is_dds(bytes) {
+        parse_dds(bytes)?
+    } else if is_ktx2(bytes) {
+        parse_ktx2(bytes)?
+    } else {
+        return Ok(None);
+    };
+
+    if image.width == 0 || image.height == 0 {
+        return Err(TextureError::new("Texture file has no pixels"));
+    }
+
+    Ok(Some(image))
+}
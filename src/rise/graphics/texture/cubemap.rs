@@ -0,0 +1,363 @@
+use std::path::Path;
+
+use super::half::srgb_to_linear;
+use super::hdr::decode_float_image;
+use super::texture::{float_pixels, write_layers};
+use super::{
+    generate_mipmaps_cpu, generate_mipmaps_gpu, mip_level_count, ColorSpace, FloatImage, MipmapGeneration, Texture,
+    TextureBuilder, TextureError,
+};
+use crate::graphics::RenderContext;
+
+/// The number of faces in a cubemap. Faces are stored as layers in the order
+/// +X, -X, +Y, -Y, +Z, -Z.
+pub const CUBE_FACE_COUNT: u32 = 6;
+
+impl<'a> TextureBuilder<'a> {
+    /// Loads a cubemap from six square images of the same size, given in the
+    /// order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn load_cube_files<P: AsRef<Path> + core::fmt::Debug>(self, paths: &[P; 6]) -> Result<Texture, Box<dyn std::error::Error>> {
+        let mut faces = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            faces.push(image::open(path)?.to_rgba());
+        }
+
+        let size = faces[0].width();
+
+        for (face, path) in faces.iter().zip(paths) {
+            if face.dimensions() != (size, size) {
+                return Err(Box::new(TextureError::new(&format!(
+                    "Cube face {:?} is {}x{}, but every face should be {}x{}",
+                    path, face.width(), face.height(), size, size
+                ))));
+            }
+        }
+
+        let levels = match self.mipmaps {
+            MipmapGeneration::None => 1,
+            _ => mip_level_count(size, size),
+        };
+
+        let mipmaps = self.mipmaps;
+        let render_context = self.render_context;
+        let format = self.get_rgba_format();
+        let texture = self.create_cube_texture(size, format, levels, mipmaps == MipmapGeneration::Gpu);
+
+        for (layer, face) in faces.iter().enumerate() {
+            write_face(render_context, &texture, layer as u32, 0, face);
+
+            if mipmaps == MipmapGeneration::Cpu {
+                for (level, image) in generate_mipmaps_cpu(face, levels).iter().enumerate() {
+                    write_face(render_context, &texture, layer as u32, level as u32 + 1, image);
+                }
+            }
+        }
+
+        if mipmaps == MipmapGeneration::Gpu {
+            generate_mipmaps_gpu(render_context, texture.get_texture(), texture.get_format(), levels, CUBE_FACE_COUNT)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Loads an equirectangular panorama and projects it onto the faces of
+    /// a cubemap on the gpu, with the middle of the image facing -Z.
+    ///
+    /// Radiance `.hdr` and OpenEXR images keep their full range, and other
+    /// images are converted from the builder's color space, so the cubemap is
+    /// always linear `Rgba16Float`. 8 bit photos usually want
+    /// `ColorSpace::Srgb`. Its mip levels are always made on the gpu.
+    pub fn load_equirectangular<P: AsRef<Path>>(self, path: P, face_size: u32) -> Result<Texture, Box<dyn std::error::Error>> {
+        if face_size == 0 {
+            return Err(Box::new(TextureError::new("Cube faces must be at least 1x1")));
+        }
+
+        let image = read_panorama(path.as_ref(), self.color_space)?;
+        let (width, height) = image.dimensions();
+
+        let render_context = self.render_context;
+
+        let panorama = render_context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Panorama Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+
+        render_context.queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &panorama,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &float_pixels(&image, wgpu::TextureFormat::Rgba16Float),
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 8 * width,
+                rows_per_image: height,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+
+        let levels = match self.mipmaps {
+            MipmapGeneration::None => 1,
+            _ => mip_level_count(face_size, face_size),
+        };
+
+        let texture = self.create_cube_texture(face_size, wgpu::TextureFormat::Rgba16Float, levels, true);
+
+        project_panorama(render_context, &panorama, &texture)?;
+
+        generate_mipmaps_gpu(render_context, texture.get_texture(), texture.get_format(), levels, CUBE_FACE_COUNT)?;
+
+        Ok(texture)
+    }
+
+    /// Creates an empty cube texture for faces to be written to.
+    fn create_cube_texture(
+        mut self,
+        size: u32,
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+        render_target: bool,
+    ) -> Texture {
+        let mut usage = wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC;
+
+        if render_target {
+            usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        }
+
+        self.texture_desc = wgpu::TextureDescriptor {
+            // Each face is a layer of a 2d texture.
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth: CUBE_FACE_COUNT,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            label: Some("Cube Texture"),
+        };
+
+        self.view_dimension = wgpu::TextureViewDimension::Cube;
+
+        // Clamping keeps the edges of neighbouring faces from bleeding,
+        // unless an address mode was asked for.
+        if !self.sampler_builder.address_modes_set {
+            self.sampler_builder = self.sampler_builder.with_address_mode(wgpu::AddressMode::ClampToEdge);
+        }
+
+        self.build()
+    }
+}
+
+/// Reads a panorama as linear floats, converting 8 bit images from srgb
+/// when they hold colors.
+fn read_panorama(path: &Path, color_space: ColorSpace) -> Result<FloatImage, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+
+    if let Some(image) = decode_float_image(&bytes)? {
+        return Ok(image);
+    }
+
+    let image = image::load_from_memory(&bytes)?.to_rgba();
+
+    let to_linear = |value: u8| match color_space {
+        ColorSpace::Srgb => srgb_to_linear(value as f32 / 255.),
+        ColorSpace::Linear => value as f32 / 255.,
+    };
+
+    Ok(FloatImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y);
+
+        image::Rgba([to_linear(pixel[0]), to_linear(pixel[1]), to_linear(pixel[2]), pixel[3] as f32 / 255.])
+    }))
+}
+
+/// Copies an image into one mip level of one face of a cubemap.
+fn write_face(
+    render_context: &RenderContext,
+    texture: &Texture,
+    layer: u32,
+    level: u32,
+    image: &image::RgbaImage,
+) {
+    write_layers(render_context, texture, level, layer, (image.width(), image.height(), 1), image);
+}
+
+/// Renders each face of the cubemap's first level from the panorama.
+fn project_panorama(
+    render_context: &RenderContext,
+    panorama: &wgpu::Texture,
+    cubemap: &Texture,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use wgpu::util::DeviceExt;
+
+    let device = &render_context.device;
+
+    let vs_spirv = std::fs::read("res/shader/blit.vert.spv")?;
+    let fs_spirv = std::fs::read("res/shader/equirect_to_cube.frag.spv")?;
+
+    let vs_module = device.create_shader_module(wgpu::util::make_spirv(&vs_spirv));
+    let fs_module = device.create_shader_module(wgpu::util::make_spirv(&fs_spirv));
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                    component_type: wgpu::TextureComponentType::Float,
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Sampler {
+                    comparison: false
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::UniformBuffer {
+                    dynamic: false,
+                    min_binding_size: None
+                },
+                count: None
+            }
+        ],
+        label: Some("Panorama Layout"),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Panorama Pipeline Layout"),
+        push_constant_ranges: &[],
+        bind_group_layouts: &[&bind_group_layout],
+    });
+
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Panorama Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: &vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: &fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::None,
+            ..Default::default()
+        }),
+        color_states: &[wgpu::ColorStateDescriptor {
+            format: cubemap.get_format(),
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        depth_stencil_state: None,
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_buffers: &[],
+        },
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    });
+
+    // Wrapping around horizontally hides the seam at the back.
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Panorama Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let panorama_view = panorama.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Panorama Encoder"),
+    });
+
+    for face in 0..CUBE_FACE_COUNT {
+        let face_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Panorama Face Buffer"),
+            contents: bytemuck::cast_slice(&[face, 0, 0, 0]),
+            usage: wgpu::BufferUsage::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&panorama_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer(face_buffer.slice(..)),
+                }
+            ],
+            label: Some("Panorama Bind Group"),
+        });
+
+        let face_view = cubemap.get_texture().create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Cube Face"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_mip_level: 0,
+            level_count: std::num::NonZeroU32::new(1),
+            base_array_layer: face,
+            array_layer_count: std::num::NonZeroU32::new(1),
+            ..Default::default()
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &face_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    render_context.queue.submit(std::iter::once(encoder.finish()));
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use super::container::ContainerImage;
+use super::reader::Reader;
+use super::{level_byte_size, mip_level_count, mip_level_size, TextureError};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+
+// Header flags.
+const MIPMAP_COUNT: u32 = 0x20000;
+
+// Pixel format flags.
+const FOURCC: u32 = 0x4;
+const RGB: u32 = 0x40;
+
+// Caps2 flags.
+const CUBEMAP: u32 = 0x200;
+const VOLUME: u32 = 0x20_0000;
+
+// DX10 misc flags.
+const TEXTURECUBE: u32 = 0x4;
+
+pub(crate) fn is_dds(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Reads a 2d DDS texture and its mip levels, from either the legacy
+/// header or the DX10 one.
+pub(crate) fn parse_dds(bytes: &[u8]) -> Result<ContainerImage<'_>, TextureError> {
+    let mut reader = Reader::new(bytes, 0);
+
+    if reader.take(4)? != MAGIC {
+        return Err(TextureError::new("Not a DDS file"));
+    }
+
+    let _size = reader.u32()?;
+    let flags = reader.u32()?;
+    let height = reader.u32()?;
+    let width = reader.u32()?;
+    let _pitch = reader.u32()?;
+    let _depth = reader.u32()?;
+    let mip_map_count = reader.u32()?;
+    reader.skip(11 * 4)?;
+
+    let _pixel_format_size = reader.u32()?;
+    let pixel_format_flags = reader.u32()?;
+    let four_cc = reader.take(4)?;
+    let bit_count = reader.u32()?;
+    let masks = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+
+    let _caps = reader.u32()?;
+    let caps2 = reader.u32()?;
+    reader.skip(3 * 4)?;
+
+    if caps2 & (CUBEMAP | VOLUME) != 0 {
+        return Err(TextureError::new("Only 2d DDS textures are supported"));
+    }
+
+    let format = if pixel_format_flags & FOURCC != 0 && four_cc == b"DX10" {
+        let dxgi_format = reader.u32()?;
+        let _dimension = reader.u32()?;
+        let misc_flags = reader.u32()?;
+        let array_size = reader.u32()?;
+        reader.skip(4)?;
+
+        if misc_flags & TEXTURECUBE != 0 || array_size > 1 {
+            return Err(TextureError::new("Only 2d DDS textures are supported"));
+        }
+
+        dxgi_texture_format(dxgi_format).ok_or_else(|| {
+            TextureError::new(&format!("DDS DXGI format {} isn't supported", dxgi_format))
+        })?
+    } else if pixel_format_flags & FOURCC != 0 {
+        four_cc_texture_format(four_cc).ok_or_else(|| {
+            TextureError::new(&format!("DDS format {:?} isn't supported", String::from_utf8_lossy(four_cc)))
+        })?
+    } else if pixel_format_flags & RGB != 0 && bit_count == 32 {
+        match masks {
+            [0xff, 0xff00, 0xff_0000, _] => wgpu::TextureFormat::Rgba8Unorm,
+            [0xff_0000, 0xff00, 0xff, _] => wgpu::TextureFormat::Bgra8Unorm,
+            _ => return Err(TextureError::new("DDS channel layout isn't supported")),
+        }
+    } else {
+        return Err(TextureError::new("DDS pixel format isn't supported"));
+    };
+
+    let level_count = if flags & MIPMAP_COUNT != 0 {
+        mip_map_count.max(1)
+    } else {
+        1
+    };
+
+    if level_count > mip_level_count(width, height) {
+        return Err(TextureError::new(&format!(
+            "DDS texture has {} mip levels, but a {}x{} texture can only have {}",
+            level_count, width, height, mip_level_count(width, height)
+        )));
+    }
+
+    let mut levels = Vec::with_capacity(level_count as usize);
+
+    for level in 0..level_count {
+        let (level_width, level_height) = mip_level_size(width, height, level);
+        let size = level_byte_size(format, level_width, level_height).unwrap_or(0);
+
+        levels.push(reader.take(size)?);
+    }
+
+    Ok(ContainerImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+fn four_cc_texture_format(four_cc: &[u8]) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+
+    match four_cc {
+        b"DXT1" => Some(Bc1RgbaUnorm),
+        b"DXT2" | b"DXT3" => Some(Bc2RgbaUnorm),
+        b"DXT4" | b"DXT5" => Some(Bc3RgbaUnorm),
+        b"ATI1" | b"BC4U" => Some(Bc4RUnorm),
+        b"BC4S" => Some(Bc4RSnorm),
+        b"ATI2" | b"BC5U" => Some(Bc5RgUnorm),
+        b"BC5S" => Some(Bc5RgSnorm),
+        // Float formats are stored as D3DFORMAT numbers instead of text.
+        [113, 0, 0, 0] => Some(Rgba16Float),
+        [116, 0, 0, 0] => Some(Rgba32Float),
+        _ => None,
+    }
+}
+
+fn dxgi_texture_format(dxgi_format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+
+    match dxgi_format {
+        2 => Some(Rgba32Float),
+        10 => Some(Rgba16Float),
+        28 => Some(Rgba8Unorm),
+        29 => Some(Rgba8UnormSrgb),
+        87 => Some(Bgra8Unorm),
+        91 => Some(Bgra8UnormSrgb),
+        71 => Some(Bc1RgbaUnorm),
+        72 => Some(Bc1RgbaUnormSrgb),
+        74 => Some(Bc2RgbaUnorm),
+        75 => Some(Bc2RgbaUnormSrgb),
+        77 => Some(Bc3RgbaUnorm),
+        78 => Some(Bc3RgbaUnormSrgb),
+        80 => Some(Bc4RUnorm),
+        81 => Some(Bc4RSnorm),
+        83 => Some(Bc5RgUnorm),
+        84 => Some(Bc5RgSnorm),
+        95 => Some(Bc6hRgbUfloat),
+        96 => Some(Bc6hRgbSfloat),
+        98 => Some(Bc7RgbaUnorm),
+        99 => Some(Bc7RgbaUnormSrgb),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dds, MIPMAP_COUNT};
+
+    /// A 4x4 DXT1 file claiming `mip_map_count` levels, with data for
+    /// `levels` of them.
+    fn dxt1(mip_map_count: u32, levels: usize) -> Vec<u8> {
+        let mut header = vec![124, MIPMAP_COUNT, 4, 4, 0, 0, mip_map_count];
+        header.extend_from_slice(&[0; 11]);
+        header.extend_from_slice(&[32, super::FOURCC, u32::from_le_bytes(*b"DXT1"), 0, 0, 0, 0, 0]);
+        header.extend_from_slice(&[0; 5]);
+
+        let mut bytes = b"DDS ".to_vec();
+        bytes.extend(header.iter().flat_map(|value| value.to_le_bytes().to_vec()));
+        bytes.extend(vec![0; 8 * levels]);
+        bytes
+    }
+
+    #[test]
+    fn reads_a_full_mip_chain() {
+        let bytes = dxt1(3, 3);
+        let image = parse_dds(&bytes).unwrap();
+
+        assert_eq!(image.levels.len(), 3);
+    }
+
+    #[test]
+    fn rejects_more_mip_levels_than_the_size_allows() {
+        assert!(parse_dds(&dxt1(4, 4)).is_err());
+        assert!(parse_dds(&dxt1(40, 40)).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use std::borrow::Cow;
+
+use super::half::f32_from_f16_bits;
+use super::reader::Reader;
+use super::{FloatImage, TextureError};
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+
+// Version flags for the kinds of file that aren't plain scanline images.
+const TILED: u32 = 0x200;
+const DEEP: u32 = 0x800;
+const MULTIPART: u32 = 0x1000;
+
+/// The widest or tallest image decoded, which is already past what gpus
+/// take as a 2d texture. Anything bigger is more likely a corrupt header.
+const MAX_DIMENSION: usize = 16384;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PixelType {
+    Uint,
+    Half,
+    Float,
+}
+
+impl PixelType {
+    fn size(self) -> usize {
+        match self {
+            PixelType::Half => 2,
+            PixelType::Uint | PixelType::Float => 4,
+        }
+    }
+}
+
+struct Channel {
+    name: String,
+    pixel_type: PixelType,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Compression {
+    None,
+    Rle,
+    Zips,
+    Zip,
+}
+
+impl Compression {
+    fn lines_per_block(self) -> usize {
+        match self {
+            Compression::Zip => 16,
+            _ => 1,
+        }
+    }
+}
+
+pub(crate) fn is_exr(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Decodes a single part scanline OpenEXR image, the kind most tools
+/// export, with no, rle or zip compression. R, G, B and A channels fill the
+/// matching components, with layered names like `diffuse.R` taking their
+/// last part, and a Y channel fills all three colors.
+pub(crate) fn decode_exr(bytes: &[u8]) -> Result<FloatImage, TextureError> {
+    let mut reader = Reader::new(bytes, 0);
+
+    if reader.take(4)? != MAGIC {
+        return Err(TextureError::new("Not an EXR image"));
+    }
+
+    if reader.u32()? & (TILED | DEEP | MULTIPART) != 0 {
+        return Err(TextureError::new("Only single part scanline EXR images are supported"));
+    }
+
+    let mut channels = None;
+    let mut compression = None;
+    let mut data_window = None;
+
+    loop {
+        let name = reader.string()?;
+
+        if name.is_empty() {
+            break;
+        }
+
+        let _attribute_type = reader.string()?;
+        let size = reader.i32()?;
+
+        if size < 0 {
+            return Err(TextureError::new(&format!("EXR attribute {} has a negative size", name)));
+        }
+
+        let mut value = Reader::new(reader.take(size as usize)?, 0);
+
+        match name.as_str() {
+            "channels" => channels = Some(read_channels(&mut value)?),
+            "compression" => compression = Some(match value.u8()? {
+                0 => Compression::None,
+                1 => Compression::Rle,
+                2 => Compression::Zips,
+                3 => Compression::Zip,
+                other => return Err(TextureError::new(&format!(
+                    "EXR compression {} isn't supported, only none, rle and zip are",
+                    other
+                ))),
+            }),
+            "dataWindow" => data_window = Some((value.i32()?, value.i32()?, value.i32()?, value.i32()?)),
+            _ => {}
+        }
+    }
+
+    let channels = channels.ok_or_else(|| TextureError::new("EXR image has no channels"))?;
+    let compression = compression.unwrap_or(Compression::None);
+    let (x_min, y_min, x_max, y_max) = data_window.ok_or_else(|| TextureError::new("EXR image has no data window"))?;
+
+    if x_max < x_min || y_max < y_min {
+        return Err(TextureError::new("EXR image has an empty data window"));
+    }
+
+    let width = (x_max as i64 - x_min as i64 + 1) as usize;
+    let height = (y_max as i64 - y_min as i64 + 1) as usize;
+
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(TextureError::new(&format!(
+            "EXR image is {}x{}, but images can be at most {} pixels across",
+            width, height, MAX_DIMENSION
+        )));
+    }
+
+    let lines_per_block = compression.lines_per_block();
+    let line_size: usize = channels.iter().map(|channel| channel.pixel_type.size() * width).sum();
+
+    let mut offsets = Vec::new();
+
+    for _ in 0..(height + lines_per_block - 1) / lines_per_block {
+        offsets.push(reader.u64()?);
+    }
+
+    // The components of each pixel a channel is written to.
+    let targets: Vec<&[usize]> = channels
+        .iter()
+        .map(|channel| match channel.name.rsplit('.').next() {
+            Some("R") => &[0][..],
+            Some("G") => &[1][..],
+            Some("B") => &[2][..],
+            Some("A") => &[3][..],
+            Some("Y") => &[0, 1, 2][..],
+            _ => &[][..],
+        })
+        .collect();
+
+    let pixel_count = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(4))
+        .ok_or_else(|| TextureError::new("EXR image is too large"))?;
+
+    let mut pixels = vec![0.; pixel_count];
+
+    for pixel in pixels.chunks_mut(4) {
+        pixel[3] = 1.;
+    }
+
+    for offset in offsets {
+        let mut block = Reader::new(bytes, offset as usize);
+
+        let y = block.i32()? as i64 - y_min as i64;
+        let size = block.i32()?;
+
+        if y < 0 || y as usize >= height || size < 0 {
+            return Err(TextureError::new(&format!("EXR image has a corrupt block at offset {}", offset)));
+        }
+
+        let first_line = y as usize;
+        let lines = lines_per_block.min(height - first_line);
+        let expected = line_size * lines;
+
+        let data = block.take(size as usize)?;
+
+        // Blocks that wouldn't get any smaller are stored uncompressed.
+        let data = if data.len() == expected {
+            Cow::Borrowed(data)
+        } else {
+            Cow::Owned(decompress(compression, data)?)
+        };
+
+        if data.len() != expected {
+            return Err(TextureError::new(&format!(
+                "EXR block for line {} has {} bytes, but should have {}",
+                first_line, data.len(), expected
+            )));
+        }
+
+        let mut data = Reader::new(&data, 0);
+
+        for line in first_line..first_line + lines {
+            for (channel, target) in channels.iter().zip(&targets) {
+                for x in 0..width {
+                    let value = match channel.pixel_type {
+                        PixelType::Uint => data.u32()? as f32,
+                        PixelType::Half => {
+                            let bytes = data.take(2)?;
+                            f32_from_f16_bits(u16::from_le_bytes([bytes[0], bytes[1]]))
+                        }
+                        PixelType::Float => f32::from_bits(data.u32()?),
+                    };
+
+                    for component in target.iter() {
+                        pixels[(line * width + x) * 4 + component] = value;
+                    }
+                }
+            }
+        }
+    }
+
+    FloatImage::from_raw(width as u32, height as u32, pixels)
+        .ok_or_else(|| TextureError::new("EXR image is too large"))
+}
+
+fn read_channels(reader: &mut Reader) -> Result<Vec<Channel>, TextureError> {
+    let mut channels = Vec::new();
+
+    loop {
+        let name = reader.string()?;
+
+        if name.is_empty() {
+            return Ok(channels);
+        }
+
+        let pixel_type = match reader.i32()? {
+            0 => PixelType::Uint,
+            1 => PixelType::Half,
+            2 => PixelType::Float,
+            other => return Err(TextureError::new(&format!("EXR channel {} has unknown type {}", name, other))),
+        };
+
+        // Skips the linear flag and reserved bytes.
+        reader.take(4)?;
+
+        if reader.i32()? != 1 || reader.i32()? != 1 {
+            return Err(TextureError::new(&format!("EXR channel {} is subsampled, which isn't supported", name)));
+        }
+
+        channels.push(Channel { name, pixel_type });
+    }
+}
+
+/// Undoes rle or zip compression, both of which store differences between
+/// bytes with the two halves of each value split apart.
+fn decompress(compression: Compression, data: &[u8]) -> Result<Vec<u8>, TextureError> {
+    let mut bytes = match compression {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Rle => decode_rle(data)?,
+        Compression::Zips | Compression::Zip => miniz_oxide::inflate::decompress_to_vec_zlib(data)
+            .map_err(|_| TextureError::new("EXR image has a corrupt zip block"))?,
+    };
+
+    for i in 1..bytes.len() {
+        bytes[i] = bytes[i - 1].wrapping_add(bytes[i]).wrapping_sub(128);
+    }
+
+    let half = (bytes.len() + 1) / 2;
+    let mut interleaved = Vec::with_capacity(bytes.len());
+
+    for i in 0..half {
+        interleaved.push(bytes[i]);
+
+        if let Some(byte) = bytes.get(half + i) {
+            interleaved.push(*byte);
+        }
+    }
+
+    Ok(interleaved)
+}
+
+fn decode_rle(data: &[u8]) -> Result<Vec<u8>, TextureError> {
+    let mut reader = Reader::new(data, 0);
+    let mut bytes = Vec::new();
+
+    while !reader.is_empty() {
+        let count = reader.u8()? as i8;
+
+        if count < 0 {
+            bytes.extend_from_slice(reader.take(-(count as i32) as usize)?);
+        } else {
+            let byte = reader.u8()?;
+            bytes.extend(std::iter::repeat(byte).take(count as usize + 1));
+        }
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,78 @@
+/// How the colors of an 8 bit image are encoded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorSpace {
+    /// Colors meant to be seen, like albedo, which the gpu converts to
+    /// linear when sampling.
+    Srgb,
+    /// Data that isn't a color, like normal, roughness or height maps.
+    Linear,
+}
+
+/// How precisely float images like `.hdr` and `.exr` files are stored.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FloatPrecision {
+    /// `Rgba16Float`, which is plenty for lighting and can be filtered.
+    Half,
+    /// `Rgba32Float`, for data that needs every bit. Not every adapter can
+    /// filter it.
+    Full,
+}
+
+impl FloatPrecision {
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        match self {
+            FloatPrecision::Half => wgpu::TextureFormat::Rgba16Float,
+            FloatPrecision::Full => wgpu::TextureFormat::Rgba32Float,
+        }
+    }
+}
+
+/// The size of one pixel of an uncompressed color format, or `None` for
+/// depth and block compressed formats.
+pub fn bytes_per_pixel(format: wgpu::TextureFormat) -> Option<u32> {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        R8Unorm | R8Snorm | R8Uint | R8Sint => Some(1),
+        R16Uint | R16Sint | R16Float | Rg8Unorm | Rg8Snorm | Rg8Uint | Rg8Sint => Some(2),
+        R32Uint | R32Sint | R32Float | Rg16Uint | Rg16Sint | Rg16Float | Rgba8Unorm | Rgba8UnormSrgb
+        | Rgba8Snorm | Rgba8Uint | Rgba8Sint | Bgra8Unorm | Bgra8UnormSrgb | Rgb10a2Unorm | Rg11b10Float => Some(4),
+        Rg32Uint | Rg32Sint | Rg32Float | Rgba16Uint | Rgba16Sint | Rgba16Float => Some(8),
+        Rgba32Uint | Rgba32Sint | Rgba32Float => Some(16),
+        _ => None,
+    }
+}
+
+/// Whether mip levels of the format can be made by rendering, which needs a
+/// format that can be both filtered and rendered to.
+pub(crate) fn can_render_mipmaps(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        R8Unorm | R16Float | Rg8Unorm | Rg16Float | Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb
+        | Rgb10a2Unorm | Rgba16Float => true,
+        _ => false,
+    }
+}
+
+/// The size of one 4x4 block of a block compressed format, or `None` for
+/// uncompressed formats.
+pub fn bytes_per_block(format: wgpu::TextureFormat) -> Option<u32> {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        Bc1RgbaUnorm | Bc1RgbaUnormSrgb | Bc4RUnorm | Bc4RSnorm => Some(8),
+        Bc2RgbaUnorm | Bc2RgbaUnormSrgb | Bc3RgbaUnorm | Bc3RgbaUnormSrgb | Bc5RgUnorm | Bc5RgSnorm
+        | Bc6hRgbUfloat | Bc6hRgbSfloat | Bc7RgbaUnorm | Bc7RgbaUnormSrgb => Some(16),
+        _ => None,
+    }
+}
+
+/// The number of bytes in one mip level of a format, rounding block
+/// compressed levels up to whole blocks.
+pub fn level_byte_size(format: wgpu::TextureFormat, width: u32, height: u32) -> Option<usize> {
+    match bytes_per_block(format) {
+        Some(block) => Some(((width + 3) / 4) as usize * ((height + 3) / 4) as usize * block as usize),
+        None => bytes_per_pixel(format).map(|pixel| width as usize * height as usize * pixel as usize),
+    }
+}
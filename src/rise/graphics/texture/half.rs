@@ -0,0 +1,61 @@
+/// Converts a float to the bits of a half float, rounding to the nearest
+/// value. Values too large for a half become infinity.
+pub(crate) fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    // Infinity and NaN, keeping NaN a NaN.
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exponent = exponent - 127 + 15;
+
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // Too small for a normal half, so it's stored as a subnormal.
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+
+    // A carry out of the mantissa while rounding correctly bumps the exponent.
+    let round = (mantissa >> 12) & 1;
+
+    sign | ((((exponent as u32) << 10) | (mantissa >> 13)) + round) as u16
+}
+
+/// Converts an srgb encoded channel in 0..1 to linear.
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts the bits of a half float to a float.
+pub(crate) fn f32_from_f16_bits(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1. } else { 1. };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0. => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1. + mantissa / 1024.) * 2f32.powi(exponent - 15),
+    }
+}
@@ -0,0 +1,27 @@
+use super::exr::{decode_exr, is_exr};
+
+/// A linear rgba image with a float per component.
+pub type FloatImage = image::ImageBuffer<image::Rgba<f32>, Vec<f32>>;
+
+/// Decodes Radiance `.hdr` and OpenEXR images, keeping their full range.
+/// Returns `None` for any other kind of image.
+pub(crate) fn decode_float_image(bytes: &[u8]) -> Result<Option<FloatImage>, Box<dyn std::error::Error>> {
+    if is_exr(bytes) {
+        return Ok(Some(decode_exr(bytes)?));
+    }
+
+    if let Ok(image::ImageFormat::Hdr) = image::guess_format(bytes) {
+        let decoder = image::codecs::hdr::HdrDecoder::new(std::io::Cursor::new(bytes))?;
+        let metadata = decoder.metadata();
+
+        let pixels = decoder
+            .read_image_hdr()?
+            .iter()
+            .flat_map(|pixel| vec![pixel[0], pixel[1], pixel[2], 1.])
+            .collect();
+
+        return Ok(FloatImage::from_raw(metadata.width, metadata.height, pixels));
+    }
+
+    Ok(None)
+}
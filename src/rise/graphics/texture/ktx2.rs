@@ -0,0 +1,112 @@
+use super::container::ContainerImage;
+use super::reader::Reader;
+use super::{level_byte_size, mip_level_count, mip_level_size, TextureError};
+
+const IDENTIFIER: [u8; 12] = [0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a];
+
+pub(crate) fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(&IDENTIFIER)
+}
+
+/// Reads a 2d KTX2 texture and its mip levels. Supercompressed files, like
+/// those holding Basis Universal data, aren't supported.
+pub(crate) fn parse_ktx2(bytes: &[u8]) -> Result<ContainerImage<'_>, TextureError> {
+    let mut reader = Reader::new(bytes, 0);
+
+    if reader.take(12)? != IDENTIFIER {
+        return Err(TextureError::new("Not a KTX2 file"));
+    }
+
+    let vk_format = reader.u32()?;
+    let _type_size = reader.u32()?;
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let depth = reader.u32()?;
+    let layer_count = reader.u32()?;
+    let face_count = reader.u32()?;
+    let level_count = reader.u32()?;
+    let supercompression = reader.u32()?;
+
+    if depth > 0 || layer_count > 1 || face_count != 1 {
+        return Err(TextureError::new("Only 2d KTX2 textures are supported"));
+    }
+
+    if supercompression != 0 {
+        return Err(TextureError::new(&format!(
+            "KTX2 supercompression scheme {} isn't supported",
+            supercompression
+        )));
+    }
+
+    let format = vk_texture_format(vk_format).ok_or_else(|| {
+        TextureError::new(&format!("KTX2 Vulkan format {} isn't supported", vk_format))
+    })?;
+
+    // The data format descriptor, key/values and supercompression data.
+    reader.skip(4 * 4 + 2 * 8)?;
+
+    // 0 asks the loader to generate the levels, which only leaves the first.
+    let level_count = level_count.max(1);
+
+    if level_count > mip_level_count(width, height) {
+        return Err(TextureError::new(&format!(
+            "KTX2 texture has {} mip levels, but a {}x{} texture can only have {}",
+            level_count, width, height, mip_level_count(width, height)
+        )));
+    }
+
+    let mut levels = Vec::with_capacity(level_count as usize);
+
+    for level in 0..level_count {
+        let offset = reader.u64()? as usize;
+        let length = reader.u64()? as usize;
+        let _uncompressed_length = reader.u64()?;
+
+        let (level_width, level_height) = mip_level_size(width, height, level);
+        let expected = level_byte_size(format, level_width, level_height).unwrap_or(0);
+
+        if length != expected {
+            return Err(TextureError::new(&format!(
+                "KTX2 mip level {} has {} bytes, but should have {}",
+                level, length, expected
+            )));
+        }
+
+        levels.push(Reader::new(bytes, offset).take(length)?);
+    }
+
+    Ok(ContainerImage {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+fn vk_texture_format(vk_format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+
+    match vk_format {
+        37 => Some(Rgba8Unorm),
+        43 => Some(Rgba8UnormSrgb),
+        44 => Some(Bgra8Unorm),
+        50 => Some(Bgra8UnormSrgb),
+        97 => Some(Rgba16Float),
+        109 => Some(Rgba32Float),
+        131 | 133 => Some(Bc1RgbaUnorm),
+        132 | 134 => Some(Bc1RgbaUnormSrgb),
+        135 => Some(Bc2RgbaUnorm),
+        136 => Some(Bc2RgbaUnormSrgb),
+        137 => Some(Bc3RgbaUnorm),
+        138 => Some(Bc3RgbaUnormSrgb),
+        139 => Some(Bc4RUnorm),
+        140 => Some(Bc4RSnorm),
+        141 => Some(Bc5RgUnorm),
+        142 => Some(Bc5RgSnorm),
+        143 => Some(Bc6hRgbUfloat),
+        144 => Some(Bc6hRgbSfloat),
+        145 => Some(Bc7RgbaUnorm),
+        146 => Some(Bc7RgbaUnormSrgb),
+        _ => None,
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::Path;
+
+use super::texture::write_layers;
+use super::{
+    bytes_per_pixel, can_render_mipmaps, generate_mipmaps_cpu, generate_mipmaps_gpu, mip_level_count,
+    MipmapGeneration, Texture, TextureBuilder, TextureError,
+};
+
+impl<'a> TextureBuilder<'a> {
+    /// Loads images of the same size into the layers of a 2d array texture,
+    /// in order, for things like terrain layers or sprite animations.
+    /// Shaders sample it as a `texture2DArray`, with `"dimension": "2d_array"`
+    /// on the material uniform.
+    pub fn load_array_files<P: AsRef<Path> + core::fmt::Debug>(self, paths: &[P]) -> Result<Texture, Box<dyn std::error::Error>> {
+        let mut layers = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            layers.push(image::open(path)?.to_rgba());
+        }
+
+        let (width, height) = match layers.first() {
+            Some(layer) => layer.dimensions(),
+            None => return Err(Box::new(TextureError::new("An array texture needs at least one layer"))),
+        };
+
+        for (layer, path) in layers.iter().zip(paths) {
+            if layer.dimensions() != (width, height) {
+                return Err(Box::new(TextureError::new(&format!(
+                    "Array layer {:?} is {}x{}, but every layer should be {}x{}",
+                    path, layer.width(), layer.height(), width, height
+                ))));
+            }
+        }
+
+        let levels = match self.mipmaps {
+            MipmapGeneration::None => 1,
+            _ => mip_level_count(width, height),
+        };
+
+        let mipmaps = self.mipmaps;
+        let render_context = self.render_context;
+        let format = self.get_rgba_format();
+        let layer_count = layers.len() as u32;
+
+        let texture = self.create_layered_texture(
+            (width, height, layer_count),
+            wgpu::TextureDimension::D2,
+            wgpu::TextureViewDimension::D2Array,
+            format,
+            levels,
+            mipmaps == MipmapGeneration::Gpu,
+        );
+
+        for (index, layer) in layers.iter().enumerate() {
+            write_layers(render_context, &texture, 0, index as u32, (width, height, 1), layer);
+
+            if mipmaps == MipmapGeneration::Cpu {
+                for (level, image) in generate_mipmaps_cpu(layer, levels).iter().enumerate() {
+                    write_layers(render_context, &texture, level as u32 + 1, index as u32, (image.width(), image.height(), 1), image);
+                }
+            }
+        }
+
+        if mipmaps == MipmapGeneration::Gpu {
+            generate_mipmaps_gpu(render_context, texture.get_texture(), format, levels, layer_count)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Creates a 2d array texture from layers of tightly packed pixels in
+    /// `format`. Mip levels are made on the gpu when the format allows it.
+    pub fn from_layer_pixels(
+        self,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        layers: &[&[u8]],
+    ) -> Result<Texture, Box<dyn std::error::Error>> {
+        if layers.is_empty() {
+            return Err(Box::new(TextureError::new("An array texture needs at least one layer")));
+        }
+
+        let expected = expected_bytes(width, height, 1, format)?;
+
+        for (index, layer) in layers.iter().enumerate() {
+            if layer.len() != expected {
+                return Err(Box::new(TextureError::new(&format!(
+                    "Layer {} of a {}x{} {:?} array texture needs {} bytes, but has {}",
+                    index, width, height, format, expected, layer.len()
+                ))));
+            }
+        }
+
+        let render_mipmaps = self.mipmaps != MipmapGeneration::None && can_render_mipmaps(format);
+
+        let levels = if render_mipmaps {
+            mip_level_count(width, height)
+        } else {
+            1
+        };
+
+        let render_context = self.render_context;
+        let layer_count = layers.len() as u32;
+
+        let texture = self.create_layered_texture(
+            (width, height, layer_count),
+            wgpu::TextureDimension::D2,
+            wgpu::TextureViewDimension::D2Array,
+            format,
+            levels,
+            render_mipmaps,
+        );
+
+        for (index, layer) in layers.iter().enumerate() {
+            write_layers(render_context, &texture, 0, index as u32, (width, height, 1), layer);
+        }
+
+        if render_mipmaps {
+            generate_mipmaps_gpu(render_context, texture.get_texture(), format, levels, layer_count)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Creates a 3d texture from tightly packed slices of pixels in
+    /// `format`, front to back. Shaders sample it as a `texture3D`, with
+    /// `"dimension": "3d"` on the material uniform. 3d textures have a single
+    /// mip level.
+    pub fn from_volume_pixels(
+        self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: wgpu::TextureFormat,
+        pixels: &[u8],
+    ) -> Result<Texture, Box<dyn std::error::Error>> {
+        let expected = expected_bytes(width, height, depth, format)?;
+
+        if pixels.len() != expected {
+            return Err(Box::new(TextureError::new(&format!(
+                "A {}x{}x{} {:?} texture needs {} bytes of pixels, but {} were given",
+                width, height, depth, format, expected, pixels.len()
+            ))));
+        }
+
+        let render_context = self.render_context;
+
+        let texture = self.create_layered_texture(
+            (width, height, depth),
+            wgpu::TextureDimension::D3,
+            wgpu::TextureViewDimension::D3,
+            format,
+            1,
+            false,
+        );
+
+        write_layers(render_context, &texture, 0, 0, (width, height, depth), pixels);
+
+        Ok(texture)
+    }
+
+    /// Loads a color lookup table stored as a strip of square slices side by
+    /// side, like the 256x16 image of a 16x16x16 table, into a 3d texture.
+    /// Red increases across each slice, green down it and blue from slice to
+    /// slice.
+    ///
+    /// Tables are sampled linearly and clamped to their edges unless a
+    /// sampler was given. Their entries are read as `ColorSpace::Linear`
+    /// data, the builder's default, so grading maps colors one to one;
+    /// `with_color_space` can still ask for srgb.
+    pub fn load_lut_file<P: AsRef<Path>>(mut self, path: P) -> Result<Texture, Box<dyn std::error::Error>> {
+        let strip = image::open(path.as_ref())?.to_rgba();
+        let size = strip.height();
+
+        if size == 0 || size.checked_mul(size) != Some(strip.width()) {
+            return Err(Box::new(TextureError::new(&format!(
+                "Lookup table {:?} is {}x{}, but should be {} slices of {}x{} side by side",
+                path.as_ref(), strip.width(), strip.height(), size, size, size
+            ))));
+        }
+
+        let mut pixels = Vec::with_capacity(strip.as_raw().len());
+
+        for slice in 0..size {
+            for y in 0..size {
+                for x in 0..size {
+                    pixels.extend_from_slice(&strip.get_pixel(slice * size + x, y).0);
+                }
+            }
+        }
+
+        if self.sampler.is_none() {
+            self = self
+                .with_address_mode(wgpu::AddressMode::ClampToEdge)
+                .with_mag_filter(wgpu::FilterMode::Linear)
+                .with_min_filter(wgpu::FilterMode::Linear);
+        }
+
+        let format = self.get_rgba_format();
+
+        self.from_volume_pixels(size, size, size, format, &pixels)
+    }
+
+    /// Creates an empty texture with several layers or depth slices.
+    fn create_layered_texture(
+        mut self,
+        (width, height, depth): (u32, u32, u32),
+        dimension: wgpu::TextureDimension,
+        view_dimension: wgpu::TextureViewDimension,
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+        render_mipmaps: bool,
+    ) -> Texture {
+        let mut usage = wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC;
+
+        if render_mipmaps {
+            usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        }
+
+        self.texture_desc = wgpu::TextureDescriptor {
+            // Array layers and 3d slices are both counted by depth.
+            size: wgpu::Extent3d { width, height, depth },
+            mip_level_count,
+            sample_count: 1,
+            dimension,
+            format,
+            usage,
+            label: Some("Layered Texture"),
+        };
+
+        self.view_dimension = view_dimension;
+
+        self.build()
+    }
+}
+
+/// The number of bytes of tightly packed pixels in a block of slices, which
+/// must have at least one pixel.
+fn expected_bytes(width: u32, height: u32, depth: u32, format: wgpu::TextureFormat) -> Result<usize, TextureError> {
+    if width == 0 || height == 0 || depth == 0 {
+        return Err(TextureError::new(&format!("A {}x{}x{} texture has no pixels", width, height, depth)));
+    }
+
+    let bytes_per_pixel = bytes_per_pixel(format).ok_or_else(|| {
+        TextureError::new(&format!("{:?} textures can't be made from raw pixels", format))
+    })?;
+
+    Ok(width as usize * height as usize * depth as usize * bytes_per_pixel as usize)
+}
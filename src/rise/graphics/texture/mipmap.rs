@@ -0,0 +1,218 @@
+use crate::graphics::RenderContext;
+
+/// How a loaded texture's smaller mip levels are made.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MipmapGeneration {
+    /// Only the full size image is kept.
+    None,
+    /// Each level is downsampled from the one above it with a linear filter
+    /// in a render pass, which is the fastest way for large textures.
+    Gpu,
+    /// Each level is resized on the cpu before upload, for formats the gpu
+    /// can't render to.
+    Cpu,
+}
+
+/// The number of levels in a full mip chain, down to 1x1.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// The size of a mip level, which never goes below 1.
+pub fn mip_level_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Fills every level after the first by rendering each one from the level
+/// above, for each of the texture's layers. The texture needs
+/// `OUTPUT_ATTACHMENT` and `SAMPLED` usage and a format that can be rendered
+/// to.
+pub(crate) fn generate_mipmaps_gpu(
+    render_context: &RenderContext,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    mip_level_count: u32,
+    layer_count: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if mip_level_count < 2 {
+        return Ok(());
+    }
+
+    let device = &render_context.device;
+
+    let (pipeline, bind_group_layout) = create_blit_pipeline(device, format)?;
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Mipmap Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mipmap Encoder"),
+    });
+
+    for layer in 0..layer_count {
+        let views: Vec<wgpu::TextureView> = (0..mip_level_count)
+            .map(|level| texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Mip Level"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_mip_level: level,
+                level_count: std::num::NonZeroU32::new(1),
+                base_array_layer: layer,
+                array_layer_count: std::num::NonZeroU32::new(1),
+                ..Default::default()
+            }))
+            .collect();
+
+        generate_layer_mipmaps(device, &mut encoder, &pipeline, &bind_group_layout, &sampler, &views);
+    }
+
+    render_context.queue.submit(std::iter::once(encoder.finish()));
+
+    Ok(())
+}
+
+/// Creates a pipeline that copies a 2d texture into a target of `format` by
+/// drawing one fullscreen triangle, with the texture and a sampler bound to
+/// set 0.
+pub(crate) fn create_blit_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> Result<(wgpu::RenderPipeline, wgpu::BindGroupLayout), Box<dyn std::error::Error>> {
+    let vs_spirv = std::fs::read("res/shader/blit.vert.spv")?;
+    let fs_spirv = std::fs::read("res/shader/blit.frag.spv")?;
+
+    let vs_module = device.create_shader_module(wgpu::util::make_spirv(&vs_spirv));
+    let fs_module = device.create_shader_module(wgpu::util::make_spirv(&fs_spirv));
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                    component_type: wgpu::TextureComponentType::Float,
+                },
+                count: None
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Sampler {
+                    comparison: false
+                },
+                count: None
+            }
+        ],
+        label: Some("Blit Layout"),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Blit Pipeline Layout"),
+        push_constant_ranges: &[],
+        bind_group_layouts: &[&bind_group_layout],
+    });
+
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Blit Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: &vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: &fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::None,
+            ..Default::default()
+        }),
+        color_states: &[wgpu::ColorStateDescriptor {
+            format,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        depth_stencil_state: None,
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_buffers: &[],
+        },
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    });
+
+    Ok((pipeline, bind_group_layout))
+}
+
+fn generate_layer_mipmaps(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    views: &[wgpu::TextureView],
+) {
+    for level in 1..views.len() {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&views[level - 1]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                }
+            ],
+            label: Some("Mipmap Bind Group"),
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &views[level],
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Makes every level after the first by resizing the one above it.
+pub(crate) fn generate_mipmaps_cpu<P>(
+    image: &image::ImageBuffer<P, Vec<P::Subpixel>>,
+    mip_level_count: u32,
+) -> Vec<image::ImageBuffer<P, Vec<P::Subpixel>>>
+where
+    P: image::Pixel + 'static,
+    P::Subpixel: 'static,
+{
+    let mut levels = Vec::with_capacity(mip_level_count as usize);
+
+    for level in 1..mip_level_count {
+        let (width, height) = mip_level_size(image.width(), image.height(), level);
+        let source = levels.last().unwrap_or(image);
+
+        levels.push(image::imageops::resize(source, width, height, image::imageops::FilterType::Triangle));
+    }
+
+    levels
+}
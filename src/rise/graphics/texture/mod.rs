@@ -0,0 +1,56 @@
+mod texture;
+
+pub use texture::{*};
+
+mod mipmap;
+
+pub use mipmap::{*};
+
+mod cubemap;
+
+pub use cubemap::{*};
+
+mod half;
+
+
+mod format;
+
+pub use format::{*};
+
+mod sampler;
+
+pub use sampler::{*};
+
+mod hdr;
+
+pub use hdr::{*};
+
+mod exr;
+
+mod reader;
+
+mod container;
+
+mod dds;
+
+mod ktx2;
+
+mod bc;
+
+mod atlas;
+
+pub use atlas::{*};
+
+mod sprite_sheet;
+
+pub use sprite_sheet::{*};
+
+mod readback;
+
+pub(crate) use readback::{*};
+
+mod layered;
+
+mod streaming;
+
+pub use streaming::{*};
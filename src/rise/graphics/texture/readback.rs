@@ -0,0 +1,127 @@
+use super::{bytes_per_pixel, mip_level_size, Texture, TextureError};
+use crate::graphics::RenderContext;
+
+/// The bytes per pixel of formats that can be copied into a buffer.
+pub(crate) fn readable_bytes_per_pixel(format: wgpu::TextureFormat) -> Result<u32, TextureError> {
+    match format {
+        wgpu::TextureFormat::Depth32Float => Ok(4),
+        _ => bytes_per_pixel(format)
+            .ok_or_else(|| TextureError::new(&format!("{:?} textures can't be read back", format))),
+    }
+}
+
+/// Rows copied out of a texture have to start on multiples of
+/// `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`.
+pub(crate) fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    (width * bytes_per_pixel + alignment - 1) / alignment * alignment
+}
+
+/// Creates a buffer that a `width` by `height` region can be copied into.
+pub(crate) fn create_readback_buffer(
+    render_context: &RenderContext,
+    width: u32,
+    height: u32,
+    bytes_per_pixel: u32,
+) -> wgpu::Buffer {
+    render_context.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: padded_bytes_per_row(width, bytes_per_pixel) as u64 * height as u64,
+        usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_READ,
+        mapped_at_creation: false,
+    })
+}
+
+/// Waits for a readback buffer to be filled and returns its rows without
+/// their padding.
+pub(crate) async fn read_buffer(
+    render_context: &RenderContext,
+    buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>, TextureError> {
+    let slice = buffer.slice(..);
+    let mapping = slice.map_async(wgpu::MapMode::Read);
+
+    // Native backends only finish mapping while the device is polled.
+    render_context.device.poll(wgpu::Maintain::Wait);
+
+    mapping
+        .await
+        .map_err(|_| TextureError::new("The gpu couldn't map the readback buffer"))?;
+
+    let row_size = (width * bytes_per_pixel) as usize;
+    let padded_row_size = padded_bytes_per_row(width, bytes_per_pixel) as usize;
+    let mut pixels = Vec::with_capacity(row_size * height as usize);
+
+    {
+        let data = slice.get_mapped_range();
+
+        for row in data.chunks(padded_row_size).take(height as usize) {
+            pixels.extend_from_slice(&row[..row_size]);
+        }
+    }
+
+    buffer.unmap();
+
+    Ok(pixels)
+}
+
+impl Texture {
+    /// Copies the first mip level back from the gpu as tightly packed rows
+    /// of pixels in the texture's format. See `read_level`.
+    pub async fn read_pixels(&self, render_context: &RenderContext) -> Result<Vec<u8>, TextureError> {
+        self.read_level(render_context, 0, 0).await
+    }
+
+    /// Copies one mip level of one layer back from the gpu as tightly
+    /// packed rows of pixels. The texture needs `COPY_SRC` usage, which
+    /// loaded textures have, and an uncompressed format.
+    ///
+    /// The copy is submitted right away, so anything rendered to the
+    /// texture needs to have been submitted first.
+    pub async fn read_level(&self, render_context: &RenderContext, level: u32, layer: u32) -> Result<Vec<u8>, TextureError> {
+        if level >= self.get_mip_level_count() || layer >= self.get_size().depth {
+            return Err(TextureError::new(&format!(
+                "Can't read level {} of layer {} from a texture with {} levels and {} layers",
+                level, layer, self.get_mip_level_count(), self.get_size().depth
+            )));
+        }
+
+        let bytes_per_pixel = readable_bytes_per_pixel(self.get_format())?;
+        let (width, height) = mip_level_size(self.get_size().width, self.get_size().height, level);
+
+        let buffer = create_readback_buffer(render_context, width, height, bytes_per_pixel);
+
+        let mut encoder = render_context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: self.get_texture(),
+                mip_level: level,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: layer },
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: padded_bytes_per_row(width, bytes_per_pixel),
+                    rows_per_image: height,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+        );
+
+        render_context.queue.submit(std::iter::once(encoder.finish()));
+
+        read_buffer(render_context, &buffer, width, height, bytes_per_pixel).await
+    }
+}
@@ -0,0 +1,64 @@
+use super::TextureError;
+
+/// Reads little endian values from a file in memory, failing instead of
+/// panicking when it runs out.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8], position: usize) -> Self {
+        Reader { bytes, position }
+    }
+
+    pub(crate) fn take(&mut self, count: usize) -> Result<&'a [u8], TextureError> {
+        let end = self.position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| TextureError::new("Image ended early"))?;
+
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(taken)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    pub(crate) fn skip(&mut self, count: usize) -> Result<(), TextureError> {
+        self.take(count).map(|_| ())
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, TextureError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, TextureError> {
+        let bytes = self.take(4)?;
+
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, TextureError> {
+        Ok(self.u32()? as i32)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, TextureError> {
+        Ok(self.u32()? as u64 | (self.u32()? as u64) << 32)
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, TextureError> {
+        let length = self.bytes[self.position.min(self.bytes.len())..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| TextureError::new("Image ended early"))?;
+
+        let string = String::from_utf8_lossy(self.take(length)?).into_owned();
+        self.take(1)?;
+
+        Ok(string)
+    }
+}
@@ -0,0 +1,134 @@
+use std::rc::Rc;
+
+use super::TextureFiltering;
+
+/// A sampler that can be shared by any number of textures. Cloning it
+/// shares the same gpu sampler.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    sampler: Rc<wgpu::Sampler>,
+}
+
+impl Sampler {
+    pub(crate) fn new(render_context: &crate::graphics::RenderContext, sampler_desc: &wgpu::SamplerDescriptor) -> Sampler {
+        Sampler {
+            sampler: Rc::new(render_context.device.create_sampler(sampler_desc)),
+        }
+    }
+
+    pub fn get_sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+}
+
+/// The sampler every color texture starts with: repeating, trilinear and
+/// without a comparison.
+pub(crate) fn default_sampler_desc<'a>() -> wgpu::SamplerDescriptor<'a> {
+    let mut sampler_desc = wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        address_mode_w: wgpu::AddressMode::Repeat,
+        ..Default::default()
+    };
+
+    TextureFiltering::Trilinear.apply(&mut sampler_desc);
+
+    sampler_desc
+}
+
+/// The most samples wgpu accepts for anisotropic filtering below or at
+/// `samples`, which is rounded down to a power of two. 1 or less turns it
+/// off.
+pub(crate) fn anisotropy_clamp(samples: u8) -> Option<std::num::NonZeroU8> {
+    if samples > 1 {
+        std::num::NonZeroU8::new(1 << (7 - samples.min(16).leading_zeros()))
+    } else {
+        None
+    }
+}
+
+/// Builds a sampler on its own, to share between textures. `TextureBuilder`
+/// keeps one of these for the sampler it makes, and its sampler settings
+/// are passed through to it.
+pub struct SamplerBuilder<'a> {
+    render_context: &'a crate::graphics::RenderContext,
+    pub(super) sampler_desc: wgpu::SamplerDescriptor<'a>,
+    /// Whether the address modes were chosen, rather than left at the
+    /// default, which cubemaps replace with clamping.
+    pub(super) address_modes_set: bool,
+}
+
+impl<'a> SamplerBuilder<'a> {
+    pub fn new(r: &'a crate::graphics::RenderContext) -> Self {
+        SamplerBuilder {
+            render_context: r,
+            sampler_desc: default_sampler_desc(),
+            address_modes_set: false,
+        }
+    }
+
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.sampler_desc.label = Some(label);
+        self
+    }
+
+    /// Sets how coordinates outside 0 to 1 are handled on every axis.
+    pub fn with_address_mode(self, mode: wgpu::AddressMode) -> Self {
+        self.with_address_modes(mode, mode, mode)
+    }
+
+    /// Sets how coordinates outside 0 to 1 are handled on each axis.
+    pub fn with_address_modes(mut self, u: wgpu::AddressMode, v: wgpu::AddressMode, w: wgpu::AddressMode) -> Self {
+        self.sampler_desc.address_mode_u = u;
+        self.sampler_desc.address_mode_v = v;
+        self.sampler_desc.address_mode_w = w;
+        self.address_modes_set = true;
+        self
+    }
+
+    /// Sets the min, mag and mip filters and anisotropy together.
+    pub fn with_filtering(mut self, filtering: TextureFiltering) -> Self {
+        filtering.apply(&mut self.sampler_desc);
+        self
+    }
+
+    pub fn with_mag_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler_desc.mag_filter = filter;
+        self
+    }
+
+    pub fn with_min_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler_desc.min_filter = filter;
+        self
+    }
+
+    pub fn with_mipmap_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler_desc.mipmap_filter = filter;
+        self
+    }
+
+    /// Limits which mip levels are sampled.
+    pub fn with_lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.sampler_desc.lod_min_clamp = min;
+        self.sampler_desc.lod_max_clamp = max;
+        self
+    }
+
+    /// Takes up to this many samples (2, 4, 8 or 16) along surfaces seen at
+    /// a glancing angle. Ignored when the adapter doesn't support it.
+    pub fn with_anisotropy(mut self, samples: u8) -> Self {
+        self.sampler_desc.anisotropy_clamp = anisotropy_clamp(samples);
+        self
+    }
+
+    /// Makes a comparison sampler, like those used for shadow maps. The
+    /// material uniform it's bound to needs `"comparison": true`.
+    pub fn with_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.sampler_desc.compare = Some(compare);
+        self
+    }
+
+    pub fn build(self) -> Sampler {
+        Sampler::new(self.render_context, &self.sampler_desc)
+    }
+}
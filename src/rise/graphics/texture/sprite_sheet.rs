@@ -0,0 +1,164 @@
+use std::convert::TryFrom;
+use std::path::Path;
+
+use super::streaming::region_fits;
+use super::{AtlasRegion, TextureAtlas, TextureBuilder, TextureError};
+
+/// The layout of a sprite sheet whose frames are all the same size, read
+/// left to right and top to bottom.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpriteGrid {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Space around the edge of the whole sheet.
+    pub margin: u32,
+    /// Space between neighbouring frames.
+    pub spacing: u32,
+    /// How many frames there are, for sheets whose last row isn't full.
+    pub frame_count: Option<u32>,
+}
+
+impl SpriteGrid {
+    pub fn new(frame_width: u32, frame_height: u32) -> SpriteGrid {
+        SpriteGrid {
+            frame_width,
+            frame_height,
+            margin: 0,
+            spacing: 0,
+            frame_count: None,
+        }
+    }
+
+    pub fn with_margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn with_frame_count(mut self, frame_count: u32) -> Self {
+        self.frame_count = Some(frame_count);
+        self
+    }
+
+    /// The frames of a sheet of the given size, named by their number.
+    pub fn get_regions(&self, width: u32, height: u32) -> Result<Vec<AtlasRegion>, TextureError> {
+        if self.frame_width == 0 || self.frame_height == 0 {
+            return Err(TextureError::new("Sprite grid frames must be at least 1x1"));
+        }
+
+        let fit = |size: u32, frame: u32| {
+            (size.saturating_sub(2 * self.margin) + self.spacing) / (frame + self.spacing)
+        };
+
+        let (columns, rows) = (fit(width, self.frame_width), fit(height, self.frame_height));
+        let frame_count = self.frame_count.unwrap_or(columns * rows);
+
+        if frame_count > columns * rows {
+            return Err(TextureError::new(&format!(
+                "A {}x{} sprite sheet only fits {} frames of {}x{}, but {} were asked for",
+                width, height, columns * rows, self.frame_width, self.frame_height, frame_count
+            )));
+        }
+
+        Ok((0..frame_count)
+            .map(|frame| {
+                let (column, row) = (frame % columns, frame / columns);
+
+                AtlasRegion::new(
+                    &frame.to_string(),
+                    self.margin + column * (self.frame_width + self.spacing),
+                    self.margin + row * (self.frame_height + self.spacing),
+                    self.frame_width,
+                    self.frame_height,
+                    (width, height),
+                )
+            })
+            .collect())
+    }
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Loads a sprite sheet laid out as a grid. Frames are named `"0"`,
+    /// `"1"` and so on, and can also be found by number with
+    /// `TextureAtlas::get_frame`.
+    pub fn load_sprite_grid<P: AsRef<Path>>(self, path: P, grid: &SpriteGrid) -> Result<TextureAtlas, Box<dyn std::error::Error>> {
+        let image = image::open(path)?.to_rgba();
+        let regions = grid.get_regions(image.width(), image.height())?;
+
+        let texture = self.create_rgba_texture(&image)?;
+
+        Ok(TextureAtlas::new(texture, regions))
+    }
+
+    /// Loads a sprite sheet described by JSON in the format most packing
+    /// tools export, with `frames` either as an object keyed by name or as
+    /// an array of objects with a `filename`, and `meta.image` naming the
+    /// image relative to the JSON file. Frames in an object are ordered by
+    /// name.
+    pub fn load_sprite_sheet<P: AsRef<Path>>(self, metadata_path: P) -> Result<TextureAtlas, Box<dyn std::error::Error>> {
+        let metadata_path = metadata_path.as_ref();
+        let metadata: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(metadata_path)?)?;
+
+        let error = |message: String| TextureError::new(&format!("Sprite sheet {:?}: {}", metadata_path, message));
+
+        let image_name = metadata["meta"]["image"]
+            .as_str()
+            .ok_or_else(|| error(String::from("meta.image should name the sheet's image")))?;
+
+        let image_path = metadata_path.parent().unwrap_or_else(|| Path::new("")).join(image_name);
+        let image = image::open(&image_path)?.to_rgba();
+        let size = image.dimensions();
+
+        let frames: Vec<(String, &serde_json::Value)> = match &metadata["frames"] {
+            serde_json::Value::Object(frames) => frames.iter().map(|(name, frame)| (name.clone(), frame)).collect(),
+            serde_json::Value::Array(frames) => frames
+                .iter()
+                .enumerate()
+                .map(|(index, frame)| {
+                    let name = frame["filename"]
+                        .as_str()
+                        .map(String::from)
+                        .unwrap_or_else(|| index.to_string());
+
+                    (name, frame)
+                })
+                .collect(),
+            _ => return Err(Box::new(error(String::from("frames should be an object or an array")))),
+        };
+
+        let mut regions = Vec::with_capacity(frames.len());
+
+        for (name, frame) in frames {
+            if frame["rotated"].as_bool() == Some(true) {
+                return Err(Box::new(error(format!("frame {} is rotated, which isn't supported", name))));
+            }
+
+            let field = |key: &str| {
+                let value = frame["frame"][key]
+                    .as_u64()
+                    .ok_or_else(|| error(format!("frame {} is missing frame.{}", name, key)))?;
+
+                u32::try_from(value).map_err(|_| error(format!("frame {} has a frame.{} of {}, which is too large", name, key, value)))
+            };
+
+            let (x, y, width, height) = (field("x")?, field("y")?, field("w")?, field("h")?);
+
+            if !region_fits(x, y, width, height, size.0, size.1) {
+                return Err(Box::new(error(format!(
+                    "frame {} goes past the edge of the {}x{} image",
+                    name, size.0, size.1
+                ))));
+            }
+
+            regions.push(AtlasRegion::new(&name, x, y, width, height, size));
+        }
+
+        let texture = self.create_rgba_texture(&image)?;
+
+        Ok(TextureAtlas::new(texture, regions))
+    }
+}
@@ -0,0 +1,372 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use super::texture::write_rows;
+use super::{bytes_per_pixel, mip_level_size, Sampler, Texture, TextureBuilder, TextureError};
+use crate::graphics::RenderContext;
+
+impl Texture {
+    /// Replaces a rectangle of the first mip level with tightly packed rows
+    /// of pixels in the texture's format. See `write_level_region`.
+    pub fn write_region(
+        &self,
+        render_context: &RenderContext,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        self.write_level_region(render_context, 0, 0, x, y, width, height, data)
+    }
+
+    /// Replaces a rectangle of one mip level of one layer. The texture needs
+    /// `COPY_DST` usage, which loaded textures have, and an uncompressed
+    /// format.
+    ///
+    /// Only the level written changes, so textures that are updated often
+    /// are best made without mipmaps. The write is queued behind everything
+    /// submitted so far and doesn't wait for the gpu.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_level_region(
+        &self,
+        render_context: &RenderContext,
+        level: u32,
+        layer: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), TextureError> {
+        if !self.get_usage().contains(wgpu::TextureUsage::COPY_DST) {
+            return Err(TextureError::new("The texture wasn't made with COPY_DST usage, so it can't be written to"));
+        }
+
+        if level >= self.get_mip_level_count() || layer >= self.get_size().depth {
+            return Err(TextureError::new(&format!(
+                "Can't write level {} of layer {} of a texture with {} levels and {} layers",
+                level, layer, self.get_mip_level_count(), self.get_size().depth
+            )));
+        }
+
+        let bytes_per_pixel = bytes_per_pixel(self.get_format()).ok_or_else(|| {
+            TextureError::new(&format!("{:?} textures can't be written a region at a time", self.get_format()))
+        })?;
+
+        let (level_width, level_height) = mip_level_size(self.get_size().width, self.get_size().height, level);
+
+        if !region_fits(x, y, width, height, level_width, level_height) {
+            return Err(TextureError::new(&format!(
+                "A {}x{} region at ({}, {}) goes past the edge of the {}x{} level",
+                width, height, x, y, level_width, level_height
+            )));
+        }
+
+        let expected = width as usize * height as usize * bytes_per_pixel as usize;
+
+        if data.len() != expected {
+            return Err(TextureError::new(&format!(
+                "A {}x{} {:?} region needs {} bytes of pixels, but {} were given",
+                width, height, self.get_format(), expected, data.len()
+            )));
+        }
+
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        write_rows(
+            render_context,
+            self,
+            level,
+            wgpu::Origin3d { x, y, z: layer },
+            (width, height, 1),
+            data,
+            width * bytes_per_pixel,
+        );
+
+        Ok(())
+    }
+}
+
+/// A rectangle of a streaming texture that a buffer hasn't been given yet.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct DirtyRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Whether a rectangle lies within an image, without overflowing for
+/// rectangles far outside it.
+pub(super) fn region_fits(x: u32, y: u32, width: u32, height: u32, image_width: u32, image_height: u32) -> bool {
+    matches!(x.checked_add(width), Some(right) if right <= image_width)
+        && matches!(y.checked_add(height), Some(bottom) if bottom <= image_height)
+}
+
+impl DirtyRegion {
+    /// The smallest region covering both. Regions come from `region_fits`
+    /// checked writes, so their edges can't overflow.
+    fn union(self, other: DirtyRegion) -> DirtyRegion {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        DirtyRegion {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
+
+/// A texture that's updated every frame, like a video, a paint canvas or a
+/// glyph cache, made of two or three textures used in turn. Writes go into
+/// a copy of the pixels kept on the cpu, and `present` uploads them to the
+/// next texture in the ring, so the texture frames in flight are still
+/// sampling is never written to.
+///
+/// Each texture only gets the part of the pixels that changed since it was
+/// last presented. Bind it with `MaterialInstance::use_streaming_texture`
+/// so materials always sample the latest one.
+pub struct StreamingTexture {
+    textures: Vec<Texture>,
+    current: Rc<Cell<usize>>,
+    pixels: RefCell<Vec<u8>>,
+    dirty: RefCell<Vec<Option<DirtyRegion>>>,
+    bytes_per_pixel: u32,
+}
+
+impl StreamingTexture {
+    /// The texture presented last.
+    pub fn get_texture(&self) -> &Texture {
+        &self.textures[self.current.get()]
+    }
+
+    pub fn get_textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    pub fn get_buffer_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        let size = self.textures[0].get_size();
+
+        (size.width, size.height)
+    }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        self.textures[0].get_format()
+    }
+
+    /// Which texture is presented, shared with material bindings.
+    pub(crate) fn get_current(&self) -> Rc<Cell<usize>> {
+        self.current.clone()
+    }
+
+    /// Replaces a rectangle of the pixels with tightly packed rows in the
+    /// texture's format. Nothing reaches the gpu until `present`.
+    pub fn write_region(&self, x: u32, y: u32, width: u32, height: u32, data: &[u8]) -> Result<(), TextureError> {
+        let (texture_width, texture_height) = self.get_size();
+
+        if !region_fits(x, y, width, height, texture_width, texture_height) {
+            return Err(TextureError::new(&format!(
+                "A {}x{} region at ({}, {}) goes past the edge of the {}x{} streaming texture",
+                width, height, x, y, texture_width, texture_height
+            )));
+        }
+
+        let bytes_per_pixel = self.bytes_per_pixel as usize;
+        let row_size = width as usize * bytes_per_pixel;
+
+        if data.len() != row_size * height as usize {
+            return Err(TextureError::new(&format!(
+                "A {}x{} {:?} region needs {} bytes of pixels, but {} were given",
+                width, height, self.get_format(), row_size * height as usize, data.len()
+            )));
+        }
+
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let stride = texture_width as usize * bytes_per_pixel;
+        let mut pixels = self.pixels.borrow_mut();
+
+        for (row, source) in data.chunks(row_size).enumerate() {
+            let start = (y as usize + row) * stride + x as usize * bytes_per_pixel;
+
+            pixels[start..start + row_size].copy_from_slice(source);
+        }
+
+        let region = DirtyRegion { x, y, width, height };
+
+        for dirty in self.dirty.borrow_mut().iter_mut() {
+            *dirty = Some(dirty.map_or(region, |dirty| dirty.union(region)));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all the pixels and presents them, for things like video
+    /// frames that change completely.
+    pub fn write_frame(&self, render_context: &RenderContext, data: &[u8]) -> Result<(), TextureError> {
+        let (width, height) = self.get_size();
+
+        self.write_region(0, 0, width, height, data)?;
+        self.present(render_context);
+
+        Ok(())
+    }
+
+    /// Moves on to the next texture in the ring, uploading everything
+    /// written since it was last presented, and makes it the one materials
+    /// sample.
+    pub fn present(&self, render_context: &RenderContext) {
+        let next = (self.current.get() + 1) % self.textures.len();
+
+        if let Some(region) = self.dirty.borrow_mut()[next].take() {
+            let bytes_per_pixel = self.bytes_per_pixel as usize;
+            let stride = self.get_size().0 as usize * bytes_per_pixel;
+            let start = region.y as usize * stride + region.x as usize * bytes_per_pixel;
+            let end = start + (region.height as usize - 1) * stride + region.width as usize * bytes_per_pixel;
+
+            write_rows(
+                render_context,
+                &self.textures[next],
+                0,
+                wgpu::Origin3d { x: region.x, y: region.y, z: 0 },
+                (region.width, region.height, 1),
+                &self.pixels.borrow()[start..end],
+                stride as u32,
+            );
+        }
+
+        self.current.set(next);
+    }
+}
+
+impl<'a> TextureBuilder<'a> {
+    /// Makes a streaming texture of `buffer_count` textures, 2 for double
+    /// buffering or 3 for triple buffering, of the builder's size and
+    /// format. Its pixels start out transparent black and it has no mip
+    /// levels.
+    pub fn build_streaming(self, buffer_count: usize) -> Result<StreamingTexture, TextureError> {
+        if !(1..=3).contains(&buffer_count) {
+            return Err(TextureError::new(&format!(
+                "Streaming textures use 1 to 3 buffers, but {} were asked for",
+                buffer_count
+            )));
+        }
+
+        let size = self.texture_desc.size;
+        let format = self.texture_desc.format;
+
+        if size.width == 0 || size.height == 0 {
+            return Err(TextureError::new("Streaming textures must be at least 1x1"));
+        }
+
+        let bytes_per_pixel = bytes_per_pixel(format).ok_or_else(|| {
+            TextureError::new(&format!("{:?} can't be used for a streaming texture", format))
+        })?;
+
+        let render_context = self.render_context;
+
+        let sampler = match &self.sampler {
+            Some(sampler) => sampler.clone(),
+            None => Sampler::new(render_context, &self.sampler_builder.sampler_desc),
+        };
+
+        let texture_desc = wgpu::TextureDescriptor {
+            size: wgpu::Extent3d { depth: 1, ..size },
+            mip_level_count: 1,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC,
+            label: self.texture_desc.label.or(Some("Streaming Texture")),
+            ..self.texture_desc
+        };
+
+        let pixel_bytes = (size.width as usize)
+            .checked_mul(size.height as usize)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel as usize))
+            .ok_or_else(|| TextureError::new("The streaming texture is too large"))?;
+
+        let pixels = vec![0; pixel_bytes];
+
+        let textures: Vec<Texture> = (0..buffer_count)
+            .map(|_| TextureBuilder {
+                texture_desc: texture_desc.clone(),
+                sampler: Some(sampler.clone()),
+                ..TextureBuilder::new(render_context)
+            }.build())
+            .collect();
+
+        // New textures aren't guaranteed to be cleared.
+        for texture in &textures {
+            write_rows(
+                render_context,
+                texture,
+                0,
+                wgpu::Origin3d::ZERO,
+                (size.width, size.height, 1),
+                &pixels,
+                size.width * bytes_per_pixel,
+            );
+        }
+
+        Ok(StreamingTexture {
+            textures,
+            current: Rc::new(Cell::new(0)),
+            pixels: RefCell::new(pixels),
+            dirty: RefCell::new(vec![None; buffer_count]),
+            bytes_per_pixel,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{region_fits, DirtyRegion};
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> DirtyRegion {
+        DirtyRegion { x, y, width, height }
+    }
+
+    #[test]
+    fn union_covers_both_regions() {
+        assert_eq!(region(0, 0, 2, 2).union(region(4, 6, 1, 3)), region(0, 0, 5, 9));
+        assert_eq!(region(4, 6, 1, 3).union(region(0, 0, 2, 2)), region(0, 0, 5, 9));
+        assert_eq!(region(1, 1, 4, 4).union(region(2, 0, 1, 8)), region(1, 0, 4, 8));
+    }
+
+    #[test]
+    fn union_with_a_contained_region_changes_nothing() {
+        assert_eq!(region(0, 0, 8, 8).union(region(2, 3, 1, 1)), region(0, 0, 8, 8));
+        assert_eq!(region(2, 3, 1, 1).union(region(2, 3, 1, 1)), region(2, 3, 1, 1));
+    }
+
+    #[test]
+    fn regions_inside_the_image_fit() {
+        assert!(region_fits(0, 0, 16, 8, 16, 8));
+        assert!(region_fits(15, 7, 1, 1, 16, 8));
+        assert!(region_fits(16, 8, 0, 0, 16, 8));
+    }
+
+    #[test]
+    fn regions_past_the_edge_dont_fit() {
+        assert!(!region_fits(15, 0, 2, 1, 16, 8));
+        assert!(!region_fits(0, 7, 1, 2, 16, 8));
+        assert!(!region_fits(17, 0, 0, 0, 16, 8));
+    }
+
+    #[test]
+    fn regions_that_would_overflow_dont_fit() {
+        assert!(!region_fits(u32::MAX, 0, 2, 1, 16, 8));
+        assert!(!region_fits(0, 1, 1, u32::MAX, 16, 8));
+        assert!(!region_fits(u32::MAX, u32::MAX, u32::MAX, u32::MAX, u32::MAX, u32::MAX));
+    }
+}
@@ -0,0 +1,691 @@
+use log::{warn};
+
+use std::fmt;
+
+use super::bc::decompress_bc;
+use super::container::{parse_container, ContainerImage};
+use super::half::f16_bits;
+use super::hdr::decode_float_image;
+use super::{
+    anisotropy_clamp, bytes_per_block, bytes_per_pixel, can_render_mipmaps, mip_level_count, mip_level_size,
+    ColorSpace, FloatImage, FloatPrecision, MipmapGeneration, Sampler, SamplerBuilder,
+};
+
+#[derive(Debug, Clone)]
+pub struct TextureError {
+    message: String
+}
+
+impl TextureError {
+    pub fn new(message: &str) -> TextureError {
+        TextureError {
+            message: String::from(message)
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error creating texture: {}", self.message)
+    }
+}
+
+/// How a texture is filtered when it's drawn larger or smaller than its
+/// size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFiltering {
+    /// Takes the closest texel, for pixel art.
+    Nearest,
+    /// Blends the closest texels within the closest mip level.
+    Bilinear,
+    /// Blends the closest texels and the two closest mip levels.
+    Trilinear,
+    /// Trilinear, taking up to this many extra samples (2, 4, 8 or 16) along
+    /// surfaces seen at a glancing angle. Falls back to trilinear when the
+    /// adapter doesn't support it.
+    Anisotropic(u8),
+}
+
+impl TextureFiltering {
+    pub fn apply(&self, sampler_desc: &mut wgpu::SamplerDescriptor) {
+        let (filter, mipmap_filter) = match self {
+            TextureFiltering::Nearest => (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest),
+            TextureFiltering::Bilinear => (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest),
+            TextureFiltering::Trilinear | TextureFiltering::Anisotropic(_) => (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear),
+        };
+
+        sampler_desc.mag_filter = filter;
+        sampler_desc.min_filter = filter;
+        sampler_desc.mipmap_filter = mipmap_filter;
+
+        sampler_desc.anisotropy_clamp = match self {
+            TextureFiltering::Anisotropic(samples) => anisotropy_clamp(*samples),
+            _ => None,
+        };
+    }
+}
+
+pub struct Texture {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    sampler: Sampler,
+    size: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+    mip_level_count: u32,
+    view_dimension: wgpu::TextureViewDimension,
+    usage: wgpu::TextureUsage,
+}
+
+impl Texture {
+    
+    pub fn get_view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn get_texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn get_sampler(&self) -> &wgpu::Sampler {
+        self.sampler.get_sampler()
+    }
+
+    /// The texture's sampler, for other textures to share with
+    /// `TextureBuilder::with_sampler`.
+    pub fn share_sampler(&self) -> Sampler {
+        self.sampler.clone()
+    }
+
+    pub fn get_size(&self) -> wgpu::Extent3d {
+        self.size
+    }
+
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn get_mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+    /// How shaders see the texture, which must match the dimension of the
+    /// material uniform it's bound to.
+    pub fn get_view_dimension(&self) -> wgpu::TextureViewDimension {
+        self.view_dimension
+    }
+
+    pub fn get_usage(&self) -> wgpu::TextureUsage {
+        self.usage
+    }
+}
+
+pub struct TextureBuilder<'a> {
+    pub(super) render_context: &'a crate::graphics::RenderContext,
+    pub(super) texture_desc: wgpu::TextureDescriptor<'a>,
+    pub(super) sampler_builder: SamplerBuilder<'a>,
+    pub(super) sampler: Option<Sampler>,
+    pub(super) mipmaps: MipmapGeneration,
+    pub(super) view_dimension: wgpu::TextureViewDimension,
+    pub(super) color_space: ColorSpace,
+    pub(super) float_precision: FloatPrecision,
+}
+
+impl<'a> TextureBuilder<'a> {
+    pub fn new(r: &'a crate::graphics::RenderContext) -> Self {
+        let size =  wgpu::Extent3d {
+            width: 0,
+            height: 0,
+            depth: 1
+        };
+        
+        let texture_desc = wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT // 3.
+                | wgpu::TextureUsage::SAMPLED 
+                | wgpu::TextureUsage::COPY_SRC,
+        };
+
+        TextureBuilder {
+            render_context: r,
+            texture_desc,
+            sampler_builder: SamplerBuilder::new(r),
+            sampler: None,
+            mipmaps: MipmapGeneration::Gpu,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            color_space: ColorSpace::Linear,
+            float_precision: FloatPrecision::Half,
+        }
+    }
+
+    pub fn make_depth_texture(mut self) -> Self {
+        let width = self.render_context.sc_desc.width;
+        let height = self.render_context.sc_desc.height;
+
+        self.sampler_builder.sampler_desc = wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: -100.0,
+            lod_max_clamp: 100.0,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        };
+
+        self.with_size(width, height)
+            .with_format(wgpu::TextureFormat::Depth32Float)
+            .with_usage(
+                wgpu::TextureUsage::SAMPLED
+                    | wgpu::TextureUsage::COPY_DST
+                    | wgpu::TextureUsage::COPY_SRC
+                    | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            )
+            .with_label("Depth Texture")
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.texture_desc.size.width = width;
+        self.texture_desc.size.height = height;
+        self
+    }
+
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.texture_desc.format = format;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: wgpu::TextureUsage) -> Self {
+        self.texture_desc.usage = usage;
+        self
+    }
+
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.texture_desc.label = Some(label);
+        self
+    }
+
+    /// Sets how loaded images get their mip levels. Defaults to
+    /// `MipmapGeneration::Gpu`.
+    pub fn with_mipmaps(mut self, mipmaps: MipmapGeneration) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Changes the settings of the sampler the texture will be made with.
+    fn map_sampler(mut self, f: impl FnOnce(SamplerBuilder<'a>) -> SamplerBuilder<'a>) -> Self {
+        self.sampler_builder = f(self.sampler_builder);
+        self
+    }
+
+    /// Sets the min, mag and mip filters and anisotropy together. Defaults
+    /// to `TextureFiltering::Trilinear`.
+    pub fn with_filtering(self, filtering: TextureFiltering) -> Self {
+        self.map_sampler(|sampler| sampler.with_filtering(filtering))
+    }
+
+    /// Sets how coordinates outside 0 to 1 are handled on every axis.
+    /// Defaults to `AddressMode::Repeat`, or `ClampToEdge` for cubemaps.
+    pub fn with_address_mode(self, mode: wgpu::AddressMode) -> Self {
+        self.map_sampler(|sampler| sampler.with_address_mode(mode))
+    }
+
+    /// Sets how coordinates outside 0 to 1 are handled on each axis.
+    pub fn with_address_modes(self, u: wgpu::AddressMode, v: wgpu::AddressMode, w: wgpu::AddressMode) -> Self {
+        self.map_sampler(|sampler| sampler.with_address_modes(u, v, w))
+    }
+
+    pub fn with_mag_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.map_sampler(|sampler| sampler.with_mag_filter(filter))
+    }
+
+    pub fn with_min_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.map_sampler(|sampler| sampler.with_min_filter(filter))
+    }
+
+    pub fn with_mipmap_filter(self, filter: wgpu::FilterMode) -> Self {
+        self.map_sampler(|sampler| sampler.with_mipmap_filter(filter))
+    }
+
+    /// Limits which mip levels are sampled.
+    pub fn with_lod_clamp(self, min: f32, max: f32) -> Self {
+        self.map_sampler(|sampler| sampler.with_lod_clamp(min, max))
+    }
+
+    /// Takes up to this many samples (2, 4, 8 or 16) along surfaces seen at
+    /// a glancing angle. Ignored when the adapter doesn't support it.
+    pub fn with_anisotropy(self, samples: u8) -> Self {
+        self.map_sampler(|sampler| sampler.with_anisotropy(samples))
+    }
+
+    /// Makes a comparison sampler, like those used for shadow maps. The
+    /// material uniform it's bound to needs `"comparison": true`.
+    pub fn with_compare(self, compare: wgpu::CompareFunction) -> Self {
+        self.map_sampler(|sampler| sampler.with_compare(compare))
+    }
+
+    /// Uses a sampler shared with other textures instead of making one,
+    /// ignoring the builder's sampler settings.
+    pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Sets whether 8 bit images hold colors or data. Defaults to
+    /// `ColorSpace::Linear`, which samples the bytes as they are and suits
+    /// normal maps and other data. Load colors like albedo with
+    /// `ColorSpace::Srgb` so they're lit and blended in linear space.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Sets how `.hdr` and `.exr` images are stored. Defaults to
+    /// `FloatPrecision::Half`.
+    pub fn with_float_precision(mut self, float_precision: FloatPrecision) -> Self {
+        self.float_precision = float_precision;
+        self
+    }
+
+    /// Loads an image and generates its mip levels. DDS and KTX2 files keep
+    /// the format and mip levels they were saved with.
+    pub fn load_file<P: AsRef<std::path::Path>>(self, path: P) -> Result<Texture, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+
+        self.from_bytes(&bytes)
+    }
+
+    /// Decodes an image already in memory, like one embedded with
+    /// `include_bytes!`. Radiance `.hdr` and OpenEXR images become float
+    /// textures, and everything else becomes 8 bit rgba in the builder's
+    /// color space.
+    ///
+    /// DDS and KTX2 files are uploaded as they are, including BC1 to BC7
+    /// block compressed ones. When the device can't sample those, they're
+    /// decompressed on the cpu instead, to 8 bit pixels or, for BC6H, half
+    /// floats. Compressed textures uploaded as they are stop their mip chain
+    /// at the last level that's a whole number of blocks, as levels smaller
+    /// than 4x4 can't be copied into them, so a 256x256 texture gets 4x4 as
+    /// its smallest level.
+    pub fn from_bytes(self, bytes: &[u8]) -> Result<Texture, Box<dyn std::error::Error>> {
+        if let Some(image) = parse_container(bytes)? {
+            return self.create_container_texture(&image);
+        }
+
+        match decode_float_image(bytes)? {
+            Some(image) => self.create_float_texture(&image),
+            None => self.create_rgba_texture(&image::load_from_memory(bytes)?.to_rgba()),
+        }
+    }
+
+    /// Creates a texture from tightly packed rows of pixels in `format`,
+    /// which must be an uncompressed color format. Mip levels are made on the
+    /// gpu when the format allows it, otherwise the texture has one level.
+    pub fn from_pixels(
+        self,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        pixels: &[u8],
+    ) -> Result<Texture, Box<dyn std::error::Error>> {
+        if width == 0 || height == 0 {
+            return Err(Box::new(TextureError::new(&format!("A {}x{} texture has no pixels", width, height))));
+        }
+
+        let bytes_per_pixel = bytes_per_pixel(format).ok_or_else(|| {
+            TextureError::new(&format!("{:?} textures can't be made from raw pixels", format))
+        })?;
+
+        let expected = width as usize * height as usize * bytes_per_pixel as usize;
+
+        if pixels.len() != expected {
+            return Err(Box::new(TextureError::new(&format!(
+                "A {}x{} {:?} texture needs {} bytes of pixels, but {} were given",
+                width, height, format, expected, pixels.len()
+            ))));
+        }
+
+        let render_mipmaps = self.mipmaps != MipmapGeneration::None && can_render_mipmaps(format);
+
+        let levels = if render_mipmaps {
+            mip_level_count(width, height)
+        } else {
+            1
+        };
+
+        let render_context = self.render_context;
+        let texture = self.create_image_texture((width, height), format, levels, render_mipmaps);
+
+        write_level(render_context, &texture, 0, (width, height), pixels);
+
+        if render_mipmaps {
+            super::generate_mipmaps_gpu(render_context, texture.get_texture(), format, levels, 1)?;
+        }
+
+        Ok(texture)
+    }
+
+    pub(super) fn create_rgba_texture(self, image: &image::RgbaImage) -> Result<Texture, Box<dyn std::error::Error>> {
+        let levels = match self.mipmaps {
+            MipmapGeneration::None => 1,
+            _ => mip_level_count(image.width(), image.height()),
+        };
+
+        let mipmaps = self.mipmaps;
+        let render_context = self.render_context;
+        let format = self.get_rgba_format();
+        let texture = self.create_image_texture(image.dimensions(), format, levels, mipmaps == MipmapGeneration::Gpu);
+
+        write_level(render_context, &texture, 0, image.dimensions(), image);
+
+        match mipmaps {
+            MipmapGeneration::None => {}
+            MipmapGeneration::Gpu => {
+                super::generate_mipmaps_gpu(render_context, texture.get_texture(), format, levels, 1)?;
+            }
+            MipmapGeneration::Cpu => {
+                for (level, image) in super::generate_mipmaps_cpu(image, levels).iter().enumerate() {
+                    write_level(render_context, &texture, level as u32 + 1, image.dimensions(), image);
+                }
+            }
+        }
+
+        Ok(texture)
+    }
+
+    /// Float textures that can't be rendered to get their mip levels on the
+    /// cpu instead.
+    fn create_float_texture(self, image: &FloatImage) -> Result<Texture, Box<dyn std::error::Error>> {
+        let format = self.float_precision.get_format();
+
+        let levels = match self.mipmaps {
+            MipmapGeneration::None => 1,
+            _ => mip_level_count(image.width(), image.height()),
+        };
+
+        let render_mipmaps = self.mipmaps == MipmapGeneration::Gpu && can_render_mipmaps(format);
+
+        let render_context = self.render_context;
+        let texture = self.create_image_texture(image.dimensions(), format, levels, render_mipmaps);
+
+        write_level(render_context, &texture, 0, image.dimensions(), &float_pixels(image, format));
+
+        if render_mipmaps {
+            super::generate_mipmaps_gpu(render_context, texture.get_texture(), format, levels, 1)?;
+        } else {
+            for (level, image) in super::generate_mipmaps_cpu(image, levels).iter().enumerate() {
+                write_level(render_context, &texture, level as u32 + 1, image.dimensions(), &float_pixels(image, format));
+            }
+        }
+
+        Ok(texture)
+    }
+
+    fn create_container_texture(self, image: &ContainerImage) -> Result<Texture, Box<dyn std::error::Error>> {
+        let compressed = bytes_per_block(image.format).is_some();
+        let supported = self.render_context.get_features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC);
+
+        if compressed && !supported {
+            let mut levels = Vec::with_capacity(image.levels.len());
+            let mut format = image.format;
+
+            for (level, data) in image.levels.iter().enumerate() {
+                let (width, height) = mip_level_size(image.width, image.height, level as u32);
+                let (output, pixels) = decompress_bc(image.format, width, height, data)?;
+
+                format = output;
+                levels.push(pixels);
+            }
+
+            let render_context = self.render_context;
+            let texture = self.create_image_texture((image.width, image.height), format, levels.len() as u32, false);
+
+            for (level, pixels) in levels.iter().enumerate() {
+                write_level(render_context, &texture, level as u32, mip_level_size(image.width, image.height, level as u32), pixels);
+            }
+
+            return Ok(texture);
+        }
+
+        // Copies into compressed textures have to cover whole blocks and fit
+        // inside the level, so only the levels that are a multiple of 4 in
+        // both directions can be uploaded.
+        let level_count = if compressed {
+            if image.width % 4 != 0 || image.height % 4 != 0 {
+                return Err(Box::new(TextureError::new(&format!(
+                    "Block compressed textures must be a multiple of 4 in size, but this one is {}x{}",
+                    image.width, image.height
+                ))));
+            }
+
+            (0..image.levels.len() as u32)
+                .take_while(|level| {
+                    let (width, height) = mip_level_size(image.width, image.height, *level);
+                    width % 4 == 0 && height % 4 == 0
+                })
+                .count()
+        } else {
+            image.levels.len()
+        };
+
+        if level_count < image.levels.len() {
+            let (width, height) = mip_level_size(image.width, image.height, level_count as u32 - 1);
+
+            warn!(
+                "Dropping {} of {} mip levels from a {}x{} {:?} texture, since levels below 4x4 can't be uploaded, so it stops at {}x{}",
+                image.levels.len() - level_count, image.levels.len(), image.width, image.height, image.format, width, height
+            );
+        }
+
+        let render_context = self.render_context;
+        let texture = self.create_image_texture((image.width, image.height), image.format, level_count as u32, false);
+
+        for (level, data) in image.levels.iter().take(level_count).enumerate() {
+            write_level(render_context, &texture, level as u32, mip_level_size(image.width, image.height, level as u32), data);
+        }
+
+        Ok(texture)
+    }
+
+    /// The format 8 bit images are stored in.
+    pub(super) fn get_rgba_format(&self) -> wgpu::TextureFormat {
+        match self.color_space {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// Loads a texture whose mip levels were made ahead of time, one image
+    /// per level starting with the full size one. Each level must be half
+    /// the size of the one before, and the chain can stop before 1x1.
+    pub fn load_mip_files<P: AsRef<std::path::Path> + core::fmt::Debug>(self, paths: &[P]) -> Result<Texture, Box<dyn std::error::Error>> {
+        let mut images = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            images.push(image::open(path)?.to_rgba());
+        }
+
+        let (width, height) = match images.first() {
+            Some(image) => image.dimensions(),
+            None => return Err(Box::new(TextureError::new("No mip levels were given"))),
+        };
+
+        if images.len() as u32 > mip_level_count(width, height) {
+            return Err(Box::new(TextureError::new(&format!(
+                "{} mip levels were given, but a {}x{} texture can only have {}",
+                images.len(), width, height, mip_level_count(width, height)
+            ))));
+        }
+
+        for (level, (image, path)) in images.iter().zip(paths).enumerate() {
+            let expected = mip_level_size(width, height, level as u32);
+
+            if image.dimensions() != expected {
+                return Err(Box::new(TextureError::new(&format!(
+                    "Mip level {} ({:?}) is {}x{}, but should be {}x{}",
+                    level, path, image.width(), image.height(), expected.0, expected.1
+                ))));
+            }
+        }
+
+        let render_context = self.render_context;
+        let format = self.get_rgba_format();
+        let texture = self.create_image_texture((width, height), format, images.len() as u32, false);
+
+        for (level, image) in images.iter().enumerate() {
+            write_level(render_context, &texture, level as u32, image.dimensions(), image);
+        }
+
+        Ok(texture)
+    }
+
+    /// Creates an empty texture for loaded images to be written to.
+    fn create_image_texture(
+        mut self,
+        dimensions: (u32, u32),
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+        render_mipmaps: bool,
+    ) -> Texture {
+        let mut usage = wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC;
+
+        // Generating mipmaps on the gpu renders into each level.
+        if render_mipmaps {
+            usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        }
+
+        self.texture_desc = wgpu::TextureDescriptor {
+            // All textures are stored as 3d, we represent our 2d texture
+            // by setting depth to 1.
+            size: wgpu::Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            // SAMPLED tells wgpu that we want to use this texture in shaders
+            // COPY_DST means that we want to copy data to this texture
+            usage,
+            label: Some("Image Texture"),
+        };
+
+        self.build()
+    }
+
+    pub fn build(self) -> Texture {
+        let texture = self.render_context.device.create_texture(&self.texture_desc);
+    
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(self.view_dimension),
+            ..Default::default()
+        });
+
+        let sampler = match self.sampler {
+            Some(sampler) => sampler,
+            None => self.sampler_builder.build(),
+        };
+
+        Texture {
+            view,
+            sampler,
+            texture,
+            size: self.texture_desc.size,
+            format: self.texture_desc.format,
+            mip_level_count: self.texture_desc.mip_level_count,
+            view_dimension: self.view_dimension,
+            usage: self.texture_desc.usage,
+        }
+    }
+}
+
+/// Copies tightly packed pixels, or rows of blocks for block compressed
+/// formats, into one mip level of a texture.
+fn write_level(
+    render_context: &crate::graphics::RenderContext,
+    texture: &Texture,
+    level: u32,
+    (width, height): (u32, u32),
+    pixels: &[u8],
+) {
+    write_layers(render_context, texture, level, 0, (width, height, 1), pixels);
+}
+
+/// Copies tightly packed pixels into one mip level of a run of layers, or
+/// of depth slices for 3d textures, one after another.
+pub(super) fn write_layers(
+    render_context: &crate::graphics::RenderContext,
+    texture: &Texture,
+    level: u32,
+    first_layer: u32,
+    (width, height, layers): (u32, u32, u32),
+    pixels: &[u8],
+) {
+    let bytes_per_row = match bytes_per_block(texture.get_format()) {
+        Some(bytes_per_block) => bytes_per_block * ((width + 3) / 4),
+        None => bytes_per_pixel(texture.get_format()).unwrap_or(4) * width,
+    };
+
+    write_rows(
+        render_context,
+        texture,
+        level,
+        wgpu::Origin3d { x: 0, y: 0, z: first_layer },
+        (width, height, layers),
+        pixels,
+        bytes_per_row,
+    );
+}
+
+/// Copies rows of pixels `bytes_per_row` apart into a box of a texture
+/// starting at `origin`.
+pub(super) fn write_rows(
+    render_context: &crate::graphics::RenderContext,
+    texture: &Texture,
+    level: u32,
+    origin: wgpu::Origin3d,
+    (width, height, layers): (u32, u32, u32),
+    pixels: &[u8],
+    bytes_per_row: u32,
+) {
+    render_context.queue.write_texture(
+        wgpu::TextureCopyView {
+            texture: texture.get_texture(),
+            mip_level: level,
+            origin,
+        },
+        pixels,
+        wgpu::TextureDataLayout {
+            offset: 0,
+            bytes_per_row,
+            rows_per_image: height,
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth: layers,
+        },
+    );
+}
+
+/// The bytes of a float image in a float texture format.
+pub(super) fn float_pixels(image: &FloatImage, format: wgpu::TextureFormat) -> Vec<u8> {
+    match format {
+        wgpu::TextureFormat::Rgba16Float => {
+            let halves: Vec<u16> = image.as_raw().iter().map(|value| f16_bits(*value)).collect();
+
+            bytemuck::cast_slice(&halves).to_vec()
+        }
+        _ => bytemuck::cast_slice(image.as_raw()).to_vec(),
+    }
+}
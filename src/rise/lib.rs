@@ -1,12 +1,13 @@
 use std::fmt;
 
+// Lets code generated by rise_derive refer to `rise::` from inside this crate.
+extern crate self as rise;
+
 #[derive(Debug, Clone)]
 pub struct RISEError;
 
-
-
-type Result<T> = std::result::Result<T, RISEError>;
-
 pub mod core;
 
-pub mod graphics;
\ No newline at end of file
+pub mod graphics;
+
+pub mod animation;
\ No newline at end of file
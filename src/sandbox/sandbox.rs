@@ -20,7 +20,8 @@ impl rise::core::Application for Game {
         let standard_material = render_context
             .create_material()
             .from_file("res/mat/standard.mat")
-            .build();
+            .build()
+            .expect("Error building material.");
         let standard_material = std::rc::Rc::new(standard_material);
 
         let camera_uniform = &standard_material.get_camera_uniform();
@@ -30,39 +31,9 @@ impl rise::core::Application for Game {
 
         camera.bind(render_context, camera_uniform);
 
-        let plane_mesh = Mesh::load_from_file("res/model/sphere.obj");
-
-        /*let mut plane_mesh = Mesh::new();
-
-        use rise::graphics::Vertex;
-        use cgmath::{Vector3, Vector2};
-
-        let vertices = vec!(
-            Vertex::new(
-                Vector3::new(-1., 1., 0.0),
-                Vector3::new(0., 0., -1.),
-                Vector2::new(0., 0.)
-            ),
-            Vertex::new(
-                Vector3::new(1., 1., 0.0),
-                Vector3::new(0., 0., -1.),
-                Vector2::new(1., 0.)
-            ),
-            Vertex::new(
-                Vector3::new(1., -1., 0.0),
-                Vector3::new(0., 0., -1.),
-                Vector2::new(1., 1.)
-            ),
-            Vertex::new(
-                Vector3::new(-1., -1., 0.0),
-                Vector3::new(0., 0., -1.),
-                Vector2::new(0., 1.)
-            )
-        );
+        let plane_mesh = Mesh::load_from_file("res/model/sphere.obj").expect("Error loading mesh.");
 
-        
-        plane_mesh.set_indices(vec![2 as u16, 1 as u16, 0 as u16, 0 as u16, 3 as u16, 2 as u16]);
-        plane_mesh.set_vertices(vertices);*/
+        //let plane_mesh = Mesh::plane(2.);
 
         // plane_mesh.create(render_context);
 
@@ -80,7 +51,8 @@ impl rise::core::Application for Game {
             .create_drawable()
             .with_mesh(plane_mesh)
             .with_material(material)
-            .build();
+            .build()
+            .expect("Error building drawable.");
 
         
         let input = rise::core::InputManager::new();